@@ -0,0 +1,252 @@
+//! Headless companion to the GTK app, for scripts and CI. Never touches a display.
+//!
+//! ```text
+//! mindhunt-cli generate <difficulty> [--seed N] [--generator N] [--out FILE]
+//! mindhunt-cli solve <FILE>
+//! mindhunt-cli verify <FILE>
+//! mindhunt-cli batch <difficulty> <COUNT> [--start-seed N]
+//! ```
+
+use std::path::Path;
+use std::process::ExitCode;
+use std::sync::atomic::AtomicBool;
+use std::time::{Duration, Instant};
+
+use mindhunt::model::puzzle_file;
+use mindhunt::model::{
+    is_supported_generator, Clue, Difficulty, GameStateSnapshot, ShareCode, GENERATOR_VERSION,
+};
+use mindhunt::solver::candidate_solver::{find_evaluation_step, EvaluationStepResult};
+
+const USAGE: &str = "\
+Usage:
+  mindhunt-cli generate <difficulty> [--seed N] [--generator N] [--out FILE]
+      Generate a puzzle and print its clues; optionally save it as a .mindhunt file.
+      --generator regenerates a puzzle as an older generator version made it
+  mindhunt-cli solve <FILE>
+      Solve a .mindhunt puzzle step by step, printing every deduction
+  mindhunt-cli verify <FILE>
+      Check that a .mindhunt puzzle has exactly one solution
+  mindhunt-cli batch <difficulty> <COUNT> [--start-seed N]
+      Generate COUNT consecutive seeds and report timing
+
+Difficulties: tutorial, easy, moderate, hard, veteran";
+
+/// Exit status for bad arguments, as opposed to a puzzle failing to solve or verify
+const USAGE_ERROR: u8 = 2;
+
+fn parse_difficulty(value: &str) -> Result<Difficulty, String> {
+    Difficulty::all()
+        .into_iter()
+        .find(|d| d.to_string().eq_ignore_ascii_case(value))
+        .ok_or_else(|| format!("Unknown difficulty '{}'", value))
+}
+
+fn parse_number(name: &str, value: Option<&String>) -> Result<u64, String> {
+    let value = value.ok_or_else(|| format!("{} needs a value", name))?;
+    value
+        .parse::<u64>()
+        .map_err(|_| format!("Invalid {} '{}'", name, value))
+}
+
+/// Splits `args` into positional arguments and `--flag value` options
+fn parse_args<'a>(
+    args: &'a [String],
+    flags: &[&str],
+) -> Result<(Vec<&'a String>, Vec<(&'a str, Option<&'a String>)>), String> {
+    let mut positional = vec![];
+    let mut options = vec![];
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        if arg.starts_with("--") {
+            if !flags.contains(&arg.as_str()) {
+                return Err(format!("Unknown option '{}'", arg));
+            }
+            options.push((arg.as_str(), iter.next()));
+        } else {
+            positional.push(arg);
+        }
+    }
+    Ok((positional, options))
+}
+
+fn load_puzzle(path: &str) -> Result<GameStateSnapshot, String> {
+    puzzle_file::import_puzzle(Path::new(path)).map_err(|err| format!("{}: {}", path, err))
+}
+
+fn print_clues(snapshot: &GameStateSnapshot) {
+    for clue in snapshot.board.clue_set.all_clues() {
+        println!("  {}", clue.clue.to_string());
+    }
+}
+
+fn generate(args: &[String]) -> Result<ExitCode, String> {
+    let (positional, options) = parse_args(args, &["--seed", "--generator", "--out"])?;
+    let [difficulty] = positional.as_slice() else {
+        return Err("generate takes a difficulty".to_string());
+    };
+    let difficulty = parse_difficulty(difficulty)?;
+    let mut seed = None;
+    let mut generator_version = GENERATOR_VERSION;
+    let mut out = None;
+    for (flag, value) in options {
+        match flag {
+            "--seed" => seed = Some(parse_number("seed", value)?),
+            "--generator" => {
+                let version = parse_number("generator", value)?;
+                generator_version = u32::try_from(version)
+                    .ok()
+                    .filter(|v| is_supported_generator(*v))
+                    .ok_or_else(|| format!("Unsupported generator version {}", version))?;
+            }
+            _ => out = Some(value.ok_or("--out needs a file name")?),
+        }
+    }
+
+    let snapshot = GameStateSnapshot::generate_new_cancellable(
+        difficulty,
+        seed,
+        generator_version,
+        &AtomicBool::new(false),
+        &|_| {},
+    )
+    .expect("generation is never cancelled");
+    let solution = &snapshot.board.solution;
+    let share_code = ShareCode {
+        difficulty,
+        seed: solution.seed,
+        generator_version,
+    };
+    println!("Difficulty: {}", difficulty.to_string());
+    println!("Seed: {}", solution.seed);
+    println!("Generator: {}", generator_version);
+    println!("Share code: {}", share_code.to_string());
+    println!("Rating: {}", snapshot.difficulty_rating().score);
+    println!("Clues ({}):", snapshot.board.clue_set.all_clues().count());
+    print_clues(&snapshot);
+
+    if let Some(out) = out {
+        puzzle_file::export_puzzle(&snapshot.board, Path::new(out))
+            .map_err(|err| format!("{}: {}", out, err))?;
+        println!("Saved to {}", out);
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+fn solve(args: &[String]) -> Result<ExitCode, String> {
+    let [path] = args else {
+        return Err("solve takes a puzzle file".to_string());
+    };
+    let snapshot = load_puzzle(path)?;
+    let mut board = snapshot.board.clone();
+    let clues: Vec<Clue> = board.clue_set.all_clues().map(|c| c.clue.clone()).collect();
+
+    let mut steps = 0;
+    while !board.is_complete() {
+        let (result, deductions) = find_evaluation_step(&board, &clues);
+        let source = match result {
+            EvaluationStepResult::Nothing => break,
+            EvaluationStepResult::DeductionsFound(clue) => format!("clue {}", clue.to_string()),
+            EvaluationStepResult::NakedSetsFound => "naked sets".to_string(),
+            EvaluationStepResult::HiddenSetsFound => "hidden sets".to_string(),
+        };
+        steps += 1;
+        println!("Step {}: {}", steps, source);
+        for deduction in deductions.iter() {
+            println!("  {:?}", deduction);
+        }
+        board.apply_deductions(&deductions);
+        for (col, tile) in board.auto_solve_all().1 {
+            println!("  {} is col {} (auto-solved)", tile, col);
+        }
+    }
+
+    if board.is_complete() && !board.is_incorrect() {
+        println!("Solved in {} steps", steps);
+        Ok(ExitCode::SUCCESS)
+    } else {
+        println!("Stuck after {} steps", steps);
+        Ok(ExitCode::FAILURE)
+    }
+}
+
+fn verify(args: &[String]) -> Result<ExitCode, String> {
+    let [path] = args else {
+        return Err("verify takes a puzzle file".to_string());
+    };
+    if load_puzzle(path)?.has_unique_solution() {
+        println!("{}: unique solution", path);
+        Ok(ExitCode::SUCCESS)
+    } else {
+        println!("{}: no unique solution", path);
+        Ok(ExitCode::FAILURE)
+    }
+}
+
+fn batch(args: &[String]) -> Result<ExitCode, String> {
+    let (positional, options) = parse_args(args, &["--start-seed"])?;
+    let [difficulty, count] = positional.as_slice() else {
+        return Err("batch takes a difficulty and a count".to_string());
+    };
+    let difficulty = parse_difficulty(difficulty)?;
+    let count = parse_number("count", Some(count))?;
+    let mut start_seed = 0;
+    for (_, value) in options {
+        start_seed = parse_number("start seed", value)?;
+    }
+
+    let mut timings: Vec<Duration> = vec![];
+    println!("seed\tclues\trating\tms");
+    for seed in start_seed..start_seed.saturating_add(count) {
+        let started = Instant::now();
+        let snapshot = GameStateSnapshot::generate_new(difficulty, Some(seed));
+        let elapsed = started.elapsed();
+        timings.push(elapsed);
+        println!(
+            "{}\t{}\t{}\t{}",
+            seed,
+            snapshot.board.clue_set.all_clues().count(),
+            snapshot.difficulty_rating().score,
+            elapsed.as_millis()
+        );
+    }
+
+    if let (Some(min), Some(max)) = (timings.iter().min(), timings.iter().max()) {
+        let total: Duration = timings.iter().sum();
+        println!(
+            "Generated {} {} puzzles in {:.2}s (mean {}ms, min {}ms, max {}ms)",
+            timings.len(),
+            difficulty.to_string(),
+            total.as_secs_f64(),
+            (total / timings.len() as u32).as_millis(),
+            min.as_millis(),
+            max.as_millis()
+        );
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+fn main() -> ExitCode {
+    env_logger::init();
+
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let Some((command, rest)) = args.split_first() else {
+        eprintln!("{}", USAGE);
+        return ExitCode::from(USAGE_ERROR);
+    };
+    let result = match command.as_str() {
+        "generate" => generate(rest),
+        "solve" => solve(rest),
+        "verify" => verify(rest),
+        "batch" => batch(rest),
+        "help" | "--help" | "-h" => {
+            println!("{}", USAGE);
+            Ok(ExitCode::SUCCESS)
+        }
+        _ => Err(format!("Unknown command '{}'", command)),
+    };
+    result.unwrap_or_else(|err| {
+        eprintln!("{}\n\n{}", err, USAGE);
+        ExitCode::from(USAGE_ERROR)
+    })
+}
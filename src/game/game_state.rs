@@ -1,35 +1,57 @@
+use chrono::NaiveDate;
+use glib::{timeout_add_local, ControlFlow, SourceId};
 use log::{error, trace};
 use std::cell::RefCell;
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, TryRecvError};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use uuid::Uuid;
 
 use super::settings::Settings;
 use crate::destroyable::Destroyable;
-use crate::events::{EventEmitter, EventObserver, Unsubscriber};
+use crate::events::{Channel, EventEmitter, EventObserver, Unsubscriber};
+use crate::model::daily_puzzle::daily_seed;
 use crate::model::game_state_snapshot::GameStateSnapshot;
 use crate::model::{
     CandidateState, ClueAddress, ClueSelection, ClueSet, ClueWithAddress, Deduction, Difficulty,
-    GameActionEvent, GameBoard, GameStateEvent, GameStats, GlobalEvent, PuzzleCompletionState,
-    Solution, TimerState,
+    DifficultyRating, GameActionEvent, GameBoard, GameStateEvent, GameStats, GenerationProgress,
+    GlobalEvent, MoveLog, NakedSet, PuzzleCompletionState, PuzzleSource, RecordedAction, Solution,
+    TimerState, GENERATOR_VERSION,
 };
 use crate::solver::candidate_solver::{
-    deduce_hidden_sets, perform_evaluation_step, EvaluationStepResult,
+    deduce_hidden_sets, find_naked_set_in_row, perform_evaluation_step, EvaluationStepResult,
 };
 use crate::solver::{deduce_clue, simplify_deductions, ConstraintSolver};
-use std::rc::Rc;
+use std::rc::{Rc, Weak};
+use std::sync::Arc;
 
 const HINT_LEVEL_MAX: u8 = 1;
 
+/// How often the main loop checks on a puzzle being generated in the background
+const GENERATION_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+enum GenerationMessage {
+    Progress(GenerationProgress),
+    Done(Box<GameStateSnapshot>),
+}
+
+/// A puzzle being generated on a worker thread
+struct GenerationJob {
+    cancelled: Arc<AtomicBool>,
+    poller: SourceId,
+}
+
 struct DeductionResult {
     deductions: Vec<Deduction>,
     clue: Option<ClueWithAddress>,
+    naked_set: Option<NakedSet>,
 }
 impl std::fmt::Debug for DeductionResult {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
             f,
-            "DeductionResult: {{deductions: {:?}, clue: {:?}}}",
-            self.deductions, self.clue
+            "DeductionResult: {{deductions: {:?}, clue: {:?}, naked_set: {:?}}}",
+            self.deductions, self.clue, self.naked_set
         )
     }
 }
@@ -50,13 +72,14 @@ impl Default for HintStatus {
 }
 
 pub struct GameState {
-    clue_set: Rc<ClueSet>,
+    clue_set: Arc<ClueSet>,
     history: Vec<Rc<GameBoard>>,
     pub current_board: Rc<GameBoard>,
-    solution: Rc<Solution>,
+    solution: Arc<Solution>,
     debug_mode: bool,
     history_index: usize,
     hints_used: u32,
+    difficulty_rating: DifficultyRating,
     hint_status: HintStatus,
     current_playthrough_id: Uuid,
     is_paused: bool,
@@ -68,10 +91,20 @@ pub struct GameState {
     current_selected_clue: Option<ClueWithAddress>,
     clue_focused: bool,
     current_clue_hint: Option<ClueWithAddress>,
+    generation: Option<GenerationJob>,
+    /// Set while playing the daily puzzle of that day
+    daily_date: Option<NaiveDate>,
+    source: PuzzleSource,
+    /// The name of the generator's puzzle variant, when known
+    puzzle_variant: Option<String>,
+    mistakes: u32,
+    move_log: MoveLog,
+    self_ref: Weak<RefCell<Self>>,
 }
 
 impl Destroyable for GameState {
     fn destroy(&mut self) {
+        self.cancel_generation();
         if let Some(subscription_id) = self.subscription_id.take() {
             subscription_id.unsubscribe();
         }
@@ -97,6 +130,7 @@ impl GameState {
             debug_mode: Settings::is_debug_mode(),
             history_index: 0,
             hints_used: 0,
+            difficulty_rating: DifficultyRating::default(),
             hint_status: HintStatus::default(),
             current_playthrough_id: Uuid::new_v4(),
             is_paused: false,
@@ -108,8 +142,16 @@ impl GameState {
             current_selected_clue: None,
             clue_focused: false,
             current_clue_hint: None,
+            generation: None,
+            daily_date: None,
+            source: PuzzleSource::default(),
+            puzzle_variant: None,
+            mistakes: 0,
+            move_log: MoveLog::new(empty_board.as_ref().clone()),
+            self_ref: Weak::new(),
         };
         let refcell = Rc::new(RefCell::new(game_state));
+        refcell.borrow_mut().self_ref = Rc::downgrade(&refcell);
         GameState::wire_subscription(refcell.clone(), game_action_observer);
         GameState::wire_global_subscription(refcell.clone(), global_event_observer);
         refcell
@@ -139,20 +181,129 @@ impl GameState {
         game_state.borrow_mut().global_subscription_id = Some(subscription_id);
     }
 
+    /// Generates a new puzzle on a worker thread, replacing any generation already running. The
+    /// current game stays in place until the new puzzle is ready.
+    fn start_generation(
+        &mut self,
+        difficulty: Difficulty,
+        seed: Option<u64>,
+        generator_version: u32,
+        daily_date: Option<NaiveDate>,
+        source: PuzzleSource,
+    ) {
+        self.cancel_generation();
+
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let (sender, receiver) = mpsc::channel();
+        {
+            let cancelled = Arc::clone(&cancelled);
+            std::thread::spawn(move || {
+                let progress_sender = sender.clone();
+                let snapshot = GameStateSnapshot::generate_new_cancellable(
+                    difficulty,
+                    seed,
+                    generator_version,
+                    &cancelled,
+                    &|progress| {
+                        // the receiver is gone if generation was cancelled; nothing to report to
+                        let _ = progress_sender.send(GenerationMessage::Progress(progress));
+                    },
+                );
+                if let Some(mut snapshot) = snapshot {
+                    snapshot.daily_date = daily_date;
+                    snapshot.source = source;
+                    let _ = sender.send(GenerationMessage::Done(Box::new(snapshot)));
+                }
+            });
+        }
+
+        let game_state = self.self_ref.clone();
+        let poller = timeout_add_local(GENERATION_POLL_INTERVAL, move || {
+            let Some(game_state) = game_state.upgrade() else {
+                return ControlFlow::Break;
+            };
+            loop {
+                match receiver.try_recv() {
+                    Ok(GenerationMessage::Progress(progress)) => {
+                        game_state
+                            .borrow()
+                            .game_state_emitter
+                            .emit(GameStateEvent::GenerationProgress(progress));
+                    }
+                    Ok(GenerationMessage::Done(snapshot)) => {
+                        game_state.borrow_mut().finish_generation(Some(&*snapshot));
+                        return ControlFlow::Break;
+                    }
+                    Err(TryRecvError::Empty) => return ControlFlow::Continue,
+                    Err(TryRecvError::Disconnected) => {
+                        error!(
+                            target: "game_state",
+                            "Puzzle generation for {:?} stopped without producing a puzzle",
+                            difficulty
+                        );
+                        game_state.borrow_mut().finish_generation(None);
+                        return ControlFlow::Break;
+                    }
+                }
+            }
+        });
+
+        self.generation = Some(GenerationJob { cancelled, poller });
+        self.game_state_emitter
+            .emit(GameStateEvent::GenerationProgress(GenerationProgress::new(
+                difficulty,
+            )));
+    }
+
+    /// Called from the generation poller once it's done; the poller removes itself.
+    fn finish_generation(&mut self, snapshot: Option<&GameStateSnapshot>) {
+        self.generation = None;
+        self.game_state_emitter
+            .emit(GameStateEvent::GenerationFinished);
+        if let Some(snapshot) = snapshot {
+            self.set_game_state(snapshot);
+        }
+    }
+
+    fn cancel_generation(&mut self) {
+        if let Some(job) = self.generation.take() {
+            job.cancelled.store(true, Ordering::Relaxed);
+            job.poller.remove();
+            self.game_state_emitter
+                .emit(GameStateEvent::GenerationFinished);
+        }
+    }
+
     fn set_game_state(&mut self, game_state_snapshot: &GameStateSnapshot) {
         println!(
             "New game; difficulty: {:?}; seed: {:?}",
             game_state_snapshot.board.solution.difficulty, game_state_snapshot.board.solution.seed
         );
         self.current_board = Rc::new(game_state_snapshot.board.clone());
-        self.clue_set = Rc::clone(&self.current_board.clue_set);
-        self.solution = Rc::clone(&self.current_board.solution);
+        self.clue_set = Arc::clone(&self.current_board.clue_set);
+        self.solution = Arc::clone(&self.current_board.solution);
         self.debug_mode = Settings::is_debug_mode();
         self.history.clear();
         self.history.push(self.current_board.clone());
         self.history_index = 0;
         self.hints_used = game_state_snapshot.hints_used;
+        self.difficulty_rating = game_state_snapshot.difficulty_rating();
         self.current_playthrough_id = Uuid::new_v4();
+        self.daily_date = game_state_snapshot.daily_date;
+        self.source = game_state_snapshot.source;
+        self.puzzle_variant = game_state_snapshot.puzzle_variant.clone();
+        self.mistakes = game_state_snapshot.mistakes;
+        self.move_log = match &game_state_snapshot.move_log {
+            Some(move_log) => {
+                let mut move_log = move_log.clone();
+                move_log.record(
+                    RecordedAction::Resumed,
+                    game_state_snapshot.timer_state.elapsed(),
+                );
+                move_log
+            }
+            None => MoveLog::new(game_state_snapshot.board.clone()),
+        };
         self.is_paused = false;
         self.timer_state = game_state_snapshot.timer_state.resumed();
         self.current_selected_clue = None;
@@ -161,6 +312,10 @@ impl GameState {
         self.sync_board_display();
         self.game_state_emitter
             .emit(GameStateEvent::HintUsageChanged(self.hints_used));
+        self.game_state_emitter
+            .emit(GameStateEvent::DifficultyRatingChanged(
+                self.difficulty_rating.clone(),
+            ));
         self.game_state_emitter
             .emit(GameStateEvent::TimerStateChanged(self.timer_state.clone()));
         self.game_state_emitter.emit(GameStateEvent::ClueSetUpdate(
@@ -266,8 +421,77 @@ impl GameState {
         }
     }
 
+    /// Actions that change the board in play
+    fn is_move(event: &GameActionEvent) -> bool {
+        matches!(
+            event,
+            GameActionEvent::CellSelect(..)
+                | GameActionEvent::CellClear(..)
+                | GameActionEvent::ClueToggleComplete(_)
+                | GameActionEvent::ClueToggleSelectedComplete
+                | GameActionEvent::Solve
+                | GameActionEvent::RewindLastGood
+                | GameActionEvent::ShowHint
+                | GameActionEvent::Undo
+                | GameActionEvent::Redo
+                | GameActionEvent::CompletePuzzle
+        )
+    }
+
     pub fn handle_event(&mut self, event: GameActionEvent) {
         log::trace!(target: "game_state", "Handling event: {:?}", event);
+        if self.generation.is_some() && Self::is_move(&event) {
+            // the board is hidden while the next puzzle is generated
+            return;
+        }
+
+        let action = match &event {
+            GameActionEvent::ClueToggleSelectedComplete => self
+                .current_selected_clue
+                .as_ref()
+                .map(|clue| RecordedAction::ClueToggleComplete(clue.address())),
+            event => RecordedAction::from_event(event),
+        };
+        let board_before = Rc::clone(&self.current_board);
+        let hints_before = self.hints_used;
+
+        self.dispatch_event(event);
+
+        // only moves that did something are worth replaying
+        if let Some(action) = action {
+            let board_changed = !Rc::ptr_eq(&board_before, &self.current_board);
+            if board_changed || hints_before != self.hints_used {
+                if board_changed && Self::is_mistake(&action, &board_before) {
+                    self.mistakes += 1;
+                }
+                self.move_log.record(action, self.timer_state.elapsed());
+            }
+        }
+    }
+
+    /// A tile placed that isn't the solution, or the solution eliminated as a candidate. Every
+    /// such move counts, even on a board that is already incorrect.
+    fn is_mistake(action: &RecordedAction, before: &GameBoard) -> bool {
+        let (RecordedAction::CellSelect(row, col, Some(variant))
+        | RecordedAction::CellClear(row, col, Some(variant))) = action
+        else {
+            return false;
+        };
+        // clearing a placed tile or restoring an eliminated candidate can't be wrong
+        let is_available = before
+            .get_candidate(*row, *col, *variant)
+            .is_some_and(|candidate| candidate.state == CandidateState::Available);
+        if before.has_selection(*row, *col) || !is_available {
+            return false;
+        }
+        let is_solution = before.solution.get(*row, *col).variant == *variant;
+        match action {
+            RecordedAction::CellSelect(..) => !is_solution,
+            _ => is_solution,
+        }
+    }
+
+    fn dispatch_event(&mut self, event: GameActionEvent) {
         match event {
             GameActionEvent::CellSelect(row, col, variant) => {
                 self.handle_cell_select(row, col, variant)
@@ -276,10 +500,33 @@ impl GameState {
                 self.handle_cell_clear(row, col, variant)
             }
             GameActionEvent::NewGame(difficulty, seed) => {
-                self.set_game_state(&GameStateSnapshot::generate_new(difficulty, seed));
+                let source = match seed {
+                    Some(_) => PuzzleSource::Seeded,
+                    None => PuzzleSource::Standard,
+                };
+                self.start_generation(difficulty, seed, GENERATOR_VERSION, None, source);
+            }
+            GameActionEvent::RegenerateGame(difficulty, seed, generator_version) => {
+                self.start_generation(
+                    difficulty,
+                    Some(seed),
+                    generator_version,
+                    None,
+                    PuzzleSource::Seeded,
+                );
+            }
+            GameActionEvent::NewDailyGame(difficulty, date) => {
+                self.start_generation(
+                    difficulty,
+                    Some(daily_seed(date, difficulty)),
+                    GENERATOR_VERSION,
+                    Some(date),
+                    PuzzleSource::Daily,
+                );
             }
             GameActionEvent::LoadState(save_state) => {
                 trace!(target: "game_state", "Loading saved state {:?}", save_state);
+                self.cancel_generation();
                 self.set_game_state(&save_state);
             }
             GameActionEvent::InitDisplay => {
@@ -299,13 +546,19 @@ impl GameState {
             GameActionEvent::Submit => todo!(),
             GameActionEvent::CompletePuzzle => self.complete_puzzle(),
             GameActionEvent::Restart => {
-                // Start a new game with current difficulty and seed
-                let current_seed = self.current_board.solution.seed;
-                let current_difficulty = self.current_board.solution.difficulty;
-                self.set_game_state(&GameStateSnapshot::generate_new(
-                    current_difficulty,
-                    Some(current_seed),
-                ));
+                // Replay the puzzle as it was dealt rather than regenerating it, so imported
+                // puzzles and those from unknown generators restart too. Saves from before move
+                // logs were kept start their log where they were resumed.
+                let mut snapshot = GameStateSnapshot::new(
+                    self.move_log.initial_board.clone(),
+                    TimerState::default(),
+                    0,
+                    Some(self.difficulty_rating.clone()),
+                );
+                snapshot.daily_date = self.daily_date;
+                snapshot.source = self.source;
+                snapshot.puzzle_variant = self.puzzle_variant.clone();
+                self.set_game_state(&snapshot);
             }
             GameActionEvent::ClueToggleComplete(clue_address) => {
                 self.handle_clue_toggle_complete(clue_address)
@@ -423,6 +676,11 @@ impl GameState {
                 );
                 return;
             }
+            EvaluationStepResult::NakedSetsFound => {
+                log::info!("Naked sets found");
+                self.game_state_emitter
+                    .emit(GameStateEvent::ClueSelected(None));
+            }
             EvaluationStepResult::HiddenSetsFound => {
                 log::info!("Hidden pairs found");
                 self.game_state_emitter
@@ -460,6 +718,7 @@ impl GameState {
                         &clue_grouping.clue,
                     ),
                     clue: Some(clue_grouping.clone()),
+                    naked_set: None,
                 });
             }
         }
@@ -475,6 +734,18 @@ impl GameState {
                         &clue_grouping.clue,
                     ),
                     clue: Some(clue_grouping.clone()),
+                    naked_set: None,
+                });
+            }
+        }
+
+        // look for naked sets
+        for row in 0..self.current_board.solution.n_rows {
+            if let Some((naked_set, deductions)) = find_naked_set_in_row(&self.current_board, row) {
+                return Some(DeductionResult {
+                    deductions,
+                    clue: None,
+                    naked_set: Some(naked_set),
                 });
             }
         }
@@ -485,6 +756,7 @@ impl GameState {
             return Some(DeductionResult {
                 deductions: hidden_pairs,
                 clue: None,
+                naked_set: None,
             });
         }
         // Nothing found! Oof.
@@ -522,7 +794,12 @@ impl GameState {
             self.current_board.solution.seed
         );
 
-        if let Some(DeductionResult { deductions, clue }) = deduction_result {
+        if let Some(DeductionResult {
+            deductions,
+            clue,
+            naked_set,
+        }) = deduction_result
+        {
             if let Some(addressed_clue) = &clue {
                 self.game_state_emitter
                     .emit(GameStateEvent::ClueSelected(Some(ClueSelection {
@@ -549,6 +826,12 @@ impl GameState {
                     )));
             }
 
+            if let Some(naked_set) = naked_set {
+                // show the whole set so the player can see why the other cells are eliminated
+                self.game_state_emitter
+                    .emit(GameStateEvent::NakedSetHintHighlight(naked_set));
+            }
+
             if self.hint_status.hint_level > 0 || clue.is_none() {
                 if let Some(first_deduction) = deductions.first() {
                     // highlight cells
@@ -593,6 +876,15 @@ impl GameState {
                 .unwrap()
                 .as_secs() as i64,
             playthrough_id: self.current_playthrough_id,
+            difficulty_rating: Some(self.difficulty_rating.score),
+            daily_date: self.daily_date,
+            move_log: Some(self.move_log.clone()),
+            seed: Some(self.current_board.solution.seed),
+            generator_version: Some(self.current_board.solution.generator_version),
+            source: Some(self.source),
+            puzzle_variant: self.puzzle_variant.clone(),
+            mistakes: Some(self.mistakes),
+            undos: Some(self.move_log.undo_count()),
         };
         stats
     }
@@ -678,11 +970,64 @@ impl GameState {
         }
     }
 
+    /// The puzzle as it was dealt, with only its revealed tiles placed, even after resuming a
+    /// saved game part way through
+    pub fn get_puzzle_board(&self) -> GameBoard {
+        self.move_log.initial_board.clone()
+    }
+
     pub fn get_game_save_state(&self) -> GameStateSnapshot {
-        GameStateSnapshot::new(
+        let mut snapshot = GameStateSnapshot::new(
             self.current_board.as_ref().clone(),
             self.timer_state.paused(SystemTime::now()),
             self.hints_used,
-        )
+            Some(self.difficulty_rating.clone()),
+        );
+        snapshot.daily_date = self.daily_date;
+        snapshot.source = self.source;
+        snapshot.puzzle_variant = self.puzzle_variant.clone();
+        snapshot.mistakes = self.mistakes;
+        snapshot.move_log = Some(self.move_log.clone());
+        snapshot
+    }
+
+    /// A game state with no UI attached, starting on `board`. Used to replay move logs with the
+    /// same rules as live play; call `destroy` when done with it.
+    pub(crate) fn headless(board: GameBoard) -> Rc<RefCell<Self>> {
+        let (_, game_action_observer) = Channel::<GameActionEvent>::new();
+        let (game_state_emitter, _) = Channel::<GameStateEvent>::new();
+        let (_, global_event_observer) = Channel::<GlobalEvent>::new();
+        let game_state = Self::new(
+            game_action_observer,
+            game_state_emitter,
+            global_event_observer,
+            Settings::default(),
+        );
+        game_state
+            .borrow_mut()
+            .set_game_state(&GameStateSnapshot::new(
+                board,
+                TimerState::default(),
+                0,
+                Some(DifficultyRating::default()),
+            ));
+        game_state
+    }
+
+    /// Applies a move from a move log
+    pub(crate) fn apply_recorded_action(&mut self, action: &RecordedAction) {
+        match action.to_event() {
+            Some(event) => self.dispatch_event(event),
+            None => {
+                // resuming a save starts a fresh undo history, as in set_game_state
+                self.history = vec![Rc::clone(&self.current_board)];
+                self.history_index = 0;
+                self.hint_status = HintStatus::default();
+            }
+        }
+    }
+
+    pub fn get_hints_used(&self) -> u32 {
+        self.hints_used
     }
 }
@@ -0,0 +1,113 @@
+//! An earlier solve of the puzzle being played, replayed against the game clock so the player
+//! can race their own best.
+
+use std::time::Duration;
+
+use crate::game::replay::build_replay;
+use crate::model::GameStats;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Ghost {
+    completion_time: Duration,
+    /// How far along the earlier solve was after each move, oldest first
+    progress: Vec<(Duration, u8)>,
+}
+
+impl Ghost {
+    /// The ghost of a recorded solve; `None` if it was recorded without its moves
+    pub fn from_stats(stats: &GameStats) -> Option<Self> {
+        let move_log = stats.move_log.as_ref()?;
+        let progress = build_replay(move_log)
+            .iter()
+            .map(|frame| (frame.elapsed(), frame.board.completion_percent()))
+            .collect();
+        Some(Self {
+            completion_time: stats.completion_time,
+            progress,
+        })
+    }
+
+    pub fn completion_time(&self) -> Duration {
+        self.completion_time
+    }
+
+    /// How much of the puzzle the earlier solve had worked out `elapsed` into the game
+    pub fn progress_at(&self, elapsed: Duration) -> u8 {
+        if elapsed >= self.completion_time {
+            return 100;
+        }
+        let moves_made = self.progress.partition_point(|(at, _)| *at <= elapsed);
+        moves_made
+            .checked_sub(1)
+            .map(|i| self.progress[i].1)
+            .unwrap_or(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::destroyable::Destroyable;
+    use crate::game::game_state::GameState;
+    use crate::model::{Difficulty, GameActionEvent, GameStateSnapshot, MoveLog, RecordedAction};
+    use uuid::Uuid;
+
+    fn solve(completion_time: Duration, move_log: Option<MoveLog>) -> GameStats {
+        GameStats {
+            completion_time,
+            hints_used: 0,
+            grid_size: 4,
+            difficulty: Difficulty::Tutorial,
+            timestamp: 0,
+            playthrough_id: Uuid::nil(),
+            difficulty_rating: None,
+            daily_date: None,
+            move_log,
+            seed: Some(1),
+            generator_version: None,
+            source: None,
+            puzzle_variant: None,
+            mistakes: None,
+            undos: None,
+        }
+    }
+
+    #[test]
+    fn test_progress_follows_the_clock() {
+        let board = GameStateSnapshot::generate_new(Difficulty::Tutorial, Some(1)).board;
+        let game_state = GameState::headless(board.clone());
+        for row in 0..board.solution.n_rows {
+            for col in 0..board.solution.n_variants {
+                let tile = board.solution.get(row, col);
+                game_state
+                    .borrow_mut()
+                    .handle_event(GameActionEvent::CellSelect(row, col, Some(tile.variant)));
+            }
+        }
+        let mut move_log = game_state.borrow().get_game_save_state().move_log.unwrap();
+        game_state.borrow_mut().destroy();
+
+        // one move every ten seconds
+        let moves: Vec<_> = move_log
+            .moves
+            .iter()
+            .filter(|m| matches!(m.action, RecordedAction::CellSelect(..)))
+            .cloned()
+            .collect();
+        move_log.moves = moves;
+        for (i, recorded_move) in move_log.moves.iter_mut().enumerate() {
+            recorded_move.elapsed = Duration::from_secs(10 * (i as u64 + 1));
+        }
+        let completion_time = move_log.moves.last().unwrap().elapsed;
+        let ghost = Ghost::from_stats(&solve(completion_time, Some(move_log))).unwrap();
+        let starting = board.completion_percent();
+        assert_eq!(ghost.progress_at(Duration::ZERO), starting);
+        assert_eq!(ghost.progress_at(Duration::from_secs(9)), starting);
+        assert!(ghost.progress_at(Duration::from_secs(10)) > starting);
+        let halfway = ghost.progress_at(completion_time / 2);
+        assert!(halfway <= ghost.progress_at(completion_time - Duration::from_secs(1)));
+        assert_eq!(ghost.progress_at(completion_time), 100);
+
+        assert!(Ghost::from_stats(&solve(completion_time, None)).is_none());
+    }
+}
@@ -1,13 +1,19 @@
 pub mod game_state;
+pub mod ghost;
+pub mod profiles;
+pub mod race;
+pub mod replay;
+pub mod save_slots;
 pub mod settings;
 pub mod stats_manager;
+pub mod stats_report;
 
 #[cfg(test)]
 pub mod tests {
-    use crate::model::{Difficulty, Solution, Tile, MAX_GRID_SIZE};
-    use std::rc::Rc;
+    use crate::model::{Difficulty, Solution, Tile, GENERATOR_VERSION, MAX_GRID_SIZE};
+    use std::sync::Arc;
 
-    pub fn create_test_solution(n_rows: usize, n_variants: usize) -> Rc<Solution> {
+    pub fn create_test_solution(n_rows: usize, n_variants: usize) -> Arc<Solution> {
         let mut grid = [['0'; MAX_GRID_SIZE]; MAX_GRID_SIZE];
         // Fill first 4x4 of grid with test data
         for row in 0..3 {
@@ -21,7 +27,7 @@ pub mod tests {
 
         let variants_range = start_variant..=end_variant;
 
-        Rc::new(Solution {
+        Arc::new(Solution {
             variants: variants_range.clone().collect(),
             grid,
             n_rows,
@@ -29,6 +35,7 @@ pub mod tests {
             variants_range,
             difficulty: Difficulty::Easy,
             seed: 0,
+            generator_version: GENERATOR_VERSION,
         })
     }
 }
@@ -0,0 +1,330 @@
+//! Named player profiles. Each profile keeps its settings, saved games and stats in its own
+//! directory, `profiles/<id>`, so people sharing a computer don't overwrite each other's
+//! progress. `profiles.json` lists the profiles and which one is in use.
+
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+
+use crate::model::save_file::{self, LoadOutcome, SaveFileError};
+
+/// The profile that data from before there were profiles is moved into
+pub const DEFAULT_PROFILE_NAME: &str = "Player";
+
+/// Files that lived directly in the data directory before there were profiles, matched by
+/// name prefix so their backups move along with them
+const LEGACY_FILE_PREFIXES: [&str; 7] = [
+    "settings.json",
+    "game_state.json",
+    "scores_",
+    "global_stats_",
+    "daily_results.json",
+    "history.json",
+    "saves",
+];
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Profile {
+    pub id: Uuid,
+    pub name: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ProfileList {
+    profiles: Vec<Profile>,
+    current: Uuid,
+}
+
+#[derive(Debug)]
+pub enum ProfileError {
+    Io(io::Error),
+    EmptyName,
+    DuplicateName(String),
+    /// The profile in use can't be deleted; the game would go on writing to it
+    InUse,
+    NotFound(Uuid),
+}
+
+impl Display for ProfileError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ProfileError::Io(err) => write!(f, "{}", err),
+            ProfileError::EmptyName => write!(f, "Please enter a name"),
+            ProfileError::DuplicateName(name) => {
+                write!(f, "There is already a profile called \"{}\"", name)
+            }
+            ProfileError::InUse => write!(f, "The profile in use can't be deleted"),
+            ProfileError::NotFound(id) => write!(f, "No profile with id {}", id),
+        }
+    }
+}
+
+impl From<io::Error> for ProfileError {
+    fn from(err: io::Error) -> Self {
+        ProfileError::Io(err)
+    }
+}
+
+#[derive(Debug)]
+pub struct ProfileManager {
+    data_dir: PathBuf,
+    profiles: Vec<Profile>,
+    current: Uuid,
+}
+
+impl ProfileManager {
+    pub fn load() -> Self {
+        Self::with_data_dir(glib::user_data_dir().join("mindhunt"))
+    }
+
+    pub(crate) fn with_data_dir(data_dir: PathBuf) -> Self {
+        let list_path = data_dir.join("profiles.json");
+        let parse = |contents: &str| -> Result<ProfileList, SaveFileError> {
+            Ok(serde_json::from_str(contents)?)
+        };
+        let list = match save_file::load(&list_path, &parse) {
+            LoadOutcome::Loaded(list) | LoadOutcome::Restored(list, _) => Some(list),
+            LoadOutcome::Unreadable(err) => {
+                log::error!(target: "profiles", "Profile list could not be read: {}", err);
+                None
+            }
+            LoadOutcome::TooNew(version) => {
+                log::error!(target: "profiles", "Profile list is from a newer version (format {})", version);
+                None
+            }
+            LoadOutcome::Missing => None,
+        };
+
+        let mut manager = Self {
+            data_dir,
+            profiles: vec![],
+            current: Uuid::nil(),
+        };
+        match list {
+            Some(list) => {
+                manager.profiles = list.profiles;
+                manager.current = list.current;
+            }
+            // the profile directories are still there, only their names are lost
+            None => manager.profiles = manager.recover_profiles(),
+        }
+
+        if manager.profiles.is_empty() {
+            manager.create_default_profile();
+        }
+        if manager.find(manager.current).is_none() {
+            manager.current = manager.profiles[0].id;
+        }
+        if let Err(e) = manager.save() {
+            log::error!(target: "profiles", "Failed to save profile list: {}", e);
+        }
+        manager
+    }
+
+    fn profiles_dir(&self) -> PathBuf {
+        self.data_dir.join("profiles")
+    }
+
+    fn save(&self) -> io::Result<()> {
+        let list = ProfileList {
+            profiles: self.profiles.clone(),
+            current: self.current,
+        };
+        let contents = serde_json::to_string(&list)?;
+        save_file::write_atomic(&self.data_dir.join("profiles.json"), &contents)
+    }
+
+    fn recover_profiles(&self) -> Vec<Profile> {
+        let Ok(entries) = fs::read_dir(self.profiles_dir()) else {
+            return vec![];
+        };
+        let mut ids: Vec<Uuid> = entries
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| Uuid::parse_str(&entry.file_name().to_string_lossy()).ok())
+            .collect();
+        ids.sort();
+        ids.into_iter()
+            .enumerate()
+            .map(|(i, id)| Profile {
+                id,
+                name: format!("Profile {}", i + 1),
+            })
+            .collect()
+    }
+
+    /// The first profile; it takes over whatever an older version left in the data directory
+    fn create_default_profile(&mut self) {
+        let profile = Profile {
+            id: Uuid::new_v4(),
+            name: DEFAULT_PROFILE_NAME.to_string(),
+        };
+        let profile_dir = self.profile_dir(profile.id);
+        if let Err(e) = fs::create_dir_all(&profile_dir) {
+            log::error!(target: "profiles", "Failed to create {:?}: {}", profile_dir, e);
+        }
+        if let Err(e) = self.migrate_legacy_data(&profile_dir) {
+            log::error!(target: "profiles", "Failed to move data into {:?}: {}", profile_dir, e);
+        }
+        self.current = profile.id;
+        self.profiles.push(profile);
+    }
+
+    fn migrate_legacy_data(&self, profile_dir: &Path) -> io::Result<()> {
+        for entry in fs::read_dir(&self.data_dir)? {
+            let entry = entry?;
+            let file_name = entry.file_name();
+            let is_legacy = LEGACY_FILE_PREFIXES
+                .iter()
+                .any(|prefix| file_name.to_string_lossy().starts_with(prefix));
+            if is_legacy {
+                log::info!(target: "profiles", "Moving {:?} into {:?}", file_name, profile_dir);
+                fs::rename(entry.path(), profile_dir.join(&file_name))?;
+            }
+        }
+        Ok(())
+    }
+
+    fn find(&self, id: Uuid) -> Option<&Profile> {
+        self.profiles.iter().find(|profile| profile.id == id)
+    }
+
+    pub fn profiles(&self) -> &[Profile] {
+        &self.profiles
+    }
+
+    pub fn current(&self) -> &Profile {
+        self.find(self.current)
+            .expect("the current profile is always in the list")
+    }
+
+    /// Where a profile keeps its settings, saved games and stats
+    pub fn profile_dir(&self, id: Uuid) -> PathBuf {
+        self.profiles_dir().join(id.to_string())
+    }
+
+    pub fn current_dir(&self) -> PathBuf {
+        self.profile_dir(self.current)
+    }
+
+    pub fn create(&mut self, name: &str) -> Result<Profile, ProfileError> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(ProfileError::EmptyName);
+        }
+        if self
+            .profiles
+            .iter()
+            .any(|profile| profile.name.to_lowercase() == name.to_lowercase())
+        {
+            return Err(ProfileError::DuplicateName(name.to_string()));
+        }
+
+        let profile = Profile {
+            id: Uuid::new_v4(),
+            name: name.to_string(),
+        };
+        fs::create_dir_all(self.profile_dir(profile.id))?;
+        self.profiles.push(profile.clone());
+        self.save()?;
+        Ok(profile)
+    }
+
+    pub fn switch_to(&mut self, id: Uuid) -> Result<(), ProfileError> {
+        if self.find(id).is_none() {
+            return Err(ProfileError::NotFound(id));
+        }
+        self.current = id;
+        Ok(self.save()?)
+    }
+
+    /// Deletes a profile other than the current one, and everything in it
+    pub fn delete(&mut self, id: Uuid) -> Result<(), ProfileError> {
+        if self.find(id).is_none() {
+            return Err(ProfileError::NotFound(id));
+        }
+        if id == self.current {
+            return Err(ProfileError::InUse);
+        }
+        self.profiles.retain(|profile| profile.id != id);
+        self.save()?;
+        let profile_dir = self.profile_dir(id);
+        if profile_dir.exists() {
+            fs::remove_dir_all(profile_dir)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::TempDir;
+
+    #[test]
+    fn test_migrates_existing_data_into_default_profile() {
+        let temp_dir = TempDir::new("profiles");
+        let data_dir = temp_dir.path().to_path_buf();
+        fs::create_dir_all(data_dir.join("saves")).unwrap();
+        fs::write(data_dir.join("settings.json"), "{}").unwrap();
+        fs::write(data_dir.join("scores_easy.json.bak1"), "[]").unwrap();
+        fs::write(data_dir.join("saves").join("auto_easy.json"), "{}").unwrap();
+        fs::write(data_dir.join("unrelated.txt"), "").unwrap();
+
+        let manager = ProfileManager::with_data_dir(data_dir.clone());
+        assert_eq!(manager.profiles().len(), 1);
+        assert_eq!(manager.current().name, DEFAULT_PROFILE_NAME);
+        let profile_dir = manager.current_dir();
+        assert!(profile_dir.join("settings.json").exists());
+        assert!(profile_dir.join("scores_easy.json.bak1").exists());
+        assert!(profile_dir.join("saves").join("auto_easy.json").exists());
+        assert!(!data_dir.join("settings.json").exists());
+        assert!(data_dir.join("unrelated.txt").exists());
+
+        // only the first time
+        let reloaded = ProfileManager::with_data_dir(data_dir.clone());
+        assert_eq!(reloaded.profiles(), manager.profiles());
+    }
+
+    #[test]
+    fn test_create_switch_and_delete() {
+        let temp_dir = TempDir::new("profiles");
+        let data_dir = temp_dir.path().to_path_buf();
+        let mut manager = ProfileManager::with_data_dir(data_dir.clone());
+        let first = manager.current().clone();
+
+        let second = manager.create("Sam").unwrap();
+        assert!(matches!(
+            manager.create(" sam "),
+            Err(ProfileError::DuplicateName(_))
+        ));
+        assert!(matches!(manager.create("  "), Err(ProfileError::EmptyName)));
+        assert!(manager.profile_dir(second.id).exists());
+
+        manager.switch_to(second.id).unwrap();
+        let reloaded = ProfileManager::with_data_dir(data_dir.clone());
+        assert_eq!(reloaded.current(), &second);
+
+        assert!(matches!(
+            manager.delete(second.id),
+            Err(ProfileError::InUse)
+        ));
+        manager.delete(first.id).unwrap();
+        assert_eq!(manager.profiles(), &[second]);
+        assert!(!manager.profile_dir(first.id).exists());
+    }
+
+    #[test]
+    fn test_recovers_profiles_from_directories() {
+        let temp_dir = TempDir::new("profiles");
+        let data_dir = temp_dir.path().to_path_buf();
+        let manager = ProfileManager::with_data_dir(data_dir.clone());
+        let id = manager.current().id;
+        save_file::remove(&data_dir.join("profiles.json")).unwrap();
+
+        let recovered = ProfileManager::with_data_dir(data_dir.clone());
+        assert_eq!(recovered.current().id, id);
+    }
+}
@@ -0,0 +1,867 @@
+//! Races over the local network. One instance hosts: it listens for TCP connections, picks the
+//! puzzle and keeps the list of players. Everyone else joins by address. Messages are JSON, one
+//! per line.
+//!
+//! Each player plays the puzzle on their own board and timer; only progress and the final time
+//! and hints cross the network. Everyone generates the puzzle while in the lobby and reports
+//! when it's ready, so that the host's start reveals it to all players at once. Sockets are read on worker threads, and `poll` hands what
+//! arrived to the main loop.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{
+    IpAddr, Ipv4Addr, Shutdown, SocketAddr, TcpListener, TcpStream, ToSocketAddrs, UdpSocket,
+};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender, TryRecvError};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::model::ShareCode;
+
+pub const DEFAULT_PORT: u16 = 47474;
+/// Bumped whenever the messages change; players on different versions can't race each other
+const PROTOCOL_VERSION: u32 = 2;
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(3);
+/// A stalled player can hold up the main loop for at most this long
+const WRITE_TIMEOUT: Duration = Duration::from_secs(2);
+/// A connection that hasn't sent `Join` by then is dropped
+const JOIN_TIMEOUT: Duration = Duration::from_secs(10);
+/// Connections beyond this many waiting to join are turned away
+const MAX_PENDING: usize = 8;
+/// A connection sending a longer message than this is dropped
+const MAX_LINE: usize = 64 * 1024;
+/// The host is always the first player
+const HOST_PLAYER_ID: u32 = 0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RaceResult {
+    pub completion_time: Duration,
+    pub hints_used: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RacePlayer {
+    pub id: u32,
+    pub name: String,
+    /// How much of the puzzle is worked out, 0 to 100
+    pub progress: u8,
+    pub result: Option<RaceResult>,
+    /// Players who leave stay in the standings with their last progress
+    pub connected: bool,
+    /// Whether the player has the puzzle generated, waiting for the start
+    pub ready: bool,
+}
+
+impl RacePlayer {
+    fn new(id: u32, name: &str) -> Self {
+        Self {
+            id,
+            name: name.to_string(),
+            progress: 0,
+            result: None,
+            connected: true,
+            ready: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+enum RaceMessage {
+    Join { name: String, protocol_version: u32 },
+    Welcome { player_id: u32, share_code: String },
+    Rejected { reason: String },
+    Players { players: Vec<RacePlayer> },
+    Ready,
+    Start,
+    Progress { percent: u8 },
+    Finished { result: RaceResult },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RacePhase {
+    /// Players are joining; the host hasn't started yet
+    Lobby,
+    Racing,
+    /// Everyone still connected has finished
+    Over,
+}
+
+/// What changed since the last `poll`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RaceUpdate {
+    PlayersChanged,
+    /// The puzzle to generate ahead of the start
+    PuzzleChosen(ShareCode),
+    /// Everyone should show this puzzle and start their clock now
+    Started(ShareCode),
+    /// The race can't go on: the host left, or turned us away
+    Closed(String),
+}
+
+#[derive(Debug)]
+pub enum RaceError {
+    Io(io::Error),
+    /// Nothing is listening at the address, or it didn't answer in time
+    Unreachable(String),
+}
+
+impl Display for RaceError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RaceError::Io(err) => write!(f, "{}", err),
+            RaceError::Unreachable(address) => {
+                write!(f, "Could not reach a race at {}", address)
+            }
+        }
+    }
+}
+
+impl From<io::Error> for RaceError {
+    fn from(err: io::Error) -> Self {
+        RaceError::Io(err)
+    }
+}
+
+/// Something a socket's worker thread picked up, tagged with the connection it came from
+enum NetEvent {
+    Connected(u32, TcpStream),
+    Message(u32, RaceMessage),
+    Disconnected(u32),
+}
+
+enum Role {
+    Host {
+        address: SocketAddr,
+        /// Guests' sockets by player id, once they've sent `Join`
+        guests: HashMap<u32, TcpStream>,
+        /// Connections that haven't sent `Join` yet, with when they connected
+        pending: HashMap<u32, (TcpStream, Instant)>,
+        stopped: Arc<AtomicBool>,
+    },
+    Guest {
+        stream: TcpStream,
+    },
+}
+
+pub struct RaceSession {
+    role: Role,
+    receiver: Receiver<NetEvent>,
+    player_id: Option<u32>,
+    share_code: Option<ShareCode>,
+    players: Vec<RacePlayer>,
+    phase: RacePhase,
+    updates: Vec<RaceUpdate>,
+}
+
+fn send(stream: &mut TcpStream, message: &RaceMessage) -> io::Result<()> {
+    let mut line = serde_json::to_string(message)?;
+    line.push('\n');
+    stream.write_all(line.as_bytes())
+}
+
+/// Tells a connection why it can't join, then closes it
+fn turn_away(mut stream: TcpStream, reason: &str) {
+    let _ = send(
+        &mut stream,
+        &RaceMessage::Rejected {
+            reason: reason.to_string(),
+        },
+    );
+    let _ = stream.shutdown(Shutdown::Both);
+}
+
+/// Reads messages from `stream` until it closes or sends a line longer than `MAX_LINE`, then
+/// reports the disconnect
+fn spawn_reader(connection_id: u32, stream: TcpStream, sender: Sender<NetEvent>) {
+    std::thread::spawn(move || {
+        let mut reader = BufReader::new(stream);
+        let mut line = String::new();
+        loop {
+            line.clear();
+            // one byte past the limit tells a line that is too long from one that just fits
+            let read = (&mut reader).take(MAX_LINE as u64 + 1).read_line(&mut line);
+            if !matches!(read, Ok(read) if read > 0) {
+                break;
+            }
+            if line.len() > MAX_LINE {
+                log::warn!(target: "race", "Dropping connection {}: message too long", connection_id);
+                let _ = reader.get_ref().shutdown(Shutdown::Both);
+                break;
+            }
+            match serde_json::from_str(line.trim_end()) {
+                Ok(message) => {
+                    if sender
+                        .send(NetEvent::Message(connection_id, message))
+                        .is_err()
+                    {
+                        return;
+                    }
+                }
+                Err(e) => {
+                    log::warn!(target: "race", "Ignoring unreadable message {:?}: {}", line, e);
+                }
+            }
+        }
+        let _ = sender.send(NetEvent::Disconnected(connection_id));
+    });
+}
+
+/// The address other machines on the network can reach this one at. Connecting a UDP socket
+/// only picks the outgoing interface; nothing is sent.
+pub fn local_address() -> IpAddr {
+    UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0))
+        .and_then(|socket| {
+            socket.connect((Ipv4Addr::new(192, 0, 2, 1), 9))?;
+            socket.local_addr()
+        })
+        .map(|address| address.ip())
+        .unwrap_or(IpAddr::V4(Ipv4Addr::LOCALHOST))
+}
+
+/// Reads `host`, `host:port` or a bare IP address, using the default port when none is given
+fn resolve(address: &str) -> Result<Vec<SocketAddr>, RaceError> {
+    let address = address.trim();
+    let resolved = match address.parse::<IpAddr>() {
+        Ok(ip) => Ok(vec![SocketAddr::new(ip, DEFAULT_PORT)]),
+        Err(_) if !address.contains(':') => (address, DEFAULT_PORT)
+            .to_socket_addrs()
+            .map(|addrs| addrs.collect()),
+        Err(_) => address.to_socket_addrs().map(|addrs| addrs.collect()),
+    };
+    resolved.map_err(|_| RaceError::Unreachable(address.to_string()))
+}
+
+impl RaceSession {
+    fn new(role: Role, receiver: Receiver<NetEvent>) -> Self {
+        Self {
+            role,
+            receiver,
+            player_id: None,
+            share_code: None,
+            players: vec![],
+            phase: RacePhase::Lobby,
+            updates: vec![],
+        }
+    }
+
+    /// Starts listening on `port` (0 picks a free one) for players to join a race on
+    /// `share_code`
+    pub fn host(name: &str, share_code: ShareCode, port: u16) -> Result<Self, RaceError> {
+        let listener = TcpListener::bind((Ipv4Addr::UNSPECIFIED, port))?;
+        let address = listener.local_addr()?;
+        let stopped = Arc::new(AtomicBool::new(false));
+        let (sender, receiver) = mpsc::channel();
+        {
+            let stopped = Arc::clone(&stopped);
+            std::thread::spawn(move || {
+                let mut next_id = HOST_PLAYER_ID + 1;
+                for stream in listener.incoming() {
+                    if stopped.load(Ordering::Relaxed) {
+                        break;
+                    }
+                    let Ok(stream) = stream else {
+                        continue;
+                    };
+                    let Ok(reader) = stream.try_clone() else {
+                        continue;
+                    };
+                    let _ = stream.set_write_timeout(Some(WRITE_TIMEOUT));
+                    if sender.send(NetEvent::Connected(next_id, stream)).is_err() {
+                        break;
+                    }
+                    spawn_reader(next_id, reader, sender.clone());
+                    next_id += 1;
+                }
+            });
+        }
+
+        let mut session = Self::new(
+            Role::Host {
+                address,
+                guests: HashMap::new(),
+                pending: HashMap::new(),
+                stopped,
+            },
+            receiver,
+        );
+        session.player_id = Some(HOST_PLAYER_ID);
+        session.share_code = Some(share_code);
+        session.players = vec![RacePlayer::new(HOST_PLAYER_ID, name)];
+        session.updates.push(RaceUpdate::PuzzleChosen(share_code));
+        Ok(session)
+    }
+
+    /// Connects to a race hosted at `address`. The host answers with the puzzle, or turns us
+    /// away; either arrives through `poll`.
+    pub fn join(name: &str, address: &str) -> Result<Self, RaceError> {
+        let stream = resolve(address)?
+            .iter()
+            .find_map(|address| TcpStream::connect_timeout(address, CONNECT_TIMEOUT).ok())
+            .ok_or_else(|| RaceError::Unreachable(address.trim().to_string()))?;
+        stream.set_write_timeout(Some(WRITE_TIMEOUT))?;
+        let (sender, receiver) = mpsc::channel();
+        spawn_reader(HOST_PLAYER_ID, stream.try_clone()?, sender);
+
+        let mut session = Self::new(Role::Guest { stream }, receiver);
+        session.send_to_host(&RaceMessage::Join {
+            name: name.to_string(),
+            protocol_version: PROTOCOL_VERSION,
+        })?;
+        Ok(session)
+    }
+
+    pub fn is_host(&self) -> bool {
+        matches!(self.role, Role::Host { .. })
+    }
+
+    /// The port a hosted race listens on
+    pub fn port(&self) -> Option<u16> {
+        match &self.role {
+            Role::Host { address, .. } => Some(address.port()),
+            Role::Guest { .. } => None,
+        }
+    }
+
+    pub fn player_id(&self) -> Option<u32> {
+        self.player_id
+    }
+
+    /// The puzzle being raced; a guest learns it once the host has let them in
+    pub fn share_code(&self) -> Option<ShareCode> {
+        self.share_code
+    }
+
+    pub fn players(&self) -> &[RacePlayer] {
+        &self.players
+    }
+
+    pub fn phase(&self) -> RacePhase {
+        self.phase
+    }
+
+    /// Finished players by time, then fewest hints; then everyone else by progress
+    pub fn standings(&self) -> Vec<RacePlayer> {
+        let mut standings = self.players.clone();
+        standings.sort_by_key(|player| match player.result {
+            Some(result) => (0, result.completion_time, result.hints_used, 0),
+            None => (1, Duration::ZERO, 0, 100 - player.progress),
+        });
+        standings
+    }
+
+    /// Whether everyone in the lobby has the puzzle ready to start
+    pub fn everyone_ready(&self) -> bool {
+        self.players
+            .iter()
+            .filter(|player| player.connected)
+            .all(|player| player.ready)
+    }
+
+    /// Starts the race for everyone who has joined, once they all have the puzzle ready. Only
+    /// the host can start it, once.
+    pub fn start(&mut self) {
+        let Some(share_code) = self.share_code else {
+            return;
+        };
+        if !self.is_host() || self.phase != RacePhase::Lobby || !self.everyone_ready() {
+            return;
+        }
+        self.phase = RacePhase::Racing;
+        self.broadcast(&RaceMessage::Start);
+        self.updates.push(RaceUpdate::Started(share_code));
+    }
+
+    /// Tells everyone this player has generated the puzzle
+    pub fn report_ready(&mut self) {
+        if self.phase != RacePhase::Lobby || self.own_player().is_some_and(|player| player.ready) {
+            return;
+        }
+        self.send_own(RaceMessage::Ready);
+    }
+
+    pub fn report_progress(&mut self, percent: u8) {
+        let percent = percent.min(100);
+        let Some(player) = self.own_player() else {
+            return;
+        };
+        if self.phase != RacePhase::Racing || player.progress == percent || player.result.is_some()
+        {
+            return;
+        }
+        self.send_own(RaceMessage::Progress { percent });
+    }
+
+    pub fn report_finished(&mut self, result: RaceResult) {
+        if self.phase != RacePhase::Racing
+            || self
+                .own_player()
+                .is_none_or(|player| player.result.is_some())
+        {
+            return;
+        }
+        self.send_own(RaceMessage::Finished { result });
+    }
+
+    fn own_player(&self) -> Option<&RacePlayer> {
+        let player_id = self.player_id?;
+        self.players.iter().find(|player| player.id == player_id)
+    }
+
+    /// The host applies its own reports directly; a guest's go through the host
+    fn send_own(&mut self, message: RaceMessage) {
+        match &self.role {
+            Role::Host { .. } => self.apply_report(HOST_PLAYER_ID, message),
+            Role::Guest { .. } => {
+                if let Err(e) = self.send_to_host(&message) {
+                    log::error!(target: "race", "Failed to reach the host: {}", e);
+                }
+            }
+        }
+    }
+
+    fn send_to_host(&mut self, message: &RaceMessage) -> io::Result<()> {
+        match &mut self.role {
+            Role::Guest { stream } => send(stream, message),
+            Role::Host { .. } => Ok(()),
+        }
+    }
+
+    fn broadcast(&mut self, message: &RaceMessage) {
+        if let Role::Host { guests, .. } = &mut self.role {
+            for (player_id, stream) in guests.iter_mut() {
+                if let Err(e) = send(stream, message) {
+                    // the reader thread reports the disconnect
+                    log::warn!(target: "race", "Failed to send to player {}: {}", player_id, e);
+                }
+            }
+        }
+    }
+
+    fn broadcast_players(&mut self) {
+        let players = self.players.clone();
+        self.broadcast(&RaceMessage::Players { players });
+        self.updates.push(RaceUpdate::PlayersChanged);
+    }
+
+    /// A player being ready, their progress or their finish, as the host sees it
+    fn apply_report(&mut self, player_id: u32, message: RaceMessage) {
+        let Some(player) = self.players.iter_mut().find(|p| p.id == player_id) else {
+            return;
+        };
+        match (self.phase, message) {
+            (RacePhase::Lobby, RaceMessage::Ready) if !player.ready => {
+                player.ready = true;
+            }
+            (RacePhase::Racing, RaceMessage::Progress { percent }) if player.result.is_none() => {
+                player.progress = percent.min(100);
+            }
+            (RacePhase::Racing, RaceMessage::Finished { result }) if player.result.is_none() => {
+                player.progress = 100;
+                player.result = Some(result);
+            }
+            _ => return,
+        }
+        self.update_phase();
+        self.broadcast_players();
+    }
+
+    fn update_phase(&mut self) {
+        let everyone_done = self
+            .players
+            .iter()
+            .filter(|player| player.connected)
+            .all(|player| player.result.is_some());
+        if self.phase == RacePhase::Racing && everyone_done {
+            self.phase = RacePhase::Over;
+        }
+    }
+
+    fn reject(&mut self, connection_id: u32, reason: &str) {
+        if let Role::Host { pending, .. } = &mut self.role {
+            if let Some((stream, _)) = pending.remove(&connection_id) {
+                turn_away(stream, reason);
+            }
+        }
+    }
+
+    /// Drops connections that have waited longer than `JOIN_TIMEOUT` at `now` without joining
+    fn expire_pending(&mut self, now: Instant) {
+        if let Role::Host { pending, .. } = &mut self.role {
+            pending.retain(|connection_id, (stream, connected_at)| {
+                let expired = now.duration_since(*connected_at) >= JOIN_TIMEOUT;
+                if expired {
+                    log::info!(target: "race", "Connection {} never joined", connection_id);
+                    let _ = stream.shutdown(Shutdown::Both);
+                }
+                !expired
+            });
+        }
+    }
+
+    fn handle_join(&mut self, connection_id: u32, name: String, protocol_version: u32) {
+        if protocol_version != PROTOCOL_VERSION {
+            self.reject(
+                connection_id,
+                "The host is running a different version of Mind Hunt",
+            );
+            return;
+        }
+        if self.phase != RacePhase::Lobby {
+            self.reject(connection_id, "The race has already started");
+            return;
+        }
+        let (
+            Role::Host {
+                guests, pending, ..
+            },
+            Some(share_code),
+        ) = (&mut self.role, self.share_code)
+        else {
+            return;
+        };
+        let Some((mut stream, _)) = pending.remove(&connection_id) else {
+            return;
+        };
+        let welcome = RaceMessage::Welcome {
+            player_id: connection_id,
+            share_code: share_code.to_string(),
+        };
+        if send(&mut stream, &welcome).is_err() {
+            return;
+        }
+        guests.insert(connection_id, stream);
+
+        let name = name.trim();
+        let name = if name.is_empty() {
+            format!("Player {}", connection_id + 1)
+        } else {
+            name.to_string()
+        };
+        self.players.push(RacePlayer::new(connection_id, &name));
+        self.broadcast_players();
+    }
+
+    fn handle_host_event(&mut self, event: NetEvent) {
+        match event {
+            NetEvent::Connected(connection_id, stream) => {
+                if let Role::Host { pending, .. } = &mut self.role {
+                    if pending.len() >= MAX_PENDING {
+                        turn_away(stream, "Too many players are joining; try again");
+                    } else {
+                        pending.insert(connection_id, (stream, Instant::now()));
+                    }
+                }
+            }
+            NetEvent::Message(
+                connection_id,
+                RaceMessage::Join {
+                    name,
+                    protocol_version,
+                },
+            ) => self.handle_join(connection_id, name, protocol_version),
+            NetEvent::Message(connection_id, message) => self.apply_report(connection_id, message),
+            NetEvent::Disconnected(connection_id) => {
+                if let Role::Host {
+                    guests, pending, ..
+                } = &mut self.role
+                {
+                    pending.remove(&connection_id);
+                    guests.remove(&connection_id);
+                }
+                let Some(index) = self.players.iter().position(|p| p.id == connection_id) else {
+                    return;
+                };
+                if self.phase == RacePhase::Lobby {
+                    self.players.remove(index);
+                } else {
+                    self.players[index].connected = false;
+                    self.update_phase();
+                }
+                self.broadcast_players();
+            }
+        }
+    }
+
+    fn handle_guest_event(&mut self, event: NetEvent) {
+        match event {
+            NetEvent::Message(
+                _,
+                RaceMessage::Welcome {
+                    player_id,
+                    share_code,
+                },
+            ) => match ShareCode::parse(&share_code) {
+                Ok(share_code) => {
+                    self.player_id = Some(player_id);
+                    self.share_code = Some(share_code);
+                    self.updates.push(RaceUpdate::PuzzleChosen(share_code));
+                }
+                Err(e) => self.close(&format!("The host's puzzle can't be played here: {}", e)),
+            },
+            NetEvent::Message(_, RaceMessage::Rejected { reason }) => self.close(&reason),
+            NetEvent::Message(_, RaceMessage::Players { players }) => {
+                self.players = players;
+                self.update_phase();
+                self.updates.push(RaceUpdate::PlayersChanged);
+            }
+            NetEvent::Message(_, RaceMessage::Start) => {
+                if let (Some(share_code), RacePhase::Lobby) = (self.share_code, self.phase) {
+                    self.phase = RacePhase::Racing;
+                    self.updates.push(RaceUpdate::Started(share_code));
+                }
+            }
+            NetEvent::Disconnected(_) => {
+                if self.phase != RacePhase::Over {
+                    self.close("The host left the race");
+                }
+            }
+            NetEvent::Message(..) | NetEvent::Connected(..) => (),
+        }
+    }
+
+    fn close(&mut self, reason: &str) {
+        if let Role::Guest { stream } = &self.role {
+            let _ = stream.shutdown(Shutdown::Both);
+        }
+        self.updates.push(RaceUpdate::Closed(reason.to_string()));
+    }
+
+    /// Takes in everything that arrived since the last call; meant to run on a timer in the
+    /// main loop
+    pub fn poll(&mut self) -> Vec<RaceUpdate> {
+        loop {
+            match self.receiver.try_recv() {
+                Ok(event) if self.is_host() => self.handle_host_event(event),
+                Ok(event) => self.handle_guest_event(event),
+                Err(TryRecvError::Empty | TryRecvError::Disconnected) => break,
+            }
+        }
+        self.expire_pending(Instant::now());
+        std::mem::take(&mut self.updates)
+    }
+}
+
+impl Drop for RaceSession {
+    fn drop(&mut self) {
+        match &self.role {
+            Role::Host {
+                address,
+                guests,
+                pending,
+                stopped,
+            } => {
+                stopped.store(true, Ordering::Relaxed);
+                // wake the listener so it sees it's been stopped
+                let _ = TcpStream::connect_timeout(
+                    &SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), address.port()),
+                    CONNECT_TIMEOUT,
+                );
+                let pending = pending.values().map(|(stream, _)| stream);
+                for stream in guests.values().chain(pending) {
+                    let _ = stream.shutdown(Shutdown::Both);
+                }
+            }
+            Role::Guest { stream } => {
+                let _ = stream.shutdown(Shutdown::Both);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::Difficulty;
+
+    /// Polls until `done` holds, failing after a few seconds
+    fn poll_until(session: &mut RaceSession, done: impl Fn(&RaceSession) -> bool) {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while !done(session) {
+            assert!(Instant::now() < deadline, "timed out waiting on the race");
+            session.poll();
+            std::thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    fn loopback(host: &RaceSession) -> String {
+        format!("127.0.0.1:{}", host.port().unwrap())
+    }
+
+    #[test]
+    fn test_race_over_loopback() {
+        let share_code = ShareCode::new(Difficulty::Easy, 1234);
+        let mut host = RaceSession::host("Alex", share_code, 0).unwrap();
+        let mut guest = RaceSession::join("Sam", &loopback(&host)).unwrap();
+
+        assert_eq!(
+            host.poll().first(),
+            Some(&RaceUpdate::PuzzleChosen(share_code))
+        );
+        let deadline = Instant::now() + Duration::from_secs(5);
+        let mut updates = vec![];
+        while !updates.contains(&RaceUpdate::PuzzleChosen(share_code)) {
+            assert!(Instant::now() < deadline, "guest never learned the puzzle");
+            host.poll();
+            updates.extend(guest.poll());
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        poll_until(&mut host, |host| host.players().len() == 2);
+        poll_until(&mut guest, |guest| guest.players().len() == 2);
+        assert_eq!(guest.share_code(), Some(share_code));
+        assert_eq!(guest.player_id(), Some(1));
+
+        // the race waits until everyone has generated the puzzle
+        host.report_ready();
+        host.start();
+        assert_eq!(host.phase(), RacePhase::Lobby);
+        guest.report_ready();
+        poll_until(&mut host, |host| host.everyone_ready());
+        poll_until(&mut guest, |guest| guest.everyone_ready());
+
+        host.start();
+        assert_eq!(host.poll(), vec![RaceUpdate::Started(share_code)]);
+        let deadline = Instant::now() + Duration::from_secs(5);
+        let mut updates = vec![];
+        while !updates.contains(&RaceUpdate::Started(share_code)) {
+            assert!(Instant::now() < deadline, "guest never started");
+            updates.extend(guest.poll());
+            std::thread::sleep(Duration::from_millis(10));
+        }
+
+        guest.report_progress(40);
+        poll_until(&mut host, |host| host.players()[1].progress == 40);
+        poll_until(&mut guest, |guest| guest.players()[1].progress == 40);
+
+        let guest_result = RaceResult {
+            completion_time: Duration::from_secs(90),
+            hints_used: 2,
+        };
+        let host_result = RaceResult {
+            completion_time: Duration::from_secs(90),
+            hints_used: 0,
+        };
+        guest.report_finished(guest_result);
+        poll_until(&mut host, |host| host.players()[1].result.is_some());
+        assert_eq!(host.phase(), RacePhase::Racing);
+        host.report_finished(host_result);
+        assert_eq!(host.phase(), RacePhase::Over);
+        poll_until(&mut guest, |guest| guest.phase() == RacePhase::Over);
+
+        // a tie on time goes to fewer hints
+        let names: Vec<String> = guest
+            .standings()
+            .into_iter()
+            .map(|player| player.name)
+            .collect();
+        assert_eq!(names, vec!["Alex", "Sam"]);
+    }
+
+    #[test]
+    fn test_late_joiner_is_turned_away() {
+        let share_code = ShareCode::new(Difficulty::Easy, 99);
+        let mut host = RaceSession::host("Alex", share_code, 0).unwrap();
+        host.report_ready();
+        host.start();
+        assert_eq!(host.phase(), RacePhase::Racing);
+
+        let mut late = RaceSession::join("Sam", &loopback(&host)).unwrap();
+        let deadline = Instant::now() + Duration::from_secs(5);
+        loop {
+            assert!(
+                Instant::now() < deadline,
+                "late player was never turned away"
+            );
+            host.poll();
+            let updates = late.poll();
+            if updates.iter().any(|u| matches!(u, RaceUpdate::Closed(_))) {
+                break;
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        assert_eq!(host.players().len(), 1);
+    }
+
+    fn pending_count(host: &RaceSession) -> usize {
+        match &host.role {
+            Role::Host { pending, .. } => pending.len(),
+            Role::Guest { .. } => 0,
+        }
+    }
+
+    #[test]
+    fn test_idle_connections_are_limited_and_dropped() {
+        let share_code = ShareCode::new(Difficulty::Easy, 7);
+        let mut host = RaceSession::host("Alex", share_code, 0).unwrap();
+        // connections that never send `Join`
+        let idle: Vec<TcpStream> = (0..=MAX_PENDING)
+            .map(|_| TcpStream::connect(loopback(&host)).unwrap())
+            .collect();
+
+        // the one over the limit is turned away
+        let mut over_limit = idle.last().unwrap().try_clone().unwrap();
+        over_limit
+            .set_read_timeout(Some(Duration::from_millis(10)))
+            .unwrap();
+        let deadline = Instant::now() + Duration::from_secs(5);
+        let mut reply = String::new();
+        while !reply.ends_with('\n') {
+            assert!(
+                Instant::now() < deadline,
+                "extra connection was never turned away"
+            );
+            host.poll();
+            let mut buffer = [0; 256];
+            if let Ok(read) = over_limit.read(&mut buffer) {
+                reply.push_str(&String::from_utf8_lossy(&buffer[..read]));
+            }
+        }
+        assert!(matches!(
+            serde_json::from_str(reply.trim()),
+            Ok(RaceMessage::Rejected { .. })
+        ));
+        assert_eq!(pending_count(&host), MAX_PENDING);
+
+        host.expire_pending(Instant::now() + JOIN_TIMEOUT);
+        assert_eq!(pending_count(&host), 0);
+        let mut buffer = [0; 1];
+        assert_eq!((&idle[0]).read(&mut buffer).unwrap(), 0);
+
+        // there's room to join again
+        let mut guest = RaceSession::join("Sam", &loopback(&host)).unwrap();
+        poll_until(&mut host, |host| host.players().len() == 2);
+        poll_until(&mut guest, |guest| guest.players().len() == 2);
+    }
+
+    #[test]
+    fn test_overlong_message_drops_connection() {
+        let share_code = ShareCode::new(Difficulty::Easy, 8);
+        let mut host = RaceSession::host("Alex", share_code, 0).unwrap();
+        let mut flooder = TcpStream::connect(loopback(&host)).unwrap();
+        poll_until(&mut host, |host| pending_count(host) == 1);
+
+        // no newline ever comes; the host stops reading once past the limit
+        let _ = flooder.write_all(&vec![b'x'; MAX_LINE + 1]);
+        poll_until(&mut host, |host| pending_count(host) == 0);
+        flooder
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+        let mut buffer = [0; 1];
+        assert!(!matches!(flooder.read(&mut buffer), Ok(1)));
+    }
+
+    #[test]
+    fn test_resolve_defaults_port() {
+        assert_eq!(
+            resolve("127.0.0.1").unwrap(),
+            vec![SocketAddr::new(
+                IpAddr::V4(Ipv4Addr::LOCALHOST),
+                DEFAULT_PORT
+            )]
+        );
+        assert_eq!(resolve(" 127.0.0.1:5000 ").unwrap()[0].port(), 5000);
+    }
+}
@@ -0,0 +1,269 @@
+//! Rebuilds every board of a finished game from its move log, for the replay viewer.
+
+use std::rc::Rc;
+use std::time::Duration;
+
+use crate::destroyable::Destroyable;
+use crate::game::game_state::GameState;
+use crate::model::{GameBoard, MoveLog, RecordedAction, RecordedMove};
+
+#[derive(Debug, Clone)]
+pub struct ReplayFrame {
+    pub board: Rc<GameBoard>,
+    /// The move that led to this board; `None` for the starting board
+    pub recorded_move: Option<RecordedMove>,
+    /// What the move did, in words
+    pub description: String,
+    pub hints_used: u32,
+    pub undos: u32,
+}
+
+impl ReplayFrame {
+    pub fn elapsed(&self) -> Duration {
+        self.recorded_move
+            .as_ref()
+            .map(|m| m.elapsed)
+            .unwrap_or_default()
+    }
+
+    pub fn is_hint(&self) -> bool {
+        matches!(
+            self.recorded_move.as_ref().map(|m| &m.action),
+            Some(RecordedAction::ShowHint | RecordedAction::IncrementHintsUsed)
+        )
+    }
+
+    pub fn is_undo(&self) -> bool {
+        matches!(
+            self.recorded_move.as_ref().map(|m| &m.action),
+            Some(RecordedAction::Undo | RecordedAction::RewindLastGood)
+        )
+    }
+}
+
+fn describe(action: &RecordedAction, before: &GameBoard, after: &GameBoard) -> String {
+    match action {
+        RecordedAction::CellSelect(row, col, variant) => {
+            let tile = variant.map_or(row.to_string(), |v| format!("{}{}", row, v));
+            if after.get_selection(*row, *col).is_some() {
+                format!("Placed {} in column {}", tile, col + 1)
+            } else {
+                format!("Restored {} in column {}", tile, col + 1)
+            }
+        }
+        RecordedAction::CellClear(row, col, variant) => match before.get_selection(*row, *col) {
+            Some(tile) => format!("Cleared {} from column {}", tile, col + 1),
+            None => {
+                let tile = variant.map_or(row.to_string(), |v| format!("{}{}", row, v));
+                format!("Eliminated {} from column {}", tile, col + 1)
+            }
+        },
+        RecordedAction::ClueToggleComplete(address) => format!(
+            "Toggled {:?} clue {}",
+            address.orientation,
+            address.index + 1
+        ),
+        RecordedAction::Solve => "Solver step".to_string(),
+        RecordedAction::RewindLastGood => "Rewound to the last correct board".to_string(),
+        RecordedAction::ShowHint | RecordedAction::IncrementHintsUsed => "Hint".to_string(),
+        RecordedAction::Undo => "Undo".to_string(),
+        RecordedAction::Redo => "Redo".to_string(),
+        RecordedAction::Resumed => "Resumed saved game".to_string(),
+    }
+}
+
+/// Replays `move_log` from its starting board; the first frame is the starting board, followed
+/// by one frame per move.
+pub fn build_replay(move_log: &MoveLog) -> Vec<ReplayFrame> {
+    let game_state = GameState::headless(move_log.initial_board.clone());
+    let mut frames = vec![ReplayFrame {
+        board: Rc::clone(&game_state.borrow().current_board),
+        recorded_move: None,
+        description: "Start".to_string(),
+        hints_used: 0,
+        undos: 0,
+    }];
+
+    let mut undos = 0;
+    for recorded_move in move_log.moves.iter() {
+        let before = Rc::clone(&game_state.borrow().current_board);
+        game_state
+            .borrow_mut()
+            .apply_recorded_action(&recorded_move.action);
+        let game_state = game_state.borrow();
+        if recorded_move.action == RecordedAction::Undo {
+            undos += 1;
+        }
+        frames.push(ReplayFrame {
+            board: Rc::clone(&game_state.current_board),
+            recorded_move: Some(recorded_move.clone()),
+            description: describe(&recorded_move.action, &before, &game_state.current_board),
+            hints_used: game_state.get_hints_used(),
+            undos,
+        });
+    }
+
+    game_state.borrow_mut().destroy();
+    frames
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{CandidateState, Difficulty, GameActionEvent, GameStateSnapshot, Tile};
+
+    fn tutorial_board() -> GameBoard {
+        GameStateSnapshot::generate_new(Difficulty::Tutorial, Some(1)).board
+    }
+
+    /// The solution tile of the first open cell
+    fn open_tile(board: &GameBoard) -> (usize, Tile) {
+        (0..board.solution.n_rows)
+            .flat_map(|row| (0..board.solution.n_variants).map(move |col| (row, col)))
+            .find(|(row, col)| board.get_selection(*row, *col).is_none())
+            .map(|(row, col)| (col, board.solution.get(row, col)))
+            .expect("puzzle should have open cells")
+    }
+
+    /// An available candidate that isn't the solution, in the first open cell
+    fn wrong_candidate(board: &GameBoard) -> (usize, Tile) {
+        let (col, solution_tile) = open_tile(board);
+        let variant = board
+            .solution
+            .variants
+            .iter()
+            .copied()
+            .find(|variant| {
+                *variant != solution_tile.variant
+                    && board
+                        .get_candidate(solution_tile.row, col, *variant)
+                        .is_some_and(|c| c.state == CandidateState::Available)
+            })
+            .expect("open cell should have a wrong candidate");
+        (col, Tile::new(solution_tile.row, variant))
+    }
+
+    fn actions(move_log: &MoveLog) -> Vec<RecordedAction> {
+        move_log.moves.iter().map(|m| m.action.clone()).collect()
+    }
+
+    #[test]
+    fn test_replay_reaches_final_board() {
+        let board = tutorial_board();
+        let (wrong_col, wrong) = wrong_candidate(&board);
+        let (col, tile) = open_tile(&board);
+
+        let game_state = GameState::headless(board);
+        for event in [
+            GameActionEvent::CellClear(wrong.row, wrong_col, Some(wrong.variant)),
+            GameActionEvent::CellSelect(tile.row, col, Some(tile.variant)),
+            // already placed; changes nothing and isn't recorded
+            GameActionEvent::CellSelect(tile.row, col, Some(tile.variant)),
+            GameActionEvent::Undo,
+            GameActionEvent::Redo,
+            GameActionEvent::IncrementHintsUsed,
+        ] {
+            game_state.borrow_mut().handle_event(event);
+        }
+        let move_log = game_state.borrow().get_game_save_state().move_log.unwrap();
+        let final_board = Rc::clone(&game_state.borrow().current_board);
+        game_state.borrow_mut().destroy();
+
+        assert_eq!(
+            actions(&move_log),
+            vec![
+                RecordedAction::CellClear(wrong.row, wrong_col, Some(wrong.variant)),
+                RecordedAction::CellSelect(tile.row, col, Some(tile.variant)),
+                RecordedAction::Undo,
+                RecordedAction::Redo,
+                RecordedAction::IncrementHintsUsed,
+            ]
+        );
+
+        let frames = build_replay(&move_log);
+        assert_eq!(frames.len(), move_log.moves.len() + 1);
+        assert_eq!(*frames.last().unwrap().board, *final_board);
+        assert_eq!(
+            frames[1].description,
+            format!("Eliminated {} from column {}", wrong, wrong_col + 1)
+        );
+        assert_eq!(
+            frames[2].description,
+            format!("Placed {} in column {}", tile, col + 1)
+        );
+        assert!(frames[3].is_undo());
+        assert_eq!(frames[3].undos, 1);
+        assert_eq!(*frames[3].board, *frames[1].board);
+        assert!(frames.last().unwrap().is_hint());
+        assert_eq!(frames.last().unwrap().hints_used, 1);
+    }
+
+    #[test]
+    fn test_replay_across_save_and_load() {
+        let board = tutorial_board();
+        let (wrong_col, wrong) = wrong_candidate(&board);
+        let (col, tile) = open_tile(&board);
+
+        let game_state = GameState::headless(board);
+        game_state
+            .borrow_mut()
+            .handle_event(GameActionEvent::CellClear(
+                wrong.row,
+                wrong_col,
+                Some(wrong.variant),
+            ));
+        let saved = game_state.borrow().get_game_save_state();
+        let saved: GameStateSnapshot =
+            serde_json::from_str(&serde_json::to_string(&saved).unwrap()).unwrap();
+        game_state
+            .borrow_mut()
+            .handle_event(GameActionEvent::LoadState(saved));
+        game_state.borrow_mut().handle_event(GameActionEvent::Undo);
+        game_state
+            .borrow_mut()
+            .handle_event(GameActionEvent::CellSelect(
+                tile.row,
+                col,
+                Some(tile.variant),
+            ));
+        let move_log = game_state.borrow().get_game_save_state().move_log.unwrap();
+        let final_board = Rc::clone(&game_state.borrow().current_board);
+        game_state.borrow_mut().destroy();
+
+        // the undo after loading had nothing to undo, so it isn't in the log
+        assert_eq!(
+            actions(&move_log),
+            vec![
+                RecordedAction::CellClear(wrong.row, wrong_col, Some(wrong.variant)),
+                RecordedAction::Resumed,
+                RecordedAction::CellSelect(tile.row, col, Some(tile.variant)),
+            ]
+        );
+        assert_eq!(*build_replay(&move_log).last().unwrap().board, *final_board);
+    }
+
+    #[test]
+    fn test_counts_every_mistake() {
+        let board = tutorial_board();
+        let (wrong_col, wrong) = wrong_candidate(&board);
+        let (col, tile) = open_tile(&board);
+
+        let game_state = GameState::headless(board);
+        let mut mistakes = vec![];
+        for event in [
+            // eliminating a wrong candidate is fine, and so is bringing it back
+            GameActionEvent::CellClear(wrong.row, wrong_col, Some(wrong.variant)),
+            GameActionEvent::CellSelect(wrong.row, wrong_col, Some(wrong.variant)),
+            GameActionEvent::CellClear(tile.row, col, Some(tile.variant)),
+            // the board is already wrong, but this is another mistake
+            GameActionEvent::CellSelect(wrong.row, wrong_col, Some(wrong.variant)),
+            GameActionEvent::CellClear(wrong.row, wrong_col, None),
+        ] {
+            game_state.borrow_mut().handle_event(event);
+            mistakes.push(game_state.borrow().get_game_save_state().mistakes);
+        }
+        game_state.borrow_mut().destroy();
+
+        assert_eq!(mistakes, vec![0, 0, 1, 2, 2]);
+    }
+}
@@ -0,0 +1,445 @@
+use crate::model::game_state_snapshot::{self, GameStateSnapshot};
+use crate::model::save_file::{self, LoadOutcome, SaveFileError};
+use crate::model::{Difficulty, Tile};
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::fs;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// A place a game can be saved: the automatic slot of a difficulty, which holds the puzzle last
+/// played at it, or a slot the player named with "Save As…"
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SaveSlotId {
+    Auto(Difficulty),
+    Named(u32),
+}
+
+#[derive(Debug, Serialize)]
+struct SaveSlot {
+    name: String,
+    /// Seconds since the Unix epoch
+    saved_at: i64,
+    snapshot: GameStateSnapshot,
+}
+
+/// A slot as stored; the snapshot is migrated separately, as it may be from an older version
+#[derive(Deserialize)]
+struct StoredSaveSlot {
+    name: String,
+    saved_at: i64,
+    snapshot: serde_json::Value,
+}
+
+impl SaveSlot {
+    fn from_json(contents: &str) -> Result<Self, SaveFileError> {
+        let stored: StoredSaveSlot = serde_json::from_str(contents)?;
+        Ok(Self {
+            name: stored.name,
+            saved_at: stored.saved_at,
+            snapshot: GameStateSnapshot::from_json_value(stored.snapshot)?,
+        })
+    }
+}
+
+/// What the "Continue…" dialog shows about a slot, without holding on to the whole game
+#[derive(Debug, Clone)]
+pub struct SaveSlotSummary {
+    pub id: SaveSlotId,
+    pub name: String,
+    pub saved_at: i64,
+    pub difficulty: Difficulty,
+    pub elapsed: Duration,
+    /// Share of the cells solved, from 0.0 to 1.0
+    pub progress: f64,
+    /// The selected tile of every cell, by row then column
+    pub thumbnail: Vec<Vec<Option<Tile>>>,
+}
+
+impl SaveSlotSummary {
+    fn new(id: SaveSlotId, slot: &SaveSlot) -> Self {
+        let board = &slot.snapshot.board;
+        let solution = &board.solution;
+        let thumbnail: Vec<Vec<Option<Tile>>> = (0..solution.n_rows)
+            .map(|row| {
+                (0..solution.n_variants)
+                    .map(|col| board.get_selection(row, col))
+                    .collect()
+            })
+            .collect();
+        let cells = solution.n_rows * solution.n_variants;
+        let solved = thumbnail
+            .iter()
+            .flatten()
+            .filter(|tile| tile.is_some())
+            .count();
+        Self {
+            id,
+            name: slot.name.clone(),
+            saved_at: slot.saved_at,
+            difficulty: solution.difficulty,
+            elapsed: slot.snapshot.timer_state.elapsed(),
+            progress: if cells > 0 {
+                solved as f64 / cells as f64
+            } else {
+                0.0
+            },
+            thumbnail,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct SaveSlotManager {
+    data_dir: PathBuf,
+    /// Why the save of an older version couldn't be carried over, until someone asks
+    legacy_save_error: RefCell<Option<SaveFileError>>,
+}
+
+impl SaveSlotManager {
+    /// The saved games kept in a profile's directory
+    pub fn new(data_dir: PathBuf) -> Self {
+        let saves_dir = data_dir.join("saves");
+        if !saves_dir.exists() {
+            let _ = fs::create_dir_all(&saves_dir);
+        }
+
+        let manager = Self {
+            data_dir,
+            legacy_save_error: RefCell::new(None),
+        };
+        manager.migrate_legacy_save();
+        manager
+    }
+
+    fn slot_path(&self, id: SaveSlotId) -> PathBuf {
+        let file_name = match id {
+            SaveSlotId::Auto(difficulty) => {
+                format!("auto_{}.json", difficulty.to_string().to_lowercase())
+            }
+            SaveSlotId::Named(number) => format!("slot_{}.json", number),
+        };
+        self.data_dir.join("saves").join(file_name)
+    }
+
+    fn legacy_save_path(&self) -> PathBuf {
+        self.data_dir.join("game_state.json")
+    }
+
+    /// Older versions kept a single `game_state.json`; it becomes the automatic slot of its
+    /// difficulty.
+    fn migrate_legacy_save(&self) {
+        let legacy_path = self.legacy_save_path();
+        let snapshot = match game_state_snapshot::load_game_state_snapshot(&legacy_path) {
+            LoadOutcome::Missing => return,
+            LoadOutcome::Unreadable(err) => {
+                self.legacy_save_error.replace(Some(err));
+                return;
+            }
+            LoadOutcome::TooNew(version) => {
+                self.legacy_save_error
+                    .replace(Some(SaveFileError::UnsupportedVersion(version)));
+                return;
+            }
+            LoadOutcome::Loaded(snapshot) | LoadOutcome::Restored(snapshot, _) => snapshot,
+        };
+        let id = SaveSlotId::Auto(snapshot.board.solution.difficulty);
+        if !self.slot_path(id).exists() {
+            if let Err(e) = self.write_slot(id, &snapshot) {
+                log::error!(target: "save_slots", "Failed to migrate {:?}: {}", legacy_path, e);
+                return;
+            }
+        }
+        if let Err(e) = save_file::remove(&legacy_path) {
+            log::error!(target: "save_slots", "Failed to remove {:?}: {}", legacy_path, e);
+        }
+    }
+
+    /// The reason the save of an older version of the game couldn't be carried over, if it
+    /// couldn't; reported once
+    pub fn take_legacy_save_error(&self) -> Option<SaveFileError> {
+        self.legacy_save_error.take()
+    }
+
+    fn read_slot(&self, id: SaveSlotId) -> LoadOutcome<SaveSlot> {
+        save_file::load(&self.slot_path(id), &SaveSlot::from_json)
+    }
+
+    fn write_slot_named(
+        &self,
+        id: SaveSlotId,
+        name: &str,
+        snapshot: &GameStateSnapshot,
+    ) -> std::io::Result<()> {
+        let slot = SaveSlot {
+            name: name.to_string(),
+            saved_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs() as i64,
+            snapshot: snapshot.clone(),
+        };
+        let contents = serde_json::to_string(&slot)?;
+        save_file::write_atomic(&self.slot_path(id), &contents)
+    }
+
+    fn write_slot(&self, id: SaveSlotId, snapshot: &GameStateSnapshot) -> std::io::Result<()> {
+        let name = match id {
+            SaveSlotId::Auto(difficulty) => difficulty.to_string(),
+            SaveSlotId::Named(number) => format!("Save {}", number),
+        };
+        self.write_slot_named(id, &name, snapshot)
+    }
+
+    /// Keeps `snapshot` in the automatic slot of its difficulty, so that switching back to that
+    /// difficulty resumes it. A solved puzzle has nothing left to resume, so its slot is cleared
+    /// instead. A slot saved by a newer version of the game is left for that version to resume.
+    pub fn save_auto(&self, snapshot: &GameStateSnapshot) -> std::io::Result<()> {
+        let id = SaveSlotId::Auto(snapshot.board.solution.difficulty);
+        if let LoadOutcome::TooNew(version) = self.read_slot(id) {
+            log::warn!(
+                target: "save_slots",
+                "Not saving over {:?}, saved by a newer version (format {})",
+                self.slot_path(id),
+                version
+            );
+            return Ok(());
+        }
+        if snapshot.board.is_complete() && !snapshot.board.is_incorrect() {
+            return self.delete(id);
+        }
+        self.write_slot(id, snapshot)
+    }
+
+    /// Saves `snapshot` under `name`, replacing the named slot that already has that name
+    pub fn save_as(&self, name: &str, snapshot: &GameStateSnapshot) -> std::io::Result<SaveSlotId> {
+        let id = self
+            .list()
+            .into_iter()
+            .find(|summary| matches!(summary.id, SaveSlotId::Named(_)) && summary.name == name)
+            .map(|summary| summary.id)
+            .unwrap_or_else(|| {
+                // numbered past every slot file, including those from newer versions
+                let next = self
+                    .slot_ids()
+                    .into_iter()
+                    .filter_map(|id| match id {
+                        SaveSlotId::Named(number) => Some(number + 1),
+                        SaveSlotId::Auto(_) => None,
+                    })
+                    .max()
+                    .unwrap_or(1);
+                SaveSlotId::Named(next)
+            });
+        self.write_slot_named(id, name, snapshot)?;
+        Ok(id)
+    }
+
+    pub fn load(&self, id: SaveSlotId) -> LoadOutcome<GameStateSnapshot> {
+        match self.read_slot(id) {
+            LoadOutcome::Missing => LoadOutcome::Missing,
+            LoadOutcome::Loaded(slot) => LoadOutcome::Loaded(slot.snapshot),
+            LoadOutcome::Restored(slot, err) => LoadOutcome::Restored(slot.snapshot, err),
+            LoadOutcome::Unreadable(err) => LoadOutcome::Unreadable(err),
+            LoadOutcome::TooNew(version) => LoadOutcome::TooNew(version),
+        }
+    }
+
+    pub fn delete(&self, id: SaveSlotId) -> std::io::Result<()> {
+        save_file::remove(&self.slot_path(id))
+    }
+
+    /// Every slot with a file in the saves directory, readable or not
+    fn slot_ids(&self) -> Vec<SaveSlotId> {
+        let Ok(entries) = fs::read_dir(self.data_dir.join("saves")) else {
+            return vec![];
+        };
+        entries
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| Self::parse_slot_file_name(&entry.file_name().to_string_lossy()))
+            .collect()
+    }
+
+    /// Every readable slot, most recently saved first
+    pub fn list(&self) -> Vec<SaveSlotSummary> {
+        let mut summaries: Vec<SaveSlotSummary> = self
+            .slot_ids()
+            .into_iter()
+            .filter_map(|id| {
+                self.read_slot(id)
+                    .value()
+                    .map(|slot| SaveSlotSummary::new(id, &slot))
+            })
+            .collect();
+        summaries.sort_by_key(|s| std::cmp::Reverse(s.saved_at));
+        summaries
+    }
+
+    /// How many slots were saved by a newer version of the game, which `list` leaves out
+    pub fn count_too_new(&self) -> usize {
+        self.slot_ids()
+            .into_iter()
+            .filter(|id| matches!(self.read_slot(*id), LoadOutcome::TooNew(_)))
+            .count()
+    }
+
+    fn parse_slot_file_name(file_name: &str) -> Option<SaveSlotId> {
+        let stem = file_name.strip_suffix(".json")?;
+        if let Some(difficulty) = stem.strip_prefix("auto_") {
+            return Difficulty::all()
+                .into_iter()
+                .find(|d| d.to_string().to_lowercase() == difficulty)
+                .map(SaveSlotId::Auto);
+        }
+        stem.strip_prefix("slot_")?
+            .parse()
+            .ok()
+            .map(SaveSlotId::Named)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{GameBoard, TimerState};
+    use crate::tests::TempDir;
+    use std::sync::Arc;
+
+    fn snapshot(difficulty: Difficulty, seed: u64) -> GameStateSnapshot {
+        let solution = Arc::new(crate::model::Solution::new(difficulty, Some(seed)));
+        GameStateSnapshot::new(GameBoard::new(solution), TimerState::default(), 0, None)
+    }
+
+    #[test]
+    fn test_auto_slots_are_per_difficulty() {
+        let dir = TempDir::new("saves");
+        let manager = SaveSlotManager::new(dir.path().to_path_buf());
+        manager.save_auto(&snapshot(Difficulty::Easy, 1)).unwrap();
+        manager.save_auto(&snapshot(Difficulty::Hard, 2)).unwrap();
+        manager.save_auto(&snapshot(Difficulty::Easy, 3)).unwrap();
+
+        let easy = manager
+            .load(SaveSlotId::Auto(Difficulty::Easy))
+            .value()
+            .unwrap();
+        assert_eq!(easy.board.solution.seed, 3);
+        let hard = manager
+            .load(SaveSlotId::Auto(Difficulty::Hard))
+            .value()
+            .unwrap();
+        assert_eq!(hard.board.solution.seed, 2);
+        assert!(matches!(
+            manager.load(SaveSlotId::Auto(Difficulty::Veteran)),
+            LoadOutcome::Missing
+        ));
+        assert_eq!(manager.list().len(), 2);
+    }
+
+    #[test]
+    fn test_save_as_reuses_slot_with_same_name() {
+        let dir = TempDir::new("saves");
+        let manager = SaveSlotManager::new(dir.path().to_path_buf());
+        let first = manager
+            .save_as("Lunch", &snapshot(Difficulty::Easy, 1))
+            .unwrap();
+        let second = manager
+            .save_as("Commute", &snapshot(Difficulty::Moderate, 2))
+            .unwrap();
+        assert_ne!(first, second);
+        assert_eq!(
+            manager
+                .save_as("Lunch", &snapshot(Difficulty::Hard, 3))
+                .unwrap(),
+            first
+        );
+
+        let summaries = manager.list();
+        assert_eq!(summaries.len(), 2);
+        let lunch = summaries.iter().find(|s| s.name == "Lunch").unwrap();
+        assert_eq!(lunch.difficulty, Difficulty::Hard);
+        assert_eq!(lunch.progress, 0.0);
+        assert_eq!(lunch.thumbnail.len(), Difficulty::Hard.n_rows());
+
+        // deleting also drops the backup left by saving over "Lunch"
+        manager.delete(first).unwrap();
+        assert!(matches!(manager.load(first), LoadOutcome::Missing));
+        assert_eq!(manager.list().len(), 1);
+    }
+
+    #[test]
+    fn test_migrates_legacy_save() {
+        let temp_dir = TempDir::new("saves");
+        let dir = temp_dir.path().to_path_buf();
+        // as written before snapshots were versioned
+        let mut legacy = serde_json::to_value(snapshot(Difficulty::Moderate, 42)).unwrap();
+        legacy.as_object_mut().unwrap().remove("version");
+        fs::write(dir.join("game_state.json"), legacy.to_string()).unwrap();
+
+        let manager = SaveSlotManager::new(dir.clone());
+        let migrated = manager
+            .load(SaveSlotId::Auto(Difficulty::Moderate))
+            .value()
+            .unwrap();
+        assert_eq!(migrated.board.solution.seed, 42);
+        assert!(!dir.join("game_state.json").exists());
+        assert!(manager.take_legacy_save_error().is_none());
+    }
+
+    #[test]
+    fn test_reports_unreadable_legacy_save() {
+        let temp_dir = TempDir::new("saves");
+        let dir = temp_dir.path().to_path_buf();
+        fs::write(dir.join("game_state.json"), "{\"board\": ").unwrap();
+
+        let manager = SaveSlotManager::new(dir.clone());
+        assert!(matches!(
+            manager.take_legacy_save_error(),
+            Some(SaveFileError::Parse(_))
+        ));
+        assert!(manager.take_legacy_save_error().is_none());
+        assert!(save_file::unreadable_path(&dir.join("game_state.json")).exists());
+    }
+
+    #[test]
+    fn test_newer_save_is_left_in_place() {
+        let dir = TempDir::new("saves");
+        let manager = SaveSlotManager::new(dir.path().to_path_buf());
+        let id = manager
+            .save_as("Future", &snapshot(Difficulty::Easy, 1))
+            .unwrap();
+        // as if saved by a version with a newer snapshot format
+        let path = manager.slot_path(id);
+        let mut slot: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        slot["snapshot"]["version"] = 99.into();
+        fs::write(&path, slot.to_string()).unwrap();
+
+        assert!(matches!(manager.load(id), LoadOutcome::TooNew(99)));
+        assert!(manager.list().is_empty());
+        assert_eq!(manager.count_too_new(), 1);
+        assert!(path.exists());
+
+        // nor is it saved over by the next named save
+        let other = manager
+            .save_as("Present", &snapshot(Difficulty::Easy, 2))
+            .unwrap();
+        assert_ne!(other, id);
+        assert!(matches!(manager.load(id), LoadOutcome::TooNew(99)));
+
+        // nor is an automatic slot saved over by the game started in its place
+        let auto = SaveSlotId::Auto(Difficulty::Hard);
+        manager.save_auto(&snapshot(Difficulty::Hard, 3)).unwrap();
+        let auto_path = manager.slot_path(auto);
+        let mut slot: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(&auto_path).unwrap()).unwrap();
+        slot["snapshot"]["version"] = 99.into();
+        let newer = slot.to_string();
+        fs::write(&auto_path, &newer).unwrap();
+
+        for _ in 0..4 {
+            manager.save_auto(&snapshot(Difficulty::Hard, 4)).unwrap();
+        }
+        assert!(matches!(manager.load(auto), LoadOutcome::TooNew(99)));
+        assert_eq!(fs::read_to_string(&auto_path).unwrap(), newer);
+    }
+}
@@ -1,8 +1,8 @@
+use crate::model::save_file::{self, LoadOutcome, SaveFileError};
 use crate::model::Difficulty;
-use glib;
 use serde::{Deserialize, Serialize};
-use std::fs;
-use std::path::PathBuf;
+use std::io;
+use std::path::{Path, PathBuf};
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Settings {
@@ -20,6 +20,14 @@ pub struct Settings {
 
     #[serde(default)]
     pub touch_screen_controls: bool,
+
+    /// Race a ghost of the fastest earlier solve when playing a puzzle again
+    #[serde(default = "default_true")]
+    pub ghost_enabled: bool,
+
+    /// The profile directory the settings were loaded from and are saved to
+    #[serde(skip)]
+    data_dir: Option<PathBuf>,
 }
 
 // Helper functions for default values
@@ -37,40 +45,57 @@ impl Default for Settings {
             clue_tooltips_enabled: true,
             clue_spotlight_enabled: false,
             touch_screen_controls: false,
+            ghost_enabled: true,
             version: 1,
+            data_dir: None,
         }
     }
 }
 
 impl Settings {
-    pub fn load() -> Self {
-        let path = Self::settings_path();
-        if let Ok(contents) = fs::read_to_string(&path) {
-            if let Ok(mut settings) = serde_json::from_str::<Settings>(&contents) {
+    /// The settings kept in a profile's directory
+    pub fn load(data_dir: &Path) -> Self {
+        let path = Self::settings_path(data_dir);
+        let parse = |contents: &str| -> Result<Settings, SaveFileError> {
+            Ok(serde_json::from_str(contents)?)
+        };
+        match save_file::load(&path, &parse) {
+            LoadOutcome::Loaded(mut settings) | LoadOutcome::Restored(mut settings, _) => {
                 settings.migrate();
+                settings.data_dir = Some(data_dir.to_path_buf());
                 return settings;
             }
+            LoadOutcome::Unreadable(err) => {
+                log::error!(target: "settings", "Settings could not be read, using defaults: {}", err);
+            }
+            LoadOutcome::TooNew(version) => {
+                log::error!(target: "settings", "Settings are from a newer version (format {}), using defaults", version);
+            }
+            LoadOutcome::Missing => (),
+        }
+        let default = Settings {
+            data_dir: Some(data_dir.to_path_buf()),
+            ..Settings::default()
+        };
+        if let Err(err) = default.save() {
+            log::error!(target: "settings", "Failed to save settings: {}", err);
         }
-        let default = Settings::default();
-        let _ = default.save();
         default
     }
 
     pub fn save(&self) -> Result<(), std::io::Error> {
-        let path = Self::settings_path();
-        // Ensure the directory exists
-        if let Some(dir) = path.parent() {
-            fs::create_dir_all(dir)?;
-        }
+        let Some(data_dir) = &self.data_dir else {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                "these settings don't belong to a profile",
+            ));
+        };
         let contents = serde_json::to_string(self)?;
-        fs::write(path, contents)
+        save_file::write_atomic(&Self::settings_path(data_dir), &contents)
     }
 
-    fn settings_path() -> PathBuf {
-        let data_dir = glib::user_data_dir();
-        let mut path = data_dir.join("mindhunt");
-        path.push("settings.json");
-        path
+    fn settings_path(data_dir: &Path) -> PathBuf {
+        data_dir.join("settings.json")
     }
 
     fn migrate(&mut self) {
@@ -1,33 +1,59 @@
-use crate::model::{Difficulty, GameStats, GlobalStats};
-use std::collections::HashMap;
+use crate::model::save_file::{self, SaveFileError};
+use crate::model::{DailyStreak, Difficulty, GameStats, GlobalStats};
+use chrono::NaiveDate;
+use serde::de::DeserializeOwned;
+use std::collections::{BTreeSet, HashMap, HashSet};
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+
+/// What importing another machine's stats did
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ImportSummary {
+    pub added: usize,
+    /// Games that were already recorded here, including dailies completed here too
+    pub already_recorded: usize,
+}
+
+/// How many ghosts are kept; the ones saved longest ago make way for new ones
+const MAX_GHOSTS: usize = 500;
 
 #[derive(Debug)]
 pub struct StatsManager {
     data_dir: PathBuf,
     scores: HashMap<Difficulty, Vec<GameStats>>,
     global_stats: HashMap<Difficulty, GlobalStats>,
+    /// Every completed daily puzzle, oldest first; at most one per date and difficulty
+    daily_results: Vec<GameStats>,
+    /// Every recorded game, oldest first. Move logs are only kept with the best times, as
+    /// they'd make this file grow quickly.
+    history: Vec<GameStats>,
 }
 
 impl StatsManager {
-    pub fn new() -> Self {
-        let data_dir = glib::user_data_dir().join("mindhunt");
+    /// The stats kept in a profile's directory
+    pub fn new(data_dir: PathBuf) -> Self {
         if !data_dir.exists() {
             let _ = fs::create_dir_all(&data_dir);
         }
 
-        let mut manager = Self {
-            data_dir,
-            scores: HashMap::new(),
-            global_stats: HashMap::new(),
-        };
+        let mut manager = Self::empty(data_dir);
 
         // Load existing data
         manager.load_all();
         manager
     }
 
+    fn empty(data_dir: PathBuf) -> Self {
+        Self {
+            data_dir,
+            scores: HashMap::new(),
+            global_stats: HashMap::new(),
+            daily_results: Vec::new(),
+            history: Vec::new(),
+        }
+    }
+
     fn scores_path(&self, difficulty: Difficulty) -> PathBuf {
         self.data_dir.join(format!(
             "scores_{}.json",
@@ -42,7 +68,36 @@ impl StatsManager {
         ))
     }
 
+    /// One file per puzzle, holding the fastest solve of it
+    fn ghost_path(&self, difficulty: Difficulty, seed: u64) -> PathBuf {
+        self.data_dir.join("ghosts").join(format!(
+            "{}_{}.json",
+            difficulty.to_string().to_lowercase(),
+            seed
+        ))
+    }
+
+    fn daily_results_path(&self) -> PathBuf {
+        self.data_dir.join("daily_results.json")
+    }
+
+    fn history_path(&self) -> PathBuf {
+        self.data_dir.join("history.json")
+    }
+
+    /// Reads a stats file, falling back to its backups; an unreadable file is set aside and
+    /// counts as empty
+    fn load_file<T: DeserializeOwned>(path: &Path) -> Option<T> {
+        let parse =
+            |contents: &str| -> Result<T, SaveFileError> { Ok(serde_json::from_str(contents)?) };
+        save_file::load(path, &parse).value()
+    }
+
     fn load_all(&mut self) {
+        if let Some(results) = Self::load_file(&self.daily_results_path()) {
+            self.daily_results = results;
+        }
+
         // Initialize empty data for all difficulties
         for difficulty in [
             Difficulty::Easy,
@@ -60,25 +115,49 @@ impl StatsManager {
             );
 
             // Try to load scores
-            if let Ok(contents) = fs::read_to_string(self.scores_path(difficulty)) {
-                if let Ok(scores) = serde_json::from_str(&contents) {
-                    self.scores.insert(difficulty, scores);
-                }
+            if let Some(scores) = Self::load_file(&self.scores_path(difficulty)) {
+                self.scores.insert(difficulty, scores);
             }
 
             // Try to load global stats
-            if let Ok(contents) = fs::read_to_string(self.global_stats_path(difficulty)) {
-                if let Ok(stats) = serde_json::from_str(&contents) {
-                    self.global_stats.insert(difficulty, stats);
-                }
+            if let Some(stats) = Self::load_file(&self.global_stats_path(difficulty)) {
+                self.global_stats.insert(difficulty, stats);
             }
         }
+
+        match Self::load_file(&self.history_path()) {
+            Some(history) => self.history = history,
+            None => self.history = self.history_from_scores(),
+        }
+    }
+
+    /// Stats from before the full history was kept only have the best times and dailies; those
+    /// are the history's start
+    fn history_from_scores(&self) -> Vec<GameStats> {
+        let mut seen = HashSet::new();
+        let mut history: Vec<GameStats> = self
+            .scores
+            .values()
+            .flatten()
+            .chain(self.daily_results.iter())
+            .filter(|stats| seen.insert(stats.playthrough_id))
+            .map(Self::history_record)
+            .collect();
+        history.sort_by_key(|stats| stats.timestamp);
+        history
+    }
+
+    fn history_record(stats: &GameStats) -> GameStats {
+        GameStats {
+            move_log: None,
+            ..stats.clone()
+        }
     }
 
     fn save_scores(&self, difficulty: Difficulty) -> std::io::Result<()> {
         if let Some(scores) = self.scores.get(&difficulty) {
             let contents = serde_json::to_string(scores)?;
-            fs::write(self.scores_path(difficulty), contents)?;
+            save_file::write_atomic(&self.scores_path(difficulty), &contents)?;
         }
         Ok(())
     }
@@ -86,13 +165,86 @@ impl StatsManager {
     fn save_global_stats(&self, difficulty: Difficulty) -> std::io::Result<()> {
         if let Some(stats) = self.global_stats.get(&difficulty) {
             let contents = serde_json::to_string(stats)?;
-            fs::write(self.global_stats_path(difficulty), contents)?;
+            save_file::write_atomic(&self.global_stats_path(difficulty), &contents)?;
+        }
+        Ok(())
+    }
+
+    fn save_daily_results(&self) -> std::io::Result<()> {
+        let contents = serde_json::to_string(&self.daily_results)?;
+        save_file::write_atomic(&self.daily_results_path(), &contents)
+    }
+
+    fn save_history(&self) -> std::io::Result<()> {
+        let contents = serde_json::to_string(&self.history)?;
+        save_file::write_atomic(&self.history_path(), &contents)
+    }
+
+    /// Keeps the fastest solve of each puzzle, move log and all, to race against when it's
+    /// played again. A solve from another generator version is of a different puzzle, so it
+    /// replaces the one kept.
+    fn save_ghost(&self, stats: &GameStats) -> std::io::Result<()> {
+        let (Some(seed), Some(_)) = (stats.seed, &stats.move_log) else {
+            return Ok(());
+        };
+        let path = self.ghost_path(stats.difficulty, seed);
+        if let Some(ghost) = Self::load_file::<GameStats>(&path) {
+            if ghost.generator_version == stats.generator_version
+                && ghost.completion_time <= stats.completion_time
+            {
+                return Ok(());
+            }
+        }
+        let contents = serde_json::to_string(stats)?;
+        save_file::write_atomic(&path, &contents)?;
+        self.evict_ghosts(&path, MAX_GHOSTS)
+    }
+
+    /// Deletes the ghosts saved longest ago, other than `keep`, until at most `limit` are left
+    fn evict_ghosts(&self, keep: &Path, limit: usize) -> std::io::Result<()> {
+        let mut ghosts = fs::read_dir(self.data_dir.join("ghosts"))?
+            .filter_map(|entry| {
+                let path = entry.ok()?.path();
+                if path.extension()? != "json" || path == keep {
+                    return None;
+                }
+                let modified = fs::metadata(&path).ok()?.modified().ok()?;
+                Some((modified, path))
+            })
+            .collect::<Vec<_>>();
+        let excess = (ghosts.len() + 1).saturating_sub(limit);
+        ghosts.sort();
+        for (_, path) in ghosts.into_iter().take(excess) {
+            save_file::remove(&path)?;
         }
         Ok(())
     }
 
+    /// Records a completed game. A daily puzzle only counts the first time it is completed;
+    /// replays of it are ignored, though they can still set a faster ghost.
     pub fn record_game(&mut self, stats: &GameStats) -> std::io::Result<()> {
         let difficulty = stats.difficulty;
+        // the ghost is a nicety; the game still counts without it
+        if let Err(e) = self.save_ghost(stats) {
+            log::error!(target: "stats_manager", "Failed to save ghost: {}", e);
+        }
+
+        if let Some(date) = stats.daily_date {
+            if self.has_daily_result(date, difficulty) {
+                log::info!(
+                    target: "stats_manager",
+                    "Daily {} puzzle for {} already recorded; not counting it again",
+                    difficulty.to_string(),
+                    date
+                );
+                return Ok(());
+            }
+            self.daily_results.push(stats.clone());
+            self.save_daily_results()?;
+        }
+
+        self.history.push(Self::history_record(stats));
+        self.save_history()?;
 
         // Update scores
         let scores = self.scores.entry(difficulty).or_default();
@@ -117,6 +269,112 @@ impl StatsManager {
         Ok(())
     }
 
+    /// Adds the games recorded in another machine's stats directory, or in a JSON export of its
+    /// history, that aren't recorded here yet. Importing the same stats again changes nothing.
+    pub fn import(&mut self, source: &Path) -> Result<ImportSummary, SaveFileError> {
+        let games = if source.is_dir() {
+            Self::read_stats_dir(source)?
+        } else {
+            serde_json::from_str(&fs::read_to_string(source)?)?
+        };
+        Ok(self.merge_games(games)?)
+    }
+
+    fn read_import_file<T: DeserializeOwned>(path: &Path) -> Result<Option<T>, SaveFileError> {
+        if !path.exists() {
+            return Ok(None);
+        }
+        Ok(Some(serde_json::from_str(&fs::read_to_string(path)?)?))
+    }
+
+    /// Every game recorded in a stats directory, read without touching the directory; it may
+    /// be a folder other machines sync to. Games are listed once, with move logs where known.
+    fn read_stats_dir(dir: &Path) -> Result<Vec<GameStats>, SaveFileError> {
+        let other = Self::empty(dir.to_path_buf());
+        let mut games: Vec<GameStats> = Vec::new();
+        for difficulty in [
+            Difficulty::Easy,
+            Difficulty::Moderate,
+            Difficulty::Hard,
+            Difficulty::Veteran,
+        ] {
+            let path = other.scores_path(difficulty);
+            if let Some(scores) = Self::read_import_file::<Vec<GameStats>>(&path)? {
+                games.extend(scores);
+            }
+        }
+        for path in [other.daily_results_path(), other.history_path()] {
+            if let Some(records) = Self::read_import_file::<Vec<GameStats>>(&path)? {
+                games.extend(records);
+            }
+        }
+
+        let mut seen = HashSet::new();
+        games.retain(|game| seen.insert(game.playthrough_id));
+        Ok(games)
+    }
+
+    /// Adds `games` to the history, best times and dailies, skipping games already recorded,
+    /// and recomputes the totals from the merged records
+    fn merge_games(&mut self, games: Vec<GameStats>) -> std::io::Result<ImportSummary> {
+        // games counted in the totals from before the history was kept can't be merged; they
+        // are carried over as they are
+        let difficulties: Vec<Difficulty> = self.global_stats.keys().copied().collect();
+        let untracked: HashMap<Difficulty, GlobalStats> = difficulties
+            .iter()
+            .map(|&difficulty| {
+                let tracked = GlobalStats::from_games(difficulty, &self.history);
+                let untracked = self.get_global_stats(difficulty).saturating_sub(&tracked);
+                (difficulty, untracked)
+            })
+            .collect();
+
+        let mut known: HashSet<Uuid> = self.history.iter().map(|g| g.playthrough_id).collect();
+        let mut summary = ImportSummary {
+            added: 0,
+            already_recorded: 0,
+        };
+        for game in games {
+            let repeated_daily = game
+                .daily_date
+                .is_some_and(|date| self.has_daily_result(date, game.difficulty));
+            if repeated_daily || !known.insert(game.playthrough_id) {
+                summary.already_recorded += 1;
+                continue;
+            }
+            summary.added += 1;
+
+            self.history.push(Self::history_record(&game));
+            if game.daily_date.is_some() {
+                self.daily_results.push(game.clone());
+            }
+            self.scores.entry(game.difficulty).or_default().push(game);
+        }
+        if summary.added == 0 {
+            return Ok(summary);
+        }
+
+        self.history.sort_by_key(|game| game.timestamp);
+        self.daily_results.sort_by_key(|game| game.timestamp);
+        for scores in self.scores.values_mut() {
+            scores.sort_by_key(|g| g.completion_time);
+            scores.truncate(20);
+        }
+        for (difficulty, untracked) in untracked {
+            let tracked = GlobalStats::from_games(difficulty, &self.history);
+            self.global_stats
+                .insert(difficulty, untracked.combined(&tracked));
+        }
+
+        self.save_history()?;
+        self.save_daily_results()?;
+        for difficulty in difficulties {
+            self.save_scores(difficulty)?;
+            self.save_global_stats(difficulty)?;
+        }
+        Ok(summary)
+    }
+
     pub fn get_high_scores(&self, difficulty: Difficulty, limit: usize) -> Vec<GameStats> {
         self.scores
             .get(&difficulty)
@@ -124,6 +382,80 @@ impl StatsManager {
             .unwrap_or_default()
     }
 
+    pub fn has_daily_result(&self, date: NaiveDate, difficulty: Difficulty) -> bool {
+        self.daily_results
+            .iter()
+            .any(|result| result.daily_date == Some(date) && result.difficulty == difficulty)
+    }
+
+    /// The fastest earlier solve of a puzzle, with its move log
+    pub fn get_ghost(
+        &self,
+        difficulty: Difficulty,
+        seed: u64,
+        generator_version: u32,
+    ) -> Option<GameStats> {
+        let is_solve_of_puzzle = |stats: &GameStats| {
+            stats.difficulty == difficulty
+                && stats.seed == Some(seed)
+                && stats.generator_version == Some(generator_version)
+                && stats.move_log.is_some()
+        };
+        Self::load_file::<GameStats>(&self.ghost_path(difficulty, seed))
+            .filter(is_solve_of_puzzle)
+            // best times from before ghosts were kept still have their move logs
+            .or_else(|| {
+                self.scores
+                    .get(&difficulty)?
+                    .iter()
+                    .find(|stats| is_solve_of_puzzle(stats))
+                    .cloned()
+            })
+    }
+
+    /// Every recorded game, oldest first, without move logs
+    pub fn get_history(&self) -> &[GameStats] {
+        &self.history
+    }
+
+    pub fn get_daily_results(&self) -> &[GameStats] {
+        &self.daily_results
+    }
+
+    pub fn get_daily_streak(&self, today: NaiveDate) -> DailyStreak {
+        let dates: BTreeSet<NaiveDate> = self
+            .daily_results
+            .iter()
+            .filter_map(|result| result.daily_date)
+            .collect();
+
+        let mut best = 0;
+        let mut run = 0;
+        let mut previous: Option<NaiveDate> = None;
+        for date in dates.iter() {
+            run = match previous {
+                Some(previous) if previous.succ_opt() == Some(*date) => run + 1,
+                _ => 1,
+            };
+            best = best.max(run);
+            previous = Some(*date);
+        }
+
+        // today's puzzle not being done yet doesn't break the streak
+        let mut day = if dates.contains(&today) {
+            Some(today)
+        } else {
+            today.pred_opt()
+        };
+        let mut current = 0;
+        while let Some(date) = day.filter(|date| dates.contains(date)) {
+            current += 1;
+            day = date.pred_opt();
+        }
+
+        DailyStreak { current, best }
+    }
+
     pub fn get_global_stats(&self, difficulty: Difficulty) -> GlobalStats {
         self.global_stats
             .get(&difficulty)
@@ -134,3 +466,290 @@ impl StatsManager {
             })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::stats_report;
+    use crate::model::{MoveLog, PuzzleSource, GENERATOR_VERSION};
+    use crate::tests::TempDir;
+    use std::time::Duration;
+    use uuid::Uuid;
+
+    fn temp_manager() -> (TempDir, StatsManager) {
+        let dir = TempDir::new("stats");
+        let manager = StatsManager::new(dir.path().to_path_buf());
+        (dir, manager)
+    }
+
+    fn date(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, 1, day).unwrap()
+    }
+
+    fn daily_game(difficulty: Difficulty, daily_date: Option<NaiveDate>) -> GameStats {
+        GameStats {
+            completion_time: Duration::from_secs(60),
+            hints_used: 0,
+            grid_size: difficulty.grid_size(),
+            difficulty,
+            timestamp: 0,
+            playthrough_id: Uuid::new_v4(),
+            difficulty_rating: None,
+            daily_date,
+            move_log: None,
+            seed: Some(1),
+            generator_version: Some(GENERATOR_VERSION),
+            source: Some(PuzzleSource::Standard),
+            puzzle_variant: Some("Standard".to_string()),
+            mistakes: Some(0),
+            undos: Some(0),
+        }
+    }
+
+    #[test]
+    fn test_daily_puzzle_counts_once() {
+        let (_dir, mut manager) = temp_manager();
+        manager
+            .record_game(&daily_game(Difficulty::Easy, Some(date(1))))
+            .unwrap();
+        manager
+            .record_game(&daily_game(Difficulty::Easy, Some(date(1))))
+            .unwrap();
+        manager
+            .record_game(&daily_game(Difficulty::Hard, Some(date(1))))
+            .unwrap();
+
+        assert_eq!(manager.get_daily_results().len(), 2);
+        assert_eq!(
+            manager
+                .get_global_stats(Difficulty::Easy)
+                .total_games_played,
+            1
+        );
+        assert!(manager.has_daily_result(date(1), Difficulty::Easy));
+        assert!(!manager.has_daily_result(date(2), Difficulty::Easy));
+
+        // results survive a restart
+        let reloaded = StatsManager::new(manager.data_dir.clone());
+        assert_eq!(reloaded.get_daily_results().len(), 2);
+    }
+
+    #[test]
+    fn test_daily_streak() {
+        let (_dir, mut manager) = temp_manager();
+        manager
+            .record_game(&daily_game(Difficulty::Easy, None))
+            .unwrap();
+        for day in [1, 2, 3, 4, 7, 8] {
+            manager
+                .record_game(&daily_game(Difficulty::Easy, Some(date(day))))
+                .unwrap();
+        }
+        manager
+            .record_game(&daily_game(Difficulty::Hard, Some(date(8))))
+            .unwrap();
+
+        assert_eq!(
+            manager.get_daily_streak(date(8)),
+            DailyStreak {
+                current: 2,
+                best: 4
+            }
+        );
+        // today still open
+        assert_eq!(manager.get_daily_streak(date(9)).current, 2);
+        assert_eq!(manager.get_daily_streak(date(10)).current, 0);
+    }
+
+    #[test]
+    fn test_history_keeps_every_game() {
+        let (_dir, mut manager) = temp_manager();
+        for _ in 0..25 {
+            let mut stats = daily_game(Difficulty::Easy, None);
+            stats.move_log = Some(MoveLog::new(Default::default()));
+            manager.record_game(&stats).unwrap();
+        }
+        assert_eq!(manager.get_high_scores(Difficulty::Easy, 100).len(), 20);
+        assert_eq!(manager.get_history().len(), 25);
+        assert!(manager
+            .get_history()
+            .iter()
+            .all(|stats| stats.move_log.is_none()));
+
+        let reloaded = StatsManager::new(manager.data_dir.clone());
+        assert_eq!(reloaded.get_history(), manager.get_history());
+    }
+
+    #[test]
+    fn test_keeps_fastest_solve_as_ghost() {
+        let (_dir, mut manager) = temp_manager();
+        let solve = |secs: u64| {
+            let mut stats = daily_game(Difficulty::Easy, None);
+            stats.completion_time = Duration::from_secs(secs);
+            stats.move_log = Some(MoveLog::new(Default::default()));
+            stats
+        };
+        // a random puzzle gets a ghost from its first solve
+        manager.record_game(&solve(90)).unwrap();
+        assert!(manager.ghost_path(Difficulty::Easy, 1).exists());
+        manager.record_game(&solve(60)).unwrap();
+        manager.record_game(&solve(120)).unwrap();
+        let seeded = GameStats {
+            seed: Some(3),
+            source: Some(PuzzleSource::Seeded),
+            ..solve(100)
+        };
+        manager.record_game(&seeded).unwrap();
+        assert!(manager.ghost_path(Difficulty::Easy, 3).exists());
+
+        // found from its own file, not the best times
+        manager.scores.clear();
+        let ghost = manager
+            .get_ghost(Difficulty::Easy, 1, GENERATOR_VERSION)
+            .unwrap();
+        assert_eq!(ghost.completion_time, Duration::from_secs(60));
+        assert!(manager
+            .get_ghost(Difficulty::Easy, 2, GENERATOR_VERSION)
+            .is_none());
+        assert!(manager
+            .get_ghost(Difficulty::Moderate, 1, GENERATOR_VERSION)
+            .is_none());
+    }
+
+    #[test]
+    fn test_evicts_ghosts_saved_longest_ago() {
+        let (_dir, mut manager) = temp_manager();
+        for seed in 1..=3 {
+            let mut stats = daily_game(Difficulty::Easy, None);
+            stats.seed = Some(seed);
+            stats.move_log = Some(MoveLog::new(Default::default()));
+            manager.record_game(&stats).unwrap();
+        }
+        // saved in the order 2, 1, 3
+        let ago = |secs| std::time::SystemTime::now() - Duration::from_secs(secs);
+        for (seed, saved) in [(2, ago(300)), (1, ago(200)), (3, ago(100))] {
+            let path = manager.ghost_path(Difficulty::Easy, seed);
+            let file = fs::File::options().write(true).open(path).unwrap();
+            file.set_modified(saved).unwrap();
+        }
+
+        let kept = manager.ghost_path(Difficulty::Moderate, 1);
+        manager.evict_ghosts(&kept, 3).unwrap();
+        assert!(!manager.ghost_path(Difficulty::Easy, 2).exists());
+        assert!(manager.ghost_path(Difficulty::Easy, 1).exists());
+        assert!(manager.ghost_path(Difficulty::Easy, 3).exists());
+        // the one just saved stays, however old its file looks
+        manager
+            .evict_ghosts(&manager.ghost_path(Difficulty::Easy, 1), 1)
+            .unwrap();
+        assert!(manager.ghost_path(Difficulty::Easy, 1).exists());
+        assert!(!manager.ghost_path(Difficulty::Easy, 3).exists());
+    }
+
+    #[test]
+    fn test_records_game_when_ghost_cannot_be_saved() {
+        let (_dir, mut manager) = temp_manager();
+        // a file where the ghosts directory should be
+        fs::write(manager.data_dir.join("ghosts"), "").unwrap();
+        let mut stats = daily_game(Difficulty::Easy, Some(date(1)));
+        stats.move_log = Some(MoveLog::new(Default::default()));
+
+        manager.record_game(&stats).unwrap();
+        assert_eq!(manager.get_history().len(), 1);
+        assert_eq!(
+            manager
+                .get_global_stats(Difficulty::Easy)
+                .total_games_played,
+            1
+        );
+    }
+
+    #[test]
+    fn test_history_starts_from_older_stats() {
+        let (_dir, mut manager) = temp_manager();
+        manager
+            .record_game(&daily_game(Difficulty::Easy, Some(date(1))))
+            .unwrap();
+        manager
+            .record_game(&daily_game(Difficulty::Hard, None))
+            .unwrap();
+        // as stats were kept before there was a history
+        save_file::remove(&manager.history_path()).unwrap();
+
+        let reloaded = StatsManager::new(manager.data_dir.clone());
+        assert_eq!(reloaded.get_history().len(), 2);
+    }
+
+    #[test]
+    fn test_import_merges_another_machine() {
+        let (_laptop_dir, mut laptop) = temp_manager();
+        let (_desktop_dir, mut desktop) = temp_manager();
+        let shared = daily_game(Difficulty::Easy, None);
+        laptop.record_game(&shared).unwrap();
+        desktop.record_game(&shared).unwrap();
+        desktop
+            .record_game(&daily_game(Difficulty::Easy, None))
+            .unwrap();
+        desktop
+            .record_game(&daily_game(Difficulty::Hard, Some(date(1))))
+            .unwrap();
+
+        let summary = laptop.import(&desktop.data_dir).unwrap();
+        assert_eq!(
+            summary,
+            ImportSummary {
+                added: 2,
+                already_recorded: 1
+            }
+        );
+        assert_eq!(laptop.get_history().len(), 3);
+        assert!(laptop.has_daily_result(date(1), Difficulty::Hard));
+        let easy = laptop.get_global_stats(Difficulty::Easy);
+        assert_eq!(easy.total_games_played, 2);
+        assert_eq!(easy.total_time_played, Duration::from_secs(120));
+
+        // importing again changes nothing
+        assert_eq!(laptop.import(&desktop.data_dir).unwrap().added, 0);
+        assert_eq!(laptop.get_global_stats(Difficulty::Easy), easy);
+
+        let reloaded = StatsManager::new(laptop.data_dir.clone());
+        assert_eq!(reloaded.get_global_stats(Difficulty::Easy), easy);
+    }
+
+    #[test]
+    fn test_import_bundle_keeps_untracked_totals() {
+        let (_dir, mut manager) = temp_manager();
+        manager
+            .record_game(&daily_game(Difficulty::Easy, None))
+            .unwrap();
+        // games counted before the history was kept
+        manager
+            .global_stats
+            .get_mut(&Difficulty::Easy)
+            .unwrap()
+            .total_games_played += 5;
+
+        let other = daily_game(Difficulty::Easy, None);
+        let bundle = manager.data_dir.join("export.json");
+        fs::write(&bundle, stats_report::to_json(&[other.clone()]).unwrap()).unwrap();
+        // a daily already completed here doesn't count twice
+        let mut repeated_daily = daily_game(Difficulty::Easy, Some(date(1)));
+        manager.record_game(&repeated_daily).unwrap();
+        repeated_daily.playthrough_id = Uuid::new_v4();
+        let daily_bundle = manager.data_dir.join("daily.json");
+        fs::write(
+            &daily_bundle,
+            stats_report::to_json(&[repeated_daily]).unwrap(),
+        )
+        .unwrap();
+
+        assert_eq!(manager.import(&bundle).unwrap().added, 1);
+        assert_eq!(manager.import(&daily_bundle).unwrap().already_recorded, 1);
+        assert_eq!(
+            manager
+                .get_global_stats(Difficulty::Easy)
+                .total_games_played,
+            8
+        );
+    }
+}
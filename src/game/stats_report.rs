@@ -0,0 +1,179 @@
+//! Summaries of the game history for the statistics window, and its export.
+
+use std::time::Duration;
+
+use chrono::DateTime;
+
+use crate::model::GameStats;
+
+/// Games whose solve times fall in `start..end`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HistogramBucket {
+    pub start: Duration,
+    pub end: Duration,
+    pub count: usize,
+}
+
+/// A game in the history, with the average solve time of the games up to it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TrendPoint {
+    pub timestamp: i64,
+    pub completion_time: Duration,
+    pub average: Duration,
+}
+
+/// Splits the range from the fastest to the slowest solve into `bucket_count` buckets of whole
+/// seconds and counts the games in each
+pub fn solve_time_histogram(games: &[GameStats], bucket_count: usize) -> Vec<HistogramBucket> {
+    let secs: Vec<u64> = games
+        .iter()
+        .map(|game| game.completion_time.as_secs())
+        .collect();
+    let (Some(&fastest), Some(&slowest)) = (secs.iter().min(), secs.iter().max()) else {
+        return vec![];
+    };
+    let bucket_count = bucket_count.max(1) as u64;
+    // the slowest game has to land in the last bucket, not just past it
+    let width = ((slowest - fastest) / bucket_count + 1).max(1);
+
+    let mut buckets: Vec<HistogramBucket> = (0..bucket_count)
+        .map(|i| HistogramBucket {
+            start: Duration::from_secs(fastest + i * width),
+            end: Duration::from_secs(fastest + (i + 1) * width),
+            count: 0,
+        })
+        .collect();
+    for secs in secs {
+        buckets[((secs - fastest) / width) as usize].count += 1;
+    }
+    buckets
+}
+
+/// Each game in the order played, with the average over it and the `window - 1` games before
+pub fn solve_time_trend(games: &[GameStats], window: usize) -> Vec<TrendPoint> {
+    let mut games: Vec<&GameStats> = games.iter().collect();
+    games.sort_by_key(|game| game.timestamp);
+    let window = window.max(1);
+
+    (0..games.len())
+        .map(|i| {
+            let recent = &games[(i + 1).saturating_sub(window)..=i];
+            let total: Duration = recent.iter().map(|game| game.completion_time).sum();
+            TrendPoint {
+                timestamp: games[i].timestamp,
+                completion_time: games[i].completion_time,
+                average: total / recent.len() as u32,
+            }
+        })
+        .collect()
+}
+
+const CSV_HEADER: &str = "date,difficulty,variant,source,seed,generator_version,time_seconds,hints_used,mistakes,undos,grid_size,rating,daily_date,playthrough_id";
+
+fn optional<T: ToString>(value: Option<T>) -> String {
+    value.map(|value| value.to_string()).unwrap_or_default()
+}
+
+/// One line per game, with a header. Values missing from games recorded by older versions are
+/// left empty.
+pub fn to_csv(games: &[GameStats]) -> String {
+    let mut csv = String::from(CSV_HEADER);
+    csv.push('\n');
+    for game in games {
+        let date = DateTime::from_timestamp(game.timestamp, 0)
+            .map(|date| date.to_rfc3339())
+            .unwrap_or_default();
+        let fields = [
+            date,
+            game.difficulty.to_string(),
+            optional(game.puzzle_variant.as_ref()),
+            optional(game.source),
+            optional(game.seed),
+            optional(game.generator_version),
+            game.completion_time.as_secs().to_string(),
+            game.hints_used.to_string(),
+            optional(game.mistakes),
+            optional(game.undos),
+            game.grid_size.to_string(),
+            optional(game.difficulty_rating),
+            optional(game.daily_date),
+            game.playthrough_id.to_string(),
+        ];
+        csv.push_str(&fields.join(","));
+        csv.push('\n');
+    }
+    csv
+}
+
+pub fn to_json(games: &[GameStats]) -> serde_json::Result<String> {
+    serde_json::to_string_pretty(games)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{Difficulty, PuzzleSource};
+    use uuid::Uuid;
+
+    fn game(secs: u64, timestamp: i64) -> GameStats {
+        GameStats {
+            completion_time: Duration::from_secs(secs),
+            hints_used: 1,
+            grid_size: 6,
+            difficulty: Difficulty::Moderate,
+            timestamp,
+            playthrough_id: Uuid::nil(),
+            difficulty_rating: None,
+            daily_date: None,
+            move_log: None,
+            seed: Some(42),
+            generator_version: Some(2),
+            source: Some(PuzzleSource::Seeded),
+            puzzle_variant: Some("Striping".to_string()),
+            mistakes: Some(3),
+            undos: None,
+        }
+    }
+
+    #[test]
+    fn test_histogram() {
+        let games: Vec<GameStats> = [60, 61, 90, 119, 120]
+            .iter()
+            .map(|secs| game(*secs, 0))
+            .collect();
+        let buckets = solve_time_histogram(&games, 3);
+        assert_eq!(
+            buckets.iter().map(|b| b.count).collect::<Vec<_>>(),
+            vec![2, 1, 2]
+        );
+        assert_eq!(buckets[0].start, Duration::from_secs(60));
+        assert_eq!(buckets[2].end, Duration::from_secs(123));
+        assert!(solve_time_histogram(&[], 3).is_empty());
+
+        // all the same time still makes a usable bucket
+        let buckets = solve_time_histogram(&games[..1], 3);
+        assert_eq!(buckets[0].count, 1);
+    }
+
+    #[test]
+    fn test_trend_averages_recent_games() {
+        let games = vec![game(300, 3), game(100, 1), game(200, 2)];
+        let averages: Vec<u64> = solve_time_trend(&games, 2)
+            .iter()
+            .map(|point| point.average.as_secs())
+            .collect();
+        assert_eq!(averages, vec![100, 150, 250]);
+    }
+
+    #[test]
+    fn test_csv() {
+        let csv = to_csv(&[game(75, 0)]);
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines[0], CSV_HEADER);
+        assert_eq!(
+            lines[1],
+            "1970-01-01T00:00:00+00:00,Moderate,Striping,Seeded,42,2,75,1,3,,6,,,00000000-0000-0000-0000-000000000000"
+        );
+        assert_eq!(lines[1].split(',').count(), CSV_HEADER.split(',').count());
+    }
+}
@@ -8,8 +8,10 @@ pub mod ui;
 
 #[cfg(test)]
 mod tests {
+    use std::path::{Path, PathBuf};
     use std::sync::Once;
     use test_context::TestContext;
+    use uuid::Uuid;
 
     static INIT_LOGGER: Once = Once::new();
 
@@ -32,4 +34,27 @@ mod tests {
             // Perform any teardown you wish.
         }
     }
+
+    /// A new, empty directory under the system temp dir, removed with its contents when dropped
+    pub struct TempDir {
+        path: PathBuf,
+    }
+
+    impl TempDir {
+        pub fn new(name: &str) -> Self {
+            let path = std::env::temp_dir().join(format!("mindhunt-{}-{}", name, Uuid::new_v4()));
+            std::fs::create_dir_all(&path).unwrap();
+            TempDir { path }
+        }
+
+        pub fn path(&self) -> &Path {
+            &self.path
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.path);
+        }
+    }
 }
@@ -17,6 +17,11 @@ const SORT_INDEX_TWO_APART_NOT_MIDDLE: usize = 1;
 const SORT_INDEX_LEFT_OF: usize = 2;
 const SORT_INDEX_TWO_ADJACENT: usize = 3;
 const SORT_INDEX_NOT_ADJACENT: usize = 4;
+const SORT_INDEX_BETWEEN: usize = 5;
+const SORT_INDEX_N_APART: usize = 6;
+const SORT_INDEX_ON_EDGE: usize = 7;
+const SORT_INDEX_NOT_ON_EDGE: usize = 8;
+const SORT_INDEX_LEFT_HALF: usize = 9;
 
 // vert sort index
 const SORT_INDEX_THREE_IN_COLUMN: usize = 0;
@@ -32,6 +37,11 @@ pub enum HorizontalClueType {
     LeftOf,            // A <- B
     TwoAdjacent,       // A next to B
     NotAdjacent,       // A not next to B
+    Between,           // A ... B ... C, either order
+    NApart(usize),     // A exactly N columns from B, either order
+    OnEdge,            // A in the first or last column
+    NotOnEdge,         // A in neither the first nor the last column
+    LeftHalf,          // A left of the middle
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Ord, PartialOrd, Copy)]
@@ -74,7 +84,7 @@ impl<'de> Deserialize<'de> for Clue {
         D: serde::Deserializer<'de>,
     {
         let s = String::deserialize(deserializer)?;
-        Ok(Clue::parse(&s))
+        Clue::try_parse(&s).map_err(serde::de::Error::custom)
     }
 }
 
@@ -101,6 +111,11 @@ impl ClueType {
                 HorizontalClueType::LeftOf => "Left Of".to_string(),
                 HorizontalClueType::TwoAdjacent => "Two Adjacent".to_string(),
                 HorizontalClueType::NotAdjacent => "Not Adjacent".to_string(),
+                HorizontalClueType::Between => "Between".to_string(),
+                HorizontalClueType::NApart(distance) => format!("Exactly {} Apart", distance),
+                HorizontalClueType::OnEdge => "On An Edge".to_string(),
+                HorizontalClueType::NotOnEdge => "Not On An Edge".to_string(),
+                HorizontalClueType::LeftHalf => "In The Left Half".to_string(),
             },
             ClueType::Vertical(vert) => match vert {
                 VerticalClueType::ThreeInColumn => "All In Column".to_string(),
@@ -231,6 +246,62 @@ impl Clue {
         )
     }
 
+    pub fn between(t1: Tile, middle: Tile, t2: Tile) -> Self {
+        Self::new_with_assertions(
+            ClueType::Horizontal(HorizontalClueType::Between),
+            vec![t1, middle, t2]
+                .into_iter()
+                .map(|t| TileAssertion {
+                    tile: t,
+                    assertion: true,
+                })
+                .collect(),
+            SORT_INDEX_BETWEEN,
+        )
+    }
+
+    pub fn n_apart(t1: Tile, t2: Tile, distance: usize) -> Self {
+        assert!(
+            distance >= 2,
+            "Tiles must be at least 2 apart, got {}",
+            distance
+        );
+        Self::new_with_assertions(
+            ClueType::Horizontal(HorizontalClueType::NApart(distance)),
+            vec![t1, t2]
+                .into_iter()
+                .map(|t| TileAssertion {
+                    tile: t,
+                    assertion: true,
+                })
+                .collect(),
+            SORT_INDEX_N_APART,
+        )
+    }
+
+    fn positional(tile: Tile, clue_type: HorizontalClueType, sort_index: usize) -> Self {
+        Self::new_with_assertions(
+            ClueType::Horizontal(clue_type),
+            vec![TileAssertion {
+                tile,
+                assertion: true,
+            }],
+            sort_index,
+        )
+    }
+
+    pub fn on_edge(tile: Tile) -> Self {
+        Self::positional(tile, HorizontalClueType::OnEdge, SORT_INDEX_ON_EDGE)
+    }
+
+    pub fn not_on_edge(tile: Tile) -> Self {
+        Self::positional(tile, HorizontalClueType::NotOnEdge, SORT_INDEX_NOT_ON_EDGE)
+    }
+
+    pub fn left_half(tile: Tile) -> Self {
+        Self::positional(tile, HorizontalClueType::LeftHalf, SORT_INDEX_LEFT_HALF)
+    }
+
     pub fn three_in_column(t1: Tile, t2: Tile, t3: Tile) -> Self {
         assert_ne!(
             t1.row, t2.row,
@@ -389,6 +460,31 @@ impl Clue {
                         self.assertions[1].tile.to_string()
                     )
                 }
+                HorizontalClueType::Between => {
+                    format!(
+                        "<{}...{}...{}>",
+                        self.assertions[0].tile.to_string(),
+                        self.assertions[1].tile.to_string(),
+                        self.assertions[2].tile.to_string()
+                    )
+                }
+                HorizontalClueType::OnEdge => {
+                    format!("<{}@edge>", self.assertions[0].tile.to_string())
+                }
+                HorizontalClueType::NotOnEdge => {
+                    format!("<{}@!edge>", self.assertions[0].tile.to_string())
+                }
+                HorizontalClueType::LeftHalf => {
+                    format!("<{}@left>", self.assertions[0].tile.to_string())
+                }
+                HorizontalClueType::NApart(distance) => {
+                    format!(
+                        "<{}~{}~{}>",
+                        self.assertions[0].tile.to_string(),
+                        distance,
+                        self.assertions[1].tile.to_string()
+                    )
+                }
                 _ => {
                     let assertions = self
                         .assertions
@@ -421,108 +517,163 @@ impl Clue {
         }
     }
 
-    fn parse_horizontal(s: &str) -> Self {
+    fn try_parse_horizontal(s: &str) -> Result<Self, String> {
         let content = s.trim_matches('<').trim_matches('>');
-        if content.contains("...") {
-            let tiles: Vec<_> = content.split("...").collect();
-            assert_eq!(tiles.len(), 2);
-            let left = Tile::parse(tiles[0]);
-            let right = Tile::parse(tiles[1]);
-            Clue::left_of(left, right)
+        if let Some((tile, position)) = content.split_once('@') {
+            let tile = Tile::try_parse(tile)?;
+            match position {
+                "edge" => Ok(Clue::on_edge(tile)),
+                "!edge" => Ok(Clue::not_on_edge(tile)),
+                "left" => Ok(Clue::left_half(tile)),
+                _ => Err(format!(
+                    "Invalid position for horizontal clue: {}",
+                    position
+                )),
+            }
+        } else if content.contains('~') {
+            let parts: Vec<_> = content.split('~').collect();
+            if parts.len() != 3 {
+                return Err(format!("Invalid distance clue: {}", s));
+            }
+            let distance = parts[1]
+                .parse::<usize>()
+                .map_err(|_| format!("Invalid distance: {}", parts[1]))?;
+            if distance < 2 {
+                return Err(format!("Tiles must be at least 2 apart, got {}", distance));
+            }
+            Ok(Clue::n_apart(
+                Tile::try_parse(parts[0])?,
+                Tile::try_parse(parts[2])?,
+                distance,
+            ))
+        } else if content.contains("...") {
+            let tiles = content
+                .split("...")
+                .map(Tile::try_parse)
+                .collect::<Result<Vec<_>, _>>()?;
+            match tiles.len() {
+                2 => Ok(Clue::left_of(tiles[0], tiles[1])),
+                3 => Ok(Clue::between(tiles[0], tiles[1], tiles[2])),
+                _ => Err("Invalid number of tiles for horizontal clue".to_string()),
+            }
         } else {
-            let assertions: Vec<_> = content.split(',').collect();
-            let tile_assertions: Vec<TileAssertion> =
-                assertions.iter().map(|a| TileAssertion::parse(a)).collect();
+            let tile_assertions = content
+                .split(',')
+                .map(TileAssertion::try_parse)
+                .collect::<Result<Vec<_>, _>>()?;
             match tile_assertions.len() {
                 2 => {
                     if tile_assertions[1].is_positive() {
-                        Clue::adjacent(tile_assertions[0].tile, tile_assertions[1].tile)
+                        Ok(Clue::adjacent(
+                            tile_assertions[0].tile,
+                            tile_assertions[1].tile,
+                        ))
                     } else {
-                        Clue::not_adjacent(tile_assertions[0].tile, tile_assertions[1].tile)
+                        Ok(Clue::not_adjacent(
+                            tile_assertions[0].tile,
+                            tile_assertions[1].tile,
+                        ))
                     }
                 }
                 3 => {
                     if tile_assertions[1].is_positive() {
-                        Clue::three_adjacent(
+                        Ok(Clue::three_adjacent(
                             tile_assertions[0].tile,
                             tile_assertions[1].tile,
                             tile_assertions[2].tile,
-                        )
+                        ))
                     } else {
-                        Clue::two_apart_not_middle(
+                        Ok(Clue::two_apart_not_middle(
                             tile_assertions[0].tile,
                             tile_assertions[1].tile,
                             tile_assertions[2].tile,
-                        )
+                        ))
                     }
                 }
-                _ => panic!("Invalid number of assertions for horizontal clue"),
+                _ => Err("Invalid number of assertions for horizontal clue".to_string()),
             }
         }
     }
 
-    fn parse_vertical(s: &str) -> Self {
+    fn check_distinct_rows(tiles: &[Tile]) -> Result<(), String> {
+        for (i, t1) in tiles.iter().enumerate() {
+            if let Some(t2) = tiles[i + 1..].iter().find(|t2| t2.row == t1.row) {
+                return Err(format!("Tiles must be in different rows {} {}", t1, t2));
+            }
+        }
+        Ok(())
+    }
+
+    fn try_parse_vertical(s: &str) -> Result<Self, String> {
         let content = s.trim_matches('|');
         let assertions: Vec<_> = content.split(',').collect();
 
         // Handle one_matches_either case which uses ? notation
         if assertions.iter().any(|a| a.starts_with('?')) {
-            assert_eq!(
-                assertions.len(),
-                3,
-                "One matches either must have exactly 3 assertions"
-            );
-            let tiles: Vec<_> = assertions
+            if assertions.len() != 3 {
+                return Err("One matches either must have exactly 3 assertions".to_string());
+            }
+            let tiles = assertions
                 .iter()
-                .map(|a| TileAssertion::parse(a).tile)
-                .collect();
-            return Clue::one_matches_either(tiles[0], tiles[1], tiles[2]);
+                .map(|a| TileAssertion::try_parse(a).map(|a| a.tile))
+                .collect::<Result<Vec<_>, _>>()?;
+            Clue::check_distinct_rows(&tiles)?;
+            return Ok(Clue::one_matches_either(tiles[0], tiles[1], tiles[2]));
         }
 
         // Parse regular assertions
-        let tile_assertions: Vec<TileAssertion> =
-            assertions.iter().map(|a| TileAssertion::parse(a)).collect();
+        let tile_assertions = assertions
+            .iter()
+            .map(|a| TileAssertion::try_parse(a))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        // Only "not in same column" may relate two tiles from the same row
+        let is_not_in_same_column =
+            tile_assertions.len() == 2 && !tile_assertions.iter().all(|a| a.assertion);
+        if !is_not_in_same_column {
+            let tiles: Vec<_> = tile_assertions.iter().map(|a| a.tile).collect();
+            Clue::check_distinct_rows(&tiles)?;
+        }
 
         // Determine clue type based on number of assertions and their types
         match tile_assertions.len() {
             2 => {
                 if tile_assertions.iter().all(|a| a.assertion) {
-                    Clue::two_in_column(tile_assertions[0].tile, tile_assertions[1].tile)
+                    Ok(Clue::two_in_column(
+                        tile_assertions[0].tile,
+                        tile_assertions[1].tile,
+                    ))
                 } else {
-                    Clue::two_not_in_same_column(tile_assertions[0].tile, tile_assertions[1].tile)
+                    Ok(Clue::two_not_in_same_column(
+                        tile_assertions[0].tile,
+                        tile_assertions[1].tile,
+                    ))
                 }
             }
             3 => {
-                if tile_assertions.iter().all(|a| a.assertion) {
-                    Clue::three_in_column(
-                        tile_assertions[0].tile,
-                        tile_assertions[1].tile,
-                        tile_assertions[2].tile,
-                    )
-                } else {
-                    let positive_tiles: Vec<_> = tile_assertions
-                        .iter()
-                        .filter(|a| a.assertion)
-                        .map(|a| a.tile)
-                        .collect();
-                    let negative_tile = tile_assertions
-                        .iter()
-                        .find(|a| !a.assertion)
-                        .map(|a| a.tile)
-                        .unwrap();
-
-                    Clue::two_in_column_without(positive_tiles[0], negative_tile, positive_tiles[1])
+                let (positive, negative): (Vec<&TileAssertion>, Vec<_>) =
+                    tile_assertions.iter().partition(|a| a.assertion);
+                match (positive.as_slice(), negative.as_slice()) {
+                    ([a, b, c], []) => Ok(Clue::three_in_column(a.tile, b.tile, c.tile)),
+                    ([a, c], [b]) => Ok(Clue::two_in_column_without(a.tile, b.tile, c.tile)),
+                    _ => Err("Vertical clues may have at most one negative assertion".to_string()),
                 }
             }
-            _ => panic!("Invalid number of assertions for vertical clue"),
+            _ => Err("Invalid number of assertions for vertical clue".to_string()),
         }
     }
 
     pub fn parse(s: &str) -> Self {
+        Self::try_parse(s).unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    /// Like [`Clue::parse`], but returns an error for malformed input instead of panicking, for
+    /// clues read from files or typed by a user.
+    pub fn try_parse(s: &str) -> Result<Self, String> {
         if s.starts_with('<') {
-            Clue::parse_horizontal(s)
+            Clue::try_parse_horizontal(s)
         } else {
-            Clue::parse_vertical(s)
+            Clue::try_parse_vertical(s)
         }
     }
 
@@ -742,6 +893,40 @@ impl Clue {
                         self.assertions[1].tile.to_string()
                     )
                 }
+                HorizontalClueType::Between => {
+                    format!(
+                        "{{tile:{}}} is somewhere between {{tile:{}}} and {{tile:{}}} (forward, backward).",
+                        self.assertions[1].tile.to_string(),
+                        self.assertions[0].tile.to_string(),
+                        self.assertions[2].tile.to_string()
+                    )
+                }
+                HorizontalClueType::NApart(distance) => {
+                    format!(
+                        "{{tile:{}}} is exactly {} columns away from {{tile:{}}} (forward, backward).",
+                        self.assertions[0].tile.to_string(),
+                        distance,
+                        self.assertions[1].tile.to_string()
+                    )
+                }
+                HorizontalClueType::OnEdge => {
+                    format!(
+                        "{{tile:{}}} is in the first or last column.",
+                        self.assertions[0].tile.to_string()
+                    )
+                }
+                HorizontalClueType::NotOnEdge => {
+                    format!(
+                        "{{tile:{}}} is in neither the first nor the last column.",
+                        self.assertions[0].tile.to_string()
+                    )
+                }
+                HorizontalClueType::LeftHalf => {
+                    format!(
+                        "{{tile:{}}} is in the left half of the board (never the middle column).",
+                        self.assertions[0].tile.to_string()
+                    )
+                }
             },
             ClueType::Vertical(vert) => match vert {
                 VerticalClueType::ThreeInColumn | VerticalClueType::TwoInColumn => {
@@ -1032,6 +1217,64 @@ mod tests {
         assert_eq!(clue.assertions[1].assertion, false);
         assert_eq!(clue.assertions[2].tile, Tile::new(0, 'c'));
         assert_eq!(clue.assertions[2].assertion, true);
+
+        let clue = Clue::parse("<0a...1b...2c>");
+        assert_eq!(
+            clue.clue_type,
+            ClueType::Horizontal(HorizontalClueType::Between)
+        );
+        assert_eq!(clue.assertions.len(), 3);
+        assert_eq!(clue.assertions[0].tile, Tile::new(0, 'a'));
+        assert_eq!(clue.assertions[1].tile, Tile::new(1, 'b'));
+        assert_eq!(clue.assertions[2].tile, Tile::new(2, 'c'));
+        assert!(clue.assertions.iter().all(|a| a.assertion));
+
+        let clue = Clue::parse("<0a~3~1b>");
+        assert_eq!(
+            clue.clue_type,
+            ClueType::Horizontal(HorizontalClueType::NApart(3))
+        );
+        assert_eq!(clue.assertions.len(), 2);
+        assert_eq!(clue.assertions[0].tile, Tile::new(0, 'a'));
+        assert_eq!(clue.assertions[1].tile, Tile::new(1, 'b'));
+        assert!(clue.assertions.iter().all(|a| a.assertion));
+
+        let clue = Clue::parse("<2c@!edge>");
+        assert_eq!(
+            clue.clue_type,
+            ClueType::Horizontal(HorizontalClueType::NotOnEdge)
+        );
+        assert_eq!(clue.assertions.len(), 1);
+        assert_eq!(clue.assertions[0].tile, Tile::new(2, 'c'));
+        assert_eq!(clue.assertions[0].assertion, true);
+    }
+
+    #[test]
+    fn test_try_parse_rejects_malformed_clues() {
+        assert_eq!(
+            Clue::try_parse("<0a~3~1b>"),
+            Ok(Clue::n_apart(Tile::new(0, 'a'), Tile::new(1, 'b'), 3))
+        );
+        for malformed in [
+            "<0a~1~1b>",
+            "<0a~x~1b>",
+            "<0a~2>",
+            "<0a@middle>",
+            "<+0a>",
+            "<0a...1b...2c...3d>",
+            "<+xa,+1b>",
+            "|+0a,-1b,-2c|",
+            "|+0a,?1b|",
+            "|0a,1b|",
+            "|+0é|",
+            "|+0a,+0b|",
+            "|+0a,+0b,+1c|",
+            "|?0a,?0b,?1c|",
+            "|+0a,-0b,+1c|",
+            "",
+        ] {
+            assert!(Clue::try_parse(malformed).is_err(), "{}", malformed);
+        }
     }
 
     #[test]
@@ -1043,6 +1286,11 @@ mod tests {
             "<+0a,+1b>",
             "<+0a,-1b>",
             "<0a...1b>",
+            "<0a...1b...2c>",
+            "<0a~2~1b>",
+            "<0a@edge>",
+            "<0a@!edge>",
+            "<0a@left>",
             "<+0a,+1b,+2c>",
             "<+0a,-1b,+2c>",
         ] {
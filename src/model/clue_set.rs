@@ -6,7 +6,7 @@ use crate::model::{Clue, ClueOrientation, ClueWithAddress};
 
 use super::ClueAddress;
 
-#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
 pub struct ClueSet {
     horizontal_clues: Vec<ClueWithAddress>,
     vertical_clues: Vec<ClueWithAddress>,
@@ -0,0 +1,47 @@
+//! Daily puzzles: one puzzle per difficulty per calendar day, the same for every player on the
+//! same generator version.
+
+use chrono::{Local, NaiveDate};
+
+use super::Difficulty;
+
+/// The date of today's daily puzzle, by the local clock
+pub fn today() -> NaiveDate {
+    Local::now().date_naive()
+}
+
+/// The seed of the daily puzzle for `date` and `difficulty`: FNV-1a of
+/// `mindhunt-daily:<YYYY-MM-DD>:<difficulty>`. Fixed so that every copy of the game agrees on it.
+pub fn daily_seed(date: NaiveDate, difficulty: Difficulty) -> u64 {
+    let key = format!(
+        "mindhunt-daily:{}:{}",
+        date.format("%Y-%m-%d"),
+        difficulty.to_string()
+    );
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in key.bytes() {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_daily_seed_is_stable() {
+        let date = NaiveDate::from_ymd_opt(2025, 3, 14).unwrap();
+        // pinned: changing it would hand everyone a different puzzle for past days
+        assert_eq!(daily_seed(date, Difficulty::Easy), 0xff0a2e6c6c08ffdb);
+        assert_ne!(
+            daily_seed(date, Difficulty::Easy),
+            daily_seed(date, Difficulty::Hard)
+        );
+        assert_ne!(
+            daily_seed(date, Difficulty::Easy),
+            daily_seed(date.succ_opt().unwrap(), Difficulty::Easy)
+        );
+    }
+}
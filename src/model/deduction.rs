@@ -13,7 +13,10 @@ pub enum DeductionKind {
     Converging,
     // Last remaining tile in a row/column
     LastRemaining,
+    // Variants confined to as many cells as there are variants; other variants leave those cells
     HiddenSet,
+    // Cells that can only hold as many variants as there are cells; those variants leave the rest of the row
+    NakedSet,
 }
 
 impl DeductionKind {
@@ -24,6 +27,7 @@ impl DeductionKind {
             "Converging" => Some(Self::Converging),
             "LastRemaining" => Some(Self::LastRemaining),
             "HiddenSet" => Some(Self::HiddenSet),
+            "NakedSet" => Some(Self::NakedSet),
             _ => None,
         }
     }
@@ -35,6 +39,7 @@ impl DeductionKind {
             Self::Converging => "Converging",
             Self::LastRemaining => "LastRemaining",
             Self::HiddenSet => "HiddenSet",
+            Self::NakedSet => "NakedSet",
         }
     }
 }
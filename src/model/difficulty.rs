@@ -86,4 +86,9 @@ impl Difficulty {
             Difficulty::Veteran => 16,
         }
     }
+
+    /// After generating clues, try to drop or weaken each one while the puzzle stays solvable
+    pub fn minimize_clues(&self) -> bool {
+        matches!(self, Difficulty::Veteran)
+    }
 }
@@ -0,0 +1,20 @@
+use serde::{Deserialize, Serialize};
+
+/// How hard a specific puzzle is to solve, measured by replaying the hint solver over it.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DifficultyRating {
+    /// Overall score; higher is harder. Only comparable between puzzles of the same grid size.
+    pub score: u32,
+    /// Number of hint steps needed to solve the puzzle
+    pub steps: u32,
+    /// Total number of clues examined across all steps before one yielded a deduction
+    pub clues_scanned: u32,
+    pub constraint_deductions: u32,
+    pub converging_deductions: u32,
+    pub last_remaining_deductions: u32,
+    pub hidden_set_deductions: u32,
+    #[serde(default)]
+    pub naked_set_deductions: u32,
+    /// Whether the solver reached a complete board
+    pub solved: bool,
+}
@@ -1,3 +1,5 @@
+use chrono::NaiveDate;
+
 use super::{ClueAddress, Difficulty, GameStateSnapshot};
 
 #[derive(Debug, Clone)]
@@ -8,7 +10,9 @@ pub enum GameActionEvent {
     ClueToggleSelectedComplete,
     ClueFocus(Option<ClueAddress>), // clue_idx when Some
     ClueFocusNext(i32),
-    NewGame(Difficulty, Option<u64>), // grid rows, grid columns
+    NewGame(Difficulty, Option<u64>),     // grid rows, grid columns
+    RegenerateGame(Difficulty, u64, u32), // seed, generator version; recreates an older puzzle exactly
+    NewDailyGame(Difficulty, NaiveDate),
     InitDisplay,
     CompletePuzzle,
     Solve,
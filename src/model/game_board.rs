@@ -4,15 +4,18 @@ use super::{
 };
 use crate::model::tile_assertion::TileAssertion;
 use crate::model::{Candidate, Deduction, PartialSolution, Tile};
-use std::{collections::HashSet, rc::Rc};
+use std::{collections::HashSet, sync::Arc};
 
-#[derive(Clone, serde::Serialize, serde::Deserialize)]
+/// The available candidates and selections of a row, by column
+pub(crate) type RowState = ([u8; MAX_GRID_SIZE], [Option<char>; MAX_GRID_SIZE]);
+
+#[derive(Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
 pub struct GameBoard {
     candidates: [[u8; MAX_GRID_SIZE]; MAX_GRID_SIZE],
     resolved_candidates: [[u8; MAX_GRID_SIZE]; MAX_GRID_SIZE],
     selected: [[Option<char>; MAX_GRID_SIZE]; MAX_GRID_SIZE],
-    pub solution: Rc<Solution>,
-    pub clue_set: Rc<ClueSet>,
+    pub solution: Arc<Solution>,
+    pub clue_set: Arc<ClueSet>,
     pub completed_clues: HashSet<ClueAddress>,
 }
 
@@ -59,8 +62,8 @@ impl Default for GameBoard {
         let candidates = [[0xFF; MAX_GRID_SIZE]; MAX_GRID_SIZE];
         let resolved_candidates = [[0x00; MAX_GRID_SIZE]; MAX_GRID_SIZE];
         let selected = std::array::from_fn(|_| std::array::from_fn(|_| None));
-        let solution = Rc::new(Solution::default());
-        let clue_set = Rc::new(ClueSet::new(vec![]));
+        let solution = Arc::new(Solution::default());
+        let clue_set = Arc::new(ClueSet::new(vec![]));
         let completed_clues = HashSet::new();
 
         Self {
@@ -75,7 +78,7 @@ impl Default for GameBoard {
 }
 
 impl GameBoard {
-    pub fn new(solution: Rc<Solution>) -> Self {
+    pub fn new(solution: Arc<Solution>) -> Self {
         let candidates = [[0xFF; MAX_GRID_SIZE]; MAX_GRID_SIZE];
         let resolved_candidates = [[0x00; MAX_GRID_SIZE]; MAX_GRID_SIZE];
         let selected = std::array::from_fn(|_| std::array::from_fn(|_| None));
@@ -85,7 +88,7 @@ impl GameBoard {
             resolved_candidates,
             selected,
             solution,
-            clue_set: Rc::new(ClueSet::new(vec![])),
+            clue_set: Arc::new(ClueSet::new(vec![])),
             completed_clues: HashSet::new(),
         };
         board.recompute_resolved();
@@ -158,6 +161,11 @@ impl GameBoard {
         variants
     }
 
+    /// Everything a clue's deductions about `row` depend on
+    pub(crate) fn row_state(&self, row: usize) -> RowState {
+        (self.resolved_candidates[row], self.selected[row])
+    }
+
     pub fn is_candidate_available(&self, row: usize, col: usize, variant: char) -> bool {
         let variant_idx = Tile::variant_to_usize(variant);
         (self.resolved_candidates[row][col] & (1 << variant_idx)) != 0
@@ -254,7 +262,7 @@ impl GameBoard {
     }
 
     #[cfg(test)]
-    pub fn parse(input: &str, solution: Rc<Solution>) -> Self {
+    pub fn parse(input: &str, solution: Arc<Solution>) -> Self {
         let mut selected: [[Option<char>; MAX_GRID_SIZE]; MAX_GRID_SIZE] =
             std::array::from_fn(|_| std::array::from_fn(|_| None));
         let mut candidates = [[0xFF; MAX_GRID_SIZE]; MAX_GRID_SIZE];
@@ -298,14 +306,14 @@ impl GameBoard {
             selected,
             candidates,
             resolved_candidates,
-            clue_set: Rc::new(ClueSet::new(vec![])),
+            clue_set: Arc::new(ClueSet::new(vec![])),
             completed_clues: HashSet::new(),
         };
         board.recompute_resolved();
         board
     }
 
-    pub fn set_clues(&mut self, clues: Rc<ClueSet>) {
+    pub fn set_clues(&mut self, clues: Arc<ClueSet>) {
         self.clue_set = clues;
     }
 
@@ -394,8 +402,27 @@ impl GameBoard {
         true
     }
 
+    /// How much of the board is worked out, 0 to 100: the share of wrong candidates that have
+    /// been ruled out, counting those ruled out by a selection
+    pub fn completion_percent(&self) -> u8 {
+        let n_variants = self.solution.n_variants;
+        let to_eliminate = self.solution.n_rows * n_variants * n_variants.saturating_sub(1);
+        if to_eliminate == 0 {
+            return 0;
+        }
+        let mask = (1u16 << n_variants) - 1;
+        let mut eliminated = 0;
+        for row in 0..self.solution.n_rows {
+            for col in 0..n_variants {
+                let available = (self.resolved_candidates[row][col] as u16 & mask).count_ones();
+                eliminated += n_variants.saturating_sub(available.max(1) as usize);
+            }
+        }
+        (eliminated * 100 / to_eliminate) as u8
+    }
+
     /// Checks if the game board is fully solved.
-    pub(crate) fn is_complete(&self) -> bool {
+    pub fn is_complete(&self) -> bool {
         for row in 0..self.solution.n_rows {
             for col in 0..self.solution.n_variants {
                 if self.selected[row][col].is_none() {
@@ -406,7 +433,7 @@ impl GameBoard {
         true
     }
 
-    pub(crate) fn apply_deductions(&mut self, deductions: &[Deduction]) {
+    pub fn apply_deductions(&mut self, deductions: &[Deduction]) {
         for deduction in deductions {
             self.apply_deduction(deduction);
         }
@@ -433,7 +460,7 @@ impl GameBoard {
     }
 
     /// Check if the board is incorrect. Returns false for boards that are not complete, but have no errors.
-    pub(crate) fn is_incorrect(&self) -> bool {
+    pub fn is_incorrect(&self) -> bool {
         for row in 0..self.solution.n_rows {
             for col in 0..self.solution.n_variants {
                 let solution_tile = self.solution.get(row, col);
@@ -496,11 +523,11 @@ impl GameBoard {
 
 #[cfg(test)]
 mod tests {
-    use crate::model::{CandidateState, Difficulty};
+    use crate::model::{CandidateState, Difficulty, GENERATOR_VERSION};
 
     use super::*;
 
-    fn create_test_solution() -> Rc<Solution> {
+    fn create_test_solution() -> Arc<Solution> {
         let mut grid = [['0'; MAX_GRID_SIZE]; MAX_GRID_SIZE];
         // Fill first 4x4 of grid with test data
         for row in 0..4 {
@@ -509,7 +536,7 @@ mod tests {
             }
         }
 
-        Rc::new(Solution {
+        Arc::new(Solution {
             variants: vec!['a', 'b', 'c', 'd'],
             grid,
             n_rows: 4,
@@ -517,6 +544,7 @@ mod tests {
             variants_range: 'a'..='d',
             difficulty: Difficulty::Easy,
             seed: 0,
+            generator_version: GENERATOR_VERSION,
         })
     }
 
@@ -606,6 +634,32 @@ mod tests {
         check_cell(3, 3, 'd');
     }
 
+    #[test]
+    fn test_completion_percent() {
+        let solution = create_test_solution();
+        assert_eq!(GameBoard::new(solution.clone()).completion_percent(), 0);
+
+        // each selection rules out the rest of its cell and itself elsewhere in the row
+        let input = "\
+0|<A>|abcd|abcd|abcd|
+-----------------
+1|abcd|<B>|abcd|abcd|
+-----------------
+2|abcd|abcd|<C>|abcd|
+-----------------
+3|abcd|abcd|abcd|<D>|";
+        let board = GameBoard::parse(input, solution.clone());
+        assert_eq!(board.completion_percent(), 50);
+
+        let mut board = GameBoard::new(solution.clone());
+        for row in 0..4 {
+            for col in 0..4 {
+                board.select_tile_at_position(col, solution.get(row, col));
+            }
+        }
+        assert_eq!(board.completion_percent(), 100);
+    }
+
     #[test]
     fn test_auto_solve_row_simple() {
         let input = "\
@@ -1,6 +1,9 @@
-use super::{ClueSet, ClueWithAddress, Deduction, Difficulty, TimerState};
+use super::{
+    ClueSet, ClueWithAddress, Deduction, Difficulty, DifficultyRating, GenerationProgress,
+    NakedSet, TimerState,
+};
 use crate::model::{GameBoard, GameStats};
-use std::rc::Rc;
+use std::sync::Arc;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct ClueSelection {
@@ -27,13 +30,19 @@ pub enum GameStateEvent {
         vertical_hidden_tiles: Vec<usize>,
     },
     CellHintHighlight(Deduction),
+    NakedSetHintHighlight(NakedSet),
     HintUsageChanged(u32),
+    DifficultyRatingChanged(DifficultyRating),
     TimerStateChanged(TimerState),
     PuzzleSubmissionReadyChanged(bool),
     PuzzleCompleted(PuzzleCompletionState),
     ClueHintHighlight(Option<ClueWithAddress>),
-    ClueSetUpdate(Rc<ClueSet>, Difficulty),
+    ClueSetUpdate(Arc<ClueSet>, Difficulty),
     ClueSelected(Option<ClueSelection>),
+    /// A new puzzle is being generated in the background; sent when it starts and as clues are placed
+    GenerationProgress(GenerationProgress),
+    /// Background generation stopped, either because the puzzle is ready or it was cancelled
+    GenerationFinished,
 }
 
 impl GameStateEvent {}
@@ -1,84 +1,289 @@
+use chrono::NaiveDate;
 use log::trace;
 
-use crate::model::{GameBoard, Solution};
-use crate::solver::clue_generator::ClueGeneratorResult;
-use crate::solver::generate_clues;
-use std::path::PathBuf;
+use crate::model::{
+    DifficultyRating, GameBoard, GenerationProgress, MoveLog, PuzzleSource, Solution,
+};
+use crate::solver::clue_generator::{generate_clues_cancellable, ClueGeneratorResult};
+use crate::solver::difficulty_rater::rate_puzzle;
+use crate::solver::uniqueness_verifier::is_uniquely_solvable;
+use serde_json::Value;
+use std::path::Path;
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
 use std::time::SystemTime;
-use std::{fs, rc::Rc};
 
-use super::{Difficulty, TimerState};
+use super::save_file::{self, LoadOutcome, SaveFileError};
+use super::{Difficulty, TimerState, GENERATOR_VERSION, UNKNOWN_GENERATOR_VERSION};
+
+/// Bumped whenever the saved layout changes; `migrate` brings older saves up to date
+pub const SNAPSHOT_VERSION: u32 = 3;
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct GameStateSnapshot {
+    pub version: u32,
     pub board: GameBoard,
     pub timer_state: TimerState,
     pub hints_used: u32,
+    /// Absent in saves from older versions; recomputed on load
+    #[serde(default)]
+    pub difficulty_rating: Option<DifficultyRating>,
+    /// The generator that made the puzzle
+    pub generator_version: u32,
+    /// Set when the puzzle is the daily puzzle of that day
+    #[serde(default)]
+    pub daily_date: Option<NaiveDate>,
+    /// Called `variant` by the version that added it
+    #[serde(alias = "variant")]
+    pub source: PuzzleSource,
+    /// The name of the generator's puzzle variant; absent for imported puzzles and saves from
+    /// older versions
+    #[serde(default)]
+    pub puzzle_variant: Option<String>,
+    /// Moves so far that put a tile or elimination at odds with the solution
+    pub mistakes: u32,
+    /// The moves made so far; absent for fresh puzzles and saves from older versions
+    #[serde(default)]
+    pub move_log: Option<MoveLog>,
+}
+
+/// Upgrades a saved snapshot one version at a time, as `Settings::migrate` does
+fn migrate(value: &mut Value) -> Result<(), SaveFileError> {
+    let Some(fields) = value.as_object_mut() else {
+        // not a snapshot at all; deserializing reports what's wrong
+        return Ok(());
+    };
+    loop {
+        // saves from before snapshots were versioned have no version field
+        let version = fields
+            .get("version")
+            .and_then(Value::as_u64)
+            .map_or(1, |v| v as u32);
+        match version {
+            1 => {
+                // the generator wasn't recorded before there was more than one, and the one in
+                // use then can't be run any more
+                fields
+                    .entry("generator_version")
+                    .or_insert(UNKNOWN_GENERATOR_VERSION.into());
+                fields.insert("version".to_string(), 2.into());
+            }
+            2 => {
+                // where the puzzle came from wasn't recorded, apart from daily puzzles
+                let is_daily = fields.get("daily_date").is_some_and(|date| !date.is_null());
+                let source = if is_daily {
+                    PuzzleSource::Daily
+                } else {
+                    PuzzleSource::Standard
+                };
+                fields.insert("source".to_string(), serde_json::to_value(source)?);
+                fields.insert("mistakes".to_string(), 0.into());
+                fields.insert("version".to_string(), 3.into());
+            }
+            SNAPSHOT_VERSION => return Ok(()),
+            newer => return Err(SaveFileError::UnsupportedVersion(newer)),
+        }
+    }
 }
 
-fn game_state_path() -> PathBuf {
-    let data_dir = glib::user_data_dir();
-    let mut path = data_dir.join("mindhunt");
-    path.push("game_state.json");
-    path
+/// One generation worker per available core
+fn generation_threads() -> usize {
+    std::thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(1)
 }
 
 impl GameStateSnapshot {
-    pub fn new(board: GameBoard, timer_state: TimerState, hints_used: u32) -> Self {
+    pub fn new(
+        board: GameBoard,
+        timer_state: TimerState,
+        hints_used: u32,
+        difficulty_rating: Option<DifficultyRating>,
+    ) -> Self {
         let paused_timer_state = if timer_state.is_paused() {
             timer_state.paused(SystemTime::now())
         } else {
             timer_state
         };
+        let generator_version = board.solution.generator_version;
         Self {
+            version: SNAPSHOT_VERSION,
             board,
             timer_state: paused_timer_state,
             hints_used,
+            difficulty_rating,
+            generator_version,
+            daily_date: None,
+            source: PuzzleSource::default(),
+            puzzle_variant: None,
+            mistakes: 0,
+            move_log: None,
         }
     }
 
     pub fn generate_new(difficulty: Difficulty, seed: Option<u64>) -> Self {
-        let solution = Rc::new(Solution::new(difficulty, seed));
+        Self::generate_new_cancellable(
+            difficulty,
+            seed,
+            GENERATOR_VERSION,
+            &AtomicBool::new(false),
+            &|_| {},
+        )
+        .expect("generation is never cancelled")
+    }
+
+    /// Generates a new puzzle as `generator_version` did, reporting progress as clues are placed.
+    /// Returns `None` if `cancelled` is set before the puzzle is ready.
+    pub fn generate_new_cancellable(
+        difficulty: Difficulty,
+        seed: Option<u64>,
+        generator_version: u32,
+        cancelled: &AtomicBool,
+        on_progress: &dyn Fn(GenerationProgress),
+    ) -> Option<Self> {
+        let solution = Arc::new(Solution::new_with_generator(
+            difficulty,
+            seed,
+            generator_version,
+        ));
         trace!(target: "game_state", "Generated solution: {:?}", solution);
-        let blank_board = GameBoard::new(Rc::clone(&solution));
+        let blank_board = GameBoard::new(Arc::clone(&solution));
         let ClueGeneratorResult {
             clues: _,
             board,
             revealed_tiles: _,
-        } = generate_clues(&blank_board);
+            variant_type,
+        } = generate_clues_cancellable(&blank_board, generation_threads(), cancelled, on_progress)?;
+        let difficulty_rating = rate_puzzle(&board);
 
-        Self::new(board, TimerState::default(), 0)
+        let mut snapshot = Self::new(board, TimerState::default(), 0, Some(difficulty_rating));
+        snapshot.puzzle_variant = Some(variant_type.to_string());
+        Some(snapshot)
     }
 
-    pub fn save(&self) -> bool {
-        save_game_state_snapshot(self)
+    /// The puzzle as first presented: its clues plus the correctly placed tiles. Candidate
+    /// eliminations are dropped, as they may be mistaken.
+    fn puzzle_board(&self) -> GameBoard {
+        let solution = &self.board.solution;
+        let mut board = GameBoard::new(Arc::clone(solution));
+        board.set_clues(Arc::clone(&self.board.clue_set));
+        for row in 0..solution.n_rows {
+            for col in 0..solution.n_variants {
+                if let Some(tile) = self.board.get_selection(row, col) {
+                    if tile == solution.get(row, col) {
+                        board.select_tile_at_position(col, tile);
+                    }
+                }
+            }
+        }
+        board
     }
-}
 
-fn save_game_state_snapshot(game_state: &GameStateSnapshot) -> bool {
-    let path = game_state_path();
-    if let Some(dir) = path.parent() {
-        if let Err(_) = fs::create_dir_all(dir) {
-            return false;
-        }
+    /// Checks that the puzzle's clues, together with the correctly placed tiles, lead to exactly
+    /// the stored solution.
+    pub fn has_unique_solution(&self) -> bool {
+        is_uniquely_solvable(&self.puzzle_board())
     }
-    match serde_json::to_string(game_state) {
-        Ok(contents) => {
-            if fs::write(path, contents).is_ok() {
-                return true;
-            }
-        }
-        Err(_) => return false,
+
+    /// Reads a snapshot saved by this or an earlier version of the game
+    pub fn from_json_value(mut value: Value) -> Result<Self, SaveFileError> {
+        migrate(&mut value)?;
+        Ok(serde_json::from_value(value)?)
+    }
+
+    pub fn from_json(contents: &str) -> Result<Self, SaveFileError> {
+        Self::from_json_value(serde_json::from_str(contents)?)
+    }
+
+    /// Returns the stored rating, rating the puzzle now if the save predates ratings.
+    pub fn difficulty_rating(&self) -> DifficultyRating {
+        self.difficulty_rating
+            .clone()
+            .unwrap_or_else(|| rate_puzzle(&self.puzzle_board()))
     }
-    false
 }
 
-pub fn load_game_state_snapshot() -> Option<GameStateSnapshot> {
-    let path = game_state_path();
-    if let Ok(contents) = fs::read_to_string(&path) {
-        if let Ok(game_state) = serde_json::from_str::<GameStateSnapshot>(&contents) {
-            return Some(game_state);
-        }
+pub fn load_game_state_snapshot(path: &Path) -> LoadOutcome<GameStateSnapshot> {
+    save_file::load(path, &GameStateSnapshot::from_json)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{is_supported_generator, GameBoard};
+
+    fn snapshot_json() -> Value {
+        let solution = Arc::new(Solution::new(Difficulty::Easy, Some(7)));
+        let snapshot =
+            GameStateSnapshot::new(GameBoard::new(solution), TimerState::default(), 2, None);
+        serde_json::to_value(&snapshot).unwrap()
+    }
+
+    #[test]
+    fn test_migrates_unversioned_snapshot() {
+        let mut value = snapshot_json();
+        let fields = value.as_object_mut().unwrap();
+        fields.remove("version");
+        fields.remove("generator_version");
+        fields.remove("source");
+        fields.remove("mistakes");
+        fields["board"]["solution"]
+            .as_object_mut()
+            .unwrap()
+            .remove("generator_version");
+
+        let snapshot = GameStateSnapshot::from_json(&value.to_string()).unwrap();
+        assert_eq!(snapshot.version, SNAPSHOT_VERSION);
+        assert_eq!(snapshot.generator_version, UNKNOWN_GENERATOR_VERSION);
+        assert_eq!(
+            snapshot.board.solution.generator_version,
+            UNKNOWN_GENERATOR_VERSION
+        );
+        assert!(!is_supported_generator(snapshot.generator_version));
+        assert_eq!(snapshot.hints_used, 2);
+        assert_eq!(snapshot.source, PuzzleSource::Standard);
+    }
+
+    #[test]
+    fn test_migrates_daily_snapshot_variant() {
+        let mut value = snapshot_json();
+        let fields = value.as_object_mut().unwrap();
+        fields.insert("version".to_string(), 2.into());
+        fields.insert("daily_date".to_string(), "2025-01-01".into());
+        fields.remove("source");
+        fields.remove("mistakes");
+
+        let snapshot = GameStateSnapshot::from_json(&value.to_string()).unwrap();
+        assert_eq!(snapshot.source, PuzzleSource::Daily);
+        assert_eq!(snapshot.mistakes, 0);
+    }
+
+    #[test]
+    fn test_reads_source_saved_as_variant() {
+        let mut value = snapshot_json();
+        let fields = value.as_object_mut().unwrap();
+        fields.remove("source");
+        fields.remove("puzzle_variant");
+        fields.insert("variant".to_string(), "Seeded".into());
+
+        let snapshot = GameStateSnapshot::from_json(&value.to_string()).unwrap();
+        assert_eq!(snapshot.source, PuzzleSource::Seeded);
+        assert_eq!(snapshot.puzzle_variant, None);
+    }
+
+    #[test]
+    fn test_generated_snapshot_names_its_variant() {
+        let snapshot = GameStateSnapshot::generate_new(Difficulty::Easy, Some(7));
+        assert!(snapshot.puzzle_variant.is_some());
+    }
+
+    #[test]
+    fn test_rejects_newer_snapshot() {
+        let mut value = snapshot_json();
+        value["version"] = (SNAPSHOT_VERSION + 1).into();
+        assert!(matches!(
+            GameStateSnapshot::from_json(&value.to_string()),
+            Err(SaveFileError::UnsupportedVersion(v)) if v == SNAPSHOT_VERSION + 1
+        ));
     }
-    None
 }
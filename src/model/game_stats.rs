@@ -1,4 +1,5 @@
-use crate::model::Difficulty;
+use crate::model::{Difficulty, MoveLog, PuzzleSource};
+use chrono::NaiveDate;
 use serde::{Deserialize, Serialize};
 use std::time::Duration;
 use uuid::Uuid;
@@ -11,12 +12,93 @@ pub struct GameStats {
     pub difficulty: Difficulty,
     pub timestamp: i64,
     pub playthrough_id: Uuid,
+    /// Score from the puzzle's difficulty rating; absent for games recorded before ratings
+    #[serde(default)]
+    pub difficulty_rating: Option<u32>,
+    /// The day whose daily puzzle this game was; `None` for ordinary games
+    #[serde(default)]
+    pub daily_date: Option<NaiveDate>,
+    /// Everything that happened during the game, for replays; absent for older games
+    #[serde(default)]
+    pub move_log: Option<MoveLog>,
+    /// The puzzle's seed and the generator that made it from the seed; the fields below are
+    /// absent for games recorded before they were tracked
+    #[serde(default)]
+    pub seed: Option<u64>,
+    #[serde(default)]
+    pub generator_version: Option<u32>,
+    /// Called `variant` by the version that added it
+    #[serde(default, alias = "variant")]
+    pub source: Option<PuzzleSource>,
+    /// The name of the generator's puzzle variant; also absent for imported puzzles
+    #[serde(default)]
+    pub puzzle_variant: Option<String>,
+    /// Moves that put a tile or elimination at odds with the solution
+    #[serde(default)]
+    pub mistakes: Option<u32>,
+    #[serde(default)]
+    pub undos: Option<u32>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+/// Consecutive days with a completed daily puzzle, at any difficulty
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DailyStreak {
+    /// Days up to today, or up to yesterday while today's puzzle is still open
+    pub current: u32,
+    pub best: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq, Eq)]
 pub struct GlobalStats {
     pub difficulty: Difficulty,
     pub total_games_played: u32,
     pub total_time_played: Duration,
     pub total_hints_used: u32,
 }
+
+impl GlobalStats {
+    /// Totals over the games in `games` played at `difficulty`
+    pub fn from_games<'a>(
+        difficulty: Difficulty,
+        games: impl IntoIterator<Item = &'a GameStats>,
+    ) -> Self {
+        games
+            .into_iter()
+            .filter(|game| game.difficulty == difficulty)
+            .fold(
+                GlobalStats {
+                    difficulty,
+                    ..Default::default()
+                },
+                |mut totals, game| {
+                    totals.total_games_played += 1;
+                    totals.total_time_played += game.completion_time;
+                    totals.total_hints_used += game.hints_used;
+                    totals
+                },
+            )
+    }
+
+    /// What these totals count beyond `other`'s, never below zero
+    pub fn saturating_sub(&self, other: &GlobalStats) -> Self {
+        GlobalStats {
+            difficulty: self.difficulty,
+            total_games_played: self
+                .total_games_played
+                .saturating_sub(other.total_games_played),
+            total_time_played: self
+                .total_time_played
+                .saturating_sub(other.total_time_played),
+            total_hints_used: self.total_hints_used.saturating_sub(other.total_hints_used),
+        }
+    }
+
+    pub fn combined(&self, other: &GlobalStats) -> Self {
+        GlobalStats {
+            difficulty: self.difficulty,
+            total_games_played: self.total_games_played + other.total_games_played,
+            total_time_played: self.total_time_played + other.total_time_played,
+            total_hints_used: self.total_hints_used + other.total_hints_used,
+        }
+    }
+}
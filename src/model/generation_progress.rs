@@ -0,0 +1,30 @@
+use super::Difficulty;
+
+/// How far along a puzzle being generated in the background is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GenerationProgress {
+    pub difficulty: Difficulty,
+    pub clues_placed: usize,
+    /// Tiles the clues placed so far pin down
+    pub tiles_solved: usize,
+    pub total_tiles: usize,
+}
+
+impl GenerationProgress {
+    pub fn new(difficulty: Difficulty) -> Self {
+        Self {
+            difficulty,
+            clues_placed: 0,
+            tiles_solved: 0,
+            total_tiles: difficulty.n_rows() * difficulty.n_cols(),
+        }
+    }
+
+    /// Fraction of the board solved by the clues placed so far, between 0 and 1
+    pub fn completeness(&self) -> f64 {
+        if self.total_tiles == 0 {
+            return 0.0;
+        }
+        self.tiles_solved as f64 / self.total_tiles as f64
+    }
+}
@@ -4,17 +4,27 @@ mod clue;
 mod clue_address;
 mod clue_orientation;
 mod clue_set;
+pub mod daily_puzzle;
 mod deduction;
 mod difficulty;
+mod difficulty_rating;
 mod game_action_event;
 mod game_board;
 mod game_state_event;
 pub mod game_state_snapshot;
 mod game_stats;
+mod generation_progress;
 mod global_event;
 mod input_event;
 mod layout;
+mod move_log;
+mod naked_set;
 mod partial_solution;
+pub mod puzzle_file;
+mod puzzle_rng;
+mod puzzle_source;
+pub mod save_file;
+mod share_code;
 mod solution;
 mod tile;
 pub mod tile_assertion;
@@ -28,11 +38,14 @@ pub use clue_orientation::ClueOrientation;
 pub use clue_set::ClueSet;
 pub use deduction::{Deduction, DeductionKind};
 pub use difficulty::Difficulty;
+pub use difficulty_rating::DifficultyRating;
 pub use game_action_event::GameActionEvent;
 pub use game_board::GameBoard;
+pub(crate) use game_board::RowState;
 pub use game_state_event::{ClueSelection, GameStateEvent, PuzzleCompletionState};
 pub use game_state_snapshot::GameStateSnapshot;
-pub use game_stats::{GameStats, GlobalStats};
+pub use game_stats::{DailyStreak, GameStats, GlobalStats};
+pub use generation_progress::GenerationProgress;
 pub use global_event::GlobalEvent;
 pub use input_event::{
     CandidateCellTileData, Clickable, InputEvent, SolutionTileData, LONG_PRESS_DURATION,
@@ -41,7 +54,15 @@ pub use layout::{
     CluesSizing, Dimensions, GridCellSizing, GridSizing, HorizontalCluePanelSizing,
     LayoutConfiguration, VerticalCluePanelSizing,
 };
+pub use move_log::{MoveLog, RecordedAction, RecordedMove};
+pub use naked_set::NakedSet;
 pub use partial_solution::PartialSolution;
+pub use puzzle_rng::{
+    is_supported_generator, GeneratorRng, PuzzleRng, FIRST_GENERATOR_VERSION, GENERATOR_VERSION,
+    UNKNOWN_GENERATOR_VERSION,
+};
+pub use puzzle_source::PuzzleSource;
+pub use share_code::{ShareCode, ShareCodeError};
 pub use solution::Solution;
 pub use solution::MAX_GRID_SIZE;
 pub use tile::Tile;
@@ -0,0 +1,115 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use super::{ClueAddress, GameActionEvent, GameBoard};
+
+/// A board-changing action as recorded in a [`MoveLog`]; the serializable subset of
+/// [`GameActionEvent`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RecordedAction {
+    CellSelect(usize, usize, Option<char>),
+    CellClear(usize, usize, Option<char>),
+    ClueToggleComplete(ClueAddress),
+    Solve,
+    RewindLastGood,
+    ShowHint,
+    IncrementHintsUsed,
+    Undo,
+    Redo,
+    /// The game was saved and loaded again; the undo history before this point is gone
+    Resumed,
+}
+
+impl RecordedAction {
+    /// The action for a move event. `ClueToggleSelectedComplete` depends on the clue selection,
+    /// which isn't recorded, so the game state records it as `ClueToggleComplete` itself.
+    pub fn from_event(event: &GameActionEvent) -> Option<Self> {
+        match event {
+            GameActionEvent::CellSelect(row, col, variant) => {
+                Some(RecordedAction::CellSelect(*row, *col, *variant))
+            }
+            GameActionEvent::CellClear(row, col, variant) => {
+                Some(RecordedAction::CellClear(*row, *col, *variant))
+            }
+            GameActionEvent::ClueToggleComplete(address) => {
+                Some(RecordedAction::ClueToggleComplete(*address))
+            }
+            GameActionEvent::Solve => Some(RecordedAction::Solve),
+            GameActionEvent::RewindLastGood => Some(RecordedAction::RewindLastGood),
+            GameActionEvent::ShowHint => Some(RecordedAction::ShowHint),
+            GameActionEvent::IncrementHintsUsed => Some(RecordedAction::IncrementHintsUsed),
+            GameActionEvent::Undo => Some(RecordedAction::Undo),
+            GameActionEvent::Redo => Some(RecordedAction::Redo),
+            _ => None,
+        }
+    }
+
+    /// The event that replays this action, if it is one
+    pub fn to_event(&self) -> Option<GameActionEvent> {
+        match self {
+            RecordedAction::CellSelect(row, col, variant) => {
+                Some(GameActionEvent::CellSelect(*row, *col, *variant))
+            }
+            RecordedAction::CellClear(row, col, variant) => {
+                Some(GameActionEvent::CellClear(*row, *col, *variant))
+            }
+            RecordedAction::ClueToggleComplete(address) => {
+                Some(GameActionEvent::ClueToggleComplete(*address))
+            }
+            RecordedAction::Solve => Some(GameActionEvent::Solve),
+            RecordedAction::RewindLastGood => Some(GameActionEvent::RewindLastGood),
+            RecordedAction::ShowHint => Some(GameActionEvent::ShowHint),
+            RecordedAction::IncrementHintsUsed => Some(GameActionEvent::IncrementHintsUsed),
+            RecordedAction::Undo => Some(GameActionEvent::Undo),
+            RecordedAction::Redo => Some(GameActionEvent::Redo),
+            RecordedAction::Resumed => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecordedMove {
+    /// Time on the game clock, so pauses don't show up in replays
+    pub elapsed: Duration,
+    /// Wall clock time, in milliseconds since the Unix epoch
+    pub timestamp: i64,
+    pub action: RecordedAction,
+}
+
+/// Every action that changed the board during a game, in order, starting from the board the
+/// game started with
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MoveLog {
+    pub initial_board: GameBoard,
+    pub moves: Vec<RecordedMove>,
+}
+
+impl MoveLog {
+    pub fn new(initial_board: GameBoard) -> Self {
+        Self {
+            initial_board,
+            moves: vec![],
+        }
+    }
+
+    pub fn record(&mut self, action: RecordedAction, elapsed: Duration) {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as i64;
+        self.moves.push(RecordedMove {
+            elapsed,
+            timestamp,
+            action,
+        });
+    }
+
+    /// How many moves were taken back
+    pub fn undo_count(&self) -> u32 {
+        self.moves
+            .iter()
+            .filter(|recorded| recorded.action == RecordedAction::Undo)
+            .count() as u32
+    }
+}
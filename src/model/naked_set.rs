@@ -0,0 +1,8 @@
+/// Cells in a row that between them can only hold as many variants as there are cells. Those
+/// variants must be in these cells, so they can be removed from the rest of the row.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NakedSet {
+    pub row: usize,
+    pub columns: Vec<usize>,
+    pub variants: Vec<char>,
+}
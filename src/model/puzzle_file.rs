@@ -0,0 +1,344 @@
+//! The `.mindhunt` puzzle file format: a small line-based text format holding a solution, its
+//! clues and the tiles revealed at the start, so puzzles can be shared and reopened.
+//!
+//! ```text
+//! mindhunt-puzzle 2
+//! difficulty Easy
+//! seed 42
+//! generator 2
+//! row cadb
+//! row bdac
+//! row abdc
+//! row dcba
+//! reveal 2b
+//! clue <+0a,+1b>
+//! clue |+0a,+1b|
+//! ```
+//!
+//! Every line after the header is a keyword followed by its value. Blank lines and lines
+//! starting with `#` are ignored. Version 1 files have no `generator` line; their puzzles came
+//! from the first generator.
+
+use std::fmt::Display;
+use std::path::Path;
+use std::sync::Arc;
+use std::{fs, io};
+
+use super::{
+    Clue, ClueSet, Difficulty, GameBoard, GameStateSnapshot, PuzzleSource, Solution, Tile,
+    TimerState, FIRST_GENERATOR_VERSION, MAX_GRID_SIZE,
+};
+
+pub const PUZZLE_FILE_EXTENSION: &str = "mindhunt";
+pub const PUZZLE_FILE_VERSION: u32 = 2;
+const HEADER: &str = "mindhunt-puzzle";
+
+#[derive(Debug)]
+pub enum PuzzleFileError {
+    Io(io::Error),
+    /// The file was written by a newer version of the format
+    UnsupportedVersion(u32),
+    Invalid {
+        line: usize,
+        reason: String,
+    },
+}
+
+impl Display for PuzzleFileError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PuzzleFileError::Io(err) => write!(f, "{}", err),
+            PuzzleFileError::UnsupportedVersion(version) => write!(
+                f,
+                "Puzzle file version {} is not supported (expected {} or older)",
+                version, PUZZLE_FILE_VERSION
+            ),
+            PuzzleFileError::Invalid { line, reason } => write!(f, "Line {}: {}", line, reason),
+        }
+    }
+}
+
+impl From<io::Error> for PuzzleFileError {
+    fn from(err: io::Error) -> Self {
+        PuzzleFileError::Io(err)
+    }
+}
+
+fn invalid(line: usize, reason: impl Into<String>) -> PuzzleFileError {
+    PuzzleFileError::Invalid {
+        line,
+        reason: reason.into(),
+    }
+}
+
+/// Writes the puzzle on `board`: its solution, clues and correctly placed tiles. Candidate
+/// eliminations and misplaced tiles are left out.
+pub fn write_puzzle(board: &GameBoard) -> String {
+    let solution = &board.solution;
+    let mut lines = vec![
+        format!("{} {}", HEADER, PUZZLE_FILE_VERSION),
+        format!("difficulty {}", solution.difficulty.to_string()),
+        format!("seed {}", solution.seed),
+        format!("generator {}", solution.generator_version),
+    ];
+    for row in 0..solution.n_rows {
+        let variants: String = solution.grid[row][0..solution.n_variants].iter().collect();
+        lines.push(format!("row {}", variants));
+    }
+    for row in 0..solution.n_rows {
+        for col in 0..solution.n_variants {
+            if let Some(tile) = board.get_selection(row, col) {
+                if tile == solution.get(row, col) {
+                    lines.push(format!("reveal {}", tile));
+                }
+            }
+        }
+    }
+    for clue in board.clue_set.all_clues() {
+        lines.push(format!("clue {}", clue.clue.to_string()));
+    }
+    lines.push(String::new());
+    lines.join("\n")
+}
+
+fn parse_difficulty(line: usize, value: &str) -> Result<Difficulty, PuzzleFileError> {
+    Difficulty::all()
+        .into_iter()
+        .find(|d| d.to_string() == value)
+        .ok_or_else(|| invalid(line, format!("unknown difficulty '{}'", value)))
+}
+
+fn parse_tile(line: usize, value: &str, solution: &Solution) -> Result<Tile, PuzzleFileError> {
+    let mut chars = value.chars();
+    let tile = match (chars.next(), chars.next(), chars.next()) {
+        (Some(row), Some(variant), None) => {
+            row.to_digit(10).map(|row| Tile::new(row as usize, variant))
+        }
+        _ => None,
+    };
+    match tile {
+        Some(tile)
+            if tile.row < solution.n_rows && solution.variants_range.contains(&tile.variant) =>
+        {
+            Ok(tile)
+        }
+        _ => Err(invalid(line, format!("invalid tile '{}'", value))),
+    }
+}
+
+fn parse_clue(line: usize, value: &str, solution: &Solution) -> Result<Clue, PuzzleFileError> {
+    let clue = Clue::try_parse(value)
+        .map_err(|err| invalid(line, format!("invalid clue '{}': {}", value, err)))?;
+    for assertion in clue.assertions.iter() {
+        parse_tile(line, &assertion.tile.to_string(), solution)?;
+    }
+    Ok(clue)
+}
+
+/// Reads a puzzle written by [`write_puzzle`], returning a fresh game with the revealed tiles
+/// placed. Whether the clues actually pin down the solution is left to the caller.
+pub fn read_puzzle(contents: &str) -> Result<GameStateSnapshot, PuzzleFileError> {
+    let mut lines = contents
+        .lines()
+        .enumerate()
+        .map(|(i, line)| (i + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'));
+
+    let (header_line, header) = lines.next().ok_or_else(|| invalid(1, "file is empty"))?;
+    let version = header
+        .strip_prefix(HEADER)
+        .and_then(|version| version.trim().parse::<u32>().ok())
+        .ok_or_else(|| invalid(header_line, "not a Mind Hunt puzzle file"))?;
+    if version > PUZZLE_FILE_VERSION {
+        return Err(PuzzleFileError::UnsupportedVersion(version));
+    }
+
+    let mut difficulty = None;
+    let mut seed = 0;
+    let mut generator_version = FIRST_GENERATOR_VERSION;
+    let mut rows: Vec<(usize, &str)> = vec![];
+    let mut reveals: Vec<(usize, &str)> = vec![];
+    let mut clues: Vec<(usize, &str)> = vec![];
+    for (line, text) in lines {
+        let (keyword, value) = text.split_once(' ').unwrap_or((text, ""));
+        let value = value.trim();
+        match keyword {
+            "difficulty" => difficulty = Some(parse_difficulty(line, value)?),
+            "seed" => {
+                seed = value
+                    .parse::<u64>()
+                    .map_err(|_| invalid(line, format!("invalid seed '{}'", value)))?
+            }
+            "generator" => {
+                generator_version = value
+                    .parse::<u32>()
+                    .map_err(|_| invalid(line, format!("invalid generator '{}'", value)))?
+            }
+            "row" => rows.push((line, value)),
+            "reveal" => reveals.push((line, value)),
+            "clue" => clues.push((line, value)),
+            _ => return Err(invalid(line, format!("unknown keyword '{}'", keyword))),
+        }
+    }
+
+    let difficulty = difficulty.ok_or_else(|| invalid(header_line, "missing difficulty"))?;
+    let n_rows = difficulty.n_rows();
+    let n_variants = difficulty.n_cols();
+    if rows.len() != n_rows {
+        return Err(invalid(
+            header_line,
+            format!(
+                "{} puzzles have {} rows, found {}",
+                difficulty.to_string(),
+                n_rows,
+                rows.len()
+            ),
+        ));
+    }
+
+    let variants_range = Solution::variants_range(n_variants);
+    let mut grid = [['a'; MAX_GRID_SIZE]; MAX_GRID_SIZE];
+    for (row, (line, value)) in rows.iter().enumerate() {
+        let mut variants: Vec<char> = value.chars().collect();
+        for (col, variant) in variants.iter().enumerate().take(MAX_GRID_SIZE) {
+            grid[row][col] = *variant;
+        }
+        variants.sort();
+        if !variants.iter().copied().eq(variants_range.clone()) {
+            return Err(invalid(
+                *line,
+                format!("row must use each of {:?} exactly once", variants_range),
+            ));
+        }
+    }
+    let solution = Arc::new(Solution {
+        variants: variants_range.clone().collect(),
+        variants_range,
+        grid,
+        n_rows,
+        n_variants,
+        difficulty,
+        seed,
+        generator_version,
+    });
+
+    let clues = clues
+        .into_iter()
+        .map(|(line, value)| parse_clue(line, value, &solution))
+        .collect::<Result<Vec<Clue>, PuzzleFileError>>()?;
+    let mut board = GameBoard::new(Arc::clone(&solution));
+    board.set_clues(Arc::new(ClueSet::new(clues)));
+    for (line, value) in reveals {
+        board.select_tile_from_solution(parse_tile(line, value, &solution)?);
+    }
+
+    let mut snapshot = GameStateSnapshot::new(board, TimerState::default(), 0, None);
+    snapshot.source = PuzzleSource::Imported;
+    Ok(snapshot)
+}
+
+pub fn export_puzzle(board: &GameBoard, path: &Path) -> Result<(), PuzzleFileError> {
+    fs::write(path, write_puzzle(board))?;
+    Ok(())
+}
+
+pub fn import_puzzle(path: &Path) -> Result<GameStateSnapshot, PuzzleFileError> {
+    read_puzzle(&fs::read_to_string(path)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::UsingLogger;
+    use test_context::test_context;
+
+    fn generated_board() -> GameBoard {
+        GameStateSnapshot::generate_new(Difficulty::Easy, Some(42)).board
+    }
+
+    #[test_context(UsingLogger)]
+    #[test]
+    fn test_round_trip(_: &mut UsingLogger) {
+        let board = generated_board();
+        let contents = write_puzzle(&board);
+        let snapshot = read_puzzle(&contents).expect("puzzle should parse");
+
+        assert_eq!(snapshot.board.solution.grid, board.solution.grid);
+        assert_eq!(snapshot.board.solution.seed, 42);
+        assert_eq!(snapshot.board.solution.difficulty, Difficulty::Easy);
+        assert_eq!(
+            snapshot.board.solution.generator_version,
+            board.solution.generator_version
+        );
+        let clues = |b: &GameBoard| {
+            b.clue_set
+                .all_clues()
+                .map(|c| c.clue.clone())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(clues(&snapshot.board), clues(&board));
+        for row in 0..board.solution.n_rows {
+            for col in 0..board.solution.n_variants {
+                assert_eq!(
+                    snapshot.board.get_selection(row, col),
+                    board.get_selection(row, col)
+                );
+            }
+        }
+        assert!(snapshot.has_unique_solution());
+        assert_eq!(write_puzzle(&snapshot.board), contents);
+    }
+
+    #[test]
+    fn test_reads_version_1_files() {
+        let board = generated_board();
+        let contents = write_puzzle(&board)
+            .replacen(
+                &format!("{} {}", HEADER, PUZZLE_FILE_VERSION),
+                &format!("{} 1", HEADER),
+                1,
+            )
+            .lines()
+            .filter(|line| !line.starts_with("generator "))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let snapshot = read_puzzle(&contents).expect("version 1 puzzle should parse");
+
+        assert_eq!(snapshot.board.solution.grid, board.solution.grid);
+        assert_eq!(
+            snapshot.board.solution.generator_version,
+            FIRST_GENERATOR_VERSION
+        );
+        assert_eq!(snapshot.generator_version, FIRST_GENERATOR_VERSION);
+    }
+
+    #[test]
+    fn test_rejects_newer_version() {
+        let contents = write_puzzle(&generated_board()).replacen(
+            &format!("{} {}", HEADER, PUZZLE_FILE_VERSION),
+            &format!("{} {}", HEADER, PUZZLE_FILE_VERSION + 1),
+            1,
+        );
+        assert!(matches!(
+            read_puzzle(&contents),
+            Err(PuzzleFileError::UnsupportedVersion(v)) if v == PUZZLE_FILE_VERSION + 1
+        ));
+    }
+
+    #[test]
+    fn test_rejects_invalid_contents() {
+        let valid = write_puzzle(&generated_board());
+        let with_line = |line: &str| format!("{}{}\n", valid, line);
+
+        assert!(matches!(
+            read_puzzle("not a puzzle"),
+            Err(PuzzleFileError::Invalid { line: 1, .. })
+        ));
+        assert!(read_puzzle(&with_line("clue <+0a,garbage")).is_err());
+        assert!(read_puzzle(&with_line("clue <+0a,+7b>")).is_err());
+        assert!(read_puzzle(&with_line("clue |+0a,+0b,+1c|")).is_err());
+        assert!(read_puzzle(&with_line("reveal 9z")).is_err());
+        assert!(read_puzzle(&with_line("bogus 1")).is_err());
+        assert!(read_puzzle(&valid.replacen("row ", "row a", 1)).is_err());
+    }
+}
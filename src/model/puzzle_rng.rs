@@ -0,0 +1,186 @@
+//! Random number generation for puzzle generation.
+//!
+//! A seed only identifies a puzzle together with the generator version that made it. Each version
+//! fixes the PRNG and the generation logic, so a supported (version, seed) pair produces the same
+//! puzzle for as long as the game keeps that version:
+//!
+//! - 0: unknown. Saves from before generator versions were recorded were made by generation logic
+//!   that no longer exists, so their puzzles can't be regenerated and are replayed from the saved
+//!   board instead.
+//! - 1: rand's `StdRng` (ChaCha12 as of rand 0.9). Its output may change between rand releases;
+//!   kept only so puzzles from older saves and share codes can still be regenerated.
+//! - 2: [`PuzzleRng`], xoshiro256** seeded through SplitMix64, implemented here so its output
+//!   never depends on a dependency.
+//! - 3: as 2, but an exactly-N-apart clue whose seed tile can't be N columns from anything is
+//!   skipped rather than shortened to a distance that fits.
+//!
+//! Any change that alters the puzzle generated for a seed must bump [`GENERATOR_VERSION`] and keep
+//! the previous behaviour available to older versions, branching on
+//! `Solution::generator_version`. The golden corpus test catches changes that miss this.
+
+use rand::rngs::StdRng;
+use rand::{RngCore, SeedableRng};
+
+/// The generator version used for new puzzles
+pub const GENERATOR_VERSION: u32 = 3;
+/// The oldest generator version that can still regenerate its puzzles
+pub const FIRST_GENERATOR_VERSION: u32 = 1;
+/// Puzzles from saves made before generator versions were recorded
+pub const UNKNOWN_GENERATOR_VERSION: u32 = 0;
+
+pub fn is_supported_generator(generator_version: u32) -> bool {
+    (FIRST_GENERATOR_VERSION..=GENERATOR_VERSION).contains(&generator_version)
+}
+
+/// xoshiro256** (Blackman and Vigna), seeded from a `u64` through SplitMix64 as its authors
+/// recommend.
+#[derive(Debug, Clone)]
+pub struct PuzzleRng {
+    state: [u64; 4],
+}
+
+fn splitmix64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9e3779b97f4a7c15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+}
+
+impl RngCore for PuzzleRng {
+    fn next_u32(&mut self) -> u32 {
+        // the upper bits are the strongest
+        (self.next_u64() >> 32) as u32
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let s = &mut self.state;
+        let result = s[1].wrapping_mul(5).rotate_left(7).wrapping_mul(9);
+        let t = s[1] << 17;
+        s[2] ^= s[0];
+        s[3] ^= s[1];
+        s[1] ^= s[2];
+        s[0] ^= s[3];
+        s[2] ^= t;
+        s[3] = s[3].rotate_left(45);
+        result
+    }
+
+    fn fill_bytes(&mut self, dst: &mut [u8]) {
+        for chunk in dst.chunks_mut(8) {
+            let bytes = self.next_u64().to_le_bytes();
+            chunk.copy_from_slice(&bytes[..chunk.len()]);
+        }
+    }
+}
+
+impl SeedableRng for PuzzleRng {
+    type Seed = [u8; 32];
+
+    /// Takes the state as four little-endian words; an all-zero state is replaced, as xoshiro
+    /// would only ever produce zeros from it.
+    fn from_seed(seed: Self::Seed) -> Self {
+        let mut state = [0; 4];
+        for (word, bytes) in state.iter_mut().zip(seed.chunks(8)) {
+            *word = u64::from_le_bytes(bytes.try_into().unwrap());
+        }
+        if state == [0; 4] {
+            return Self::seed_from_u64(0);
+        }
+        Self { state }
+    }
+
+    fn seed_from_u64(mut seed: u64) -> Self {
+        Self {
+            state: std::array::from_fn(|_| splitmix64(&mut seed)),
+        }
+    }
+}
+
+/// The PRNG of a particular generator version
+#[derive(Debug, Clone)]
+pub enum GeneratorRng {
+    Legacy(Box<StdRng>),
+    Portable(PuzzleRng),
+}
+
+impl GeneratorRng {
+    pub fn new(generator_version: u32, seed: u64) -> Self {
+        assert!(
+            is_supported_generator(generator_version),
+            "Generator version {} is not supported",
+            generator_version
+        );
+        match generator_version {
+            1 => GeneratorRng::Legacy(Box::new(StdRng::seed_from_u64(seed))),
+            _ => GeneratorRng::Portable(PuzzleRng::seed_from_u64(seed)),
+        }
+    }
+}
+
+impl RngCore for GeneratorRng {
+    fn next_u32(&mut self) -> u32 {
+        match self {
+            GeneratorRng::Legacy(rng) => rng.next_u32(),
+            GeneratorRng::Portable(rng) => rng.next_u32(),
+        }
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        match self {
+            GeneratorRng::Legacy(rng) => rng.next_u64(),
+            GeneratorRng::Portable(rng) => rng.next_u64(),
+        }
+    }
+
+    fn fill_bytes(&mut self, dst: &mut [u8]) {
+        match self {
+            GeneratorRng::Legacy(rng) => rng.fill_bytes(dst),
+            GeneratorRng::Portable(rng) => rng.fill_bytes(dst),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matches_reference_xoshiro256starstar() {
+        // reference output for the state [1, 2, 3, 4]
+        let mut seed = [0u8; 32];
+        for (i, word) in [1u64, 2, 3, 4].iter().enumerate() {
+            seed[i * 8..(i + 1) * 8].copy_from_slice(&word.to_le_bytes());
+        }
+        let mut rng = PuzzleRng::from_seed(seed);
+        let expected = [
+            11520,
+            0,
+            1509978240,
+            1215971899390074240,
+            1216172134540287360,
+        ];
+        for value in expected {
+            assert_eq!(rng.next_u64(), value);
+        }
+    }
+
+    #[test]
+    fn test_seeding_is_stable() {
+        // SplitMix64 of 0 gives the first state word; pinned so seeds keep their puzzles
+        let mut seeder = 0;
+        assert_eq!(splitmix64(&mut seeder), 0xe220a8397b1dcdaf);
+
+        let mut a = PuzzleRng::seed_from_u64(42);
+        let mut b = GeneratorRng::new(GENERATOR_VERSION, 42);
+        for _ in 0..16 {
+            assert_eq!(a.next_u64(), b.next_u64());
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_rejects_unknown_generator() {
+        GeneratorRng::new(GENERATOR_VERSION + 1, 42);
+    }
+}
@@ -0,0 +1,27 @@
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+
+/// Where the puzzle being played came from
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash, Default)]
+pub enum PuzzleSource {
+    /// A new puzzle from a random seed
+    #[default]
+    Standard,
+    /// A puzzle started from a seed or share code the player entered
+    Seeded,
+    Daily,
+    /// A puzzle opened from a puzzle file
+    Imported,
+}
+
+impl Display for PuzzleSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            PuzzleSource::Standard => "Standard",
+            PuzzleSource::Seeded => "Seeded",
+            PuzzleSource::Daily => "Daily",
+            PuzzleSource::Imported => "Imported",
+        };
+        write!(f, "{}", name)
+    }
+}
@@ -0,0 +1,280 @@
+//! Crash-safe storage for the JSON files kept in the data directory.
+//!
+//! Files are written to a temporary file first and renamed over the old one, so a crash
+//! mid-write leaves the previous contents in place. The previous contents are also kept as
+//! rotating backups (`<file>.bak1` newest, up to `<file>.bak<BACKUP_COUNT>`), which are read
+//! when the file itself can't be. A file that can't be read is moved aside to
+//! `<file>.unreadable` rather than being overwritten by the next save. A file written by a newer
+//! version of the game is left where it is, for that version to read.
+
+use std::ffi::OsString;
+use std::fmt::Display;
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// How many earlier versions of a file are kept
+pub const BACKUP_COUNT: usize = 2;
+
+#[derive(Debug)]
+pub enum SaveFileError {
+    Io(io::Error),
+    Parse(serde_json::Error),
+    /// The file was written by a newer version of the game
+    UnsupportedVersion(u32),
+}
+
+impl Display for SaveFileError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SaveFileError::Io(err) => write!(f, "{}", err),
+            SaveFileError::Parse(err) => write!(f, "The file is damaged: {}", err),
+            SaveFileError::UnsupportedVersion(version) => write!(
+                f,
+                "The file is from a newer version of Mind Hunt (format {})",
+                version
+            ),
+        }
+    }
+}
+
+impl From<io::Error> for SaveFileError {
+    fn from(err: io::Error) -> Self {
+        SaveFileError::Io(err)
+    }
+}
+
+impl From<serde_json::Error> for SaveFileError {
+    fn from(err: serde_json::Error) -> Self {
+        SaveFileError::Parse(err)
+    }
+}
+
+/// The result of reading a file together with its backups
+#[derive(Debug)]
+pub enum LoadOutcome<T> {
+    /// Neither the file nor a backup exists
+    Missing,
+    Loaded(T),
+    /// The file couldn't be read, for the given reason, but a backup could
+    Restored(T, SaveFileError),
+    /// Neither the file nor its backups could be read; the file was moved aside
+    Unreadable(SaveFileError),
+    /// The file was written by a newer version of the game, in the given format; it was left
+    /// in place and its backups weren't tried
+    TooNew(u32),
+}
+
+impl<T> LoadOutcome<T> {
+    /// The loaded value, whether from the file or a backup
+    pub fn value(self) -> Option<T> {
+        match self {
+            LoadOutcome::Loaded(value) | LoadOutcome::Restored(value, _) => Some(value),
+            LoadOutcome::Missing | LoadOutcome::Unreadable(_) | LoadOutcome::TooNew(_) => None,
+        }
+    }
+}
+
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut file_name = path.file_name().map(OsString::from).unwrap_or_default();
+    file_name.push(suffix);
+    path.with_file_name(file_name)
+}
+
+pub fn backup_path(path: &Path, generation: usize) -> PathBuf {
+    with_suffix(path, &format!(".bak{}", generation))
+}
+
+pub fn unreadable_path(path: &Path) -> PathBuf {
+    with_suffix(path, ".unreadable")
+}
+
+/// Replaces the contents of `path` so that, whenever the process stops, the file holds either
+/// the old contents or the new ones. The old contents become the newest backup.
+pub fn write_atomic(path: &Path, contents: &str) -> io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+
+    let temp_path = with_suffix(path, ".tmp");
+    {
+        let mut file = File::create(&temp_path)?;
+        file.write_all(contents.as_bytes())?;
+        file.sync_all()?;
+    }
+
+    if path.exists() {
+        for generation in (1..BACKUP_COUNT).rev() {
+            let older = backup_path(path, generation);
+            if older.exists() {
+                fs::rename(&older, backup_path(path, generation + 1))?;
+            }
+        }
+        // copied rather than renamed, so `path` is never missing
+        fs::copy(path, backup_path(path, 1))?;
+    }
+
+    fs::rename(&temp_path, path)
+}
+
+/// Deletes `path` and its backups
+pub fn remove(path: &Path) -> io::Result<()> {
+    let backups = (1..=BACKUP_COUNT).map(|generation| backup_path(path, generation));
+    for path in [path.to_path_buf()].into_iter().chain(backups) {
+        match fs::remove_file(&path) {
+            Err(err) if err.kind() != io::ErrorKind::NotFound => return Err(err),
+            _ => (),
+        }
+    }
+    Ok(())
+}
+
+fn read_one<T>(
+    path: &Path,
+    parse: &dyn Fn(&str) -> Result<T, SaveFileError>,
+) -> Result<T, SaveFileError> {
+    parse(&fs::read_to_string(path)?)
+}
+
+/// Reads `path` with `parse`, falling back to its backups, newest first
+pub fn load<T>(path: &Path, parse: &dyn Fn(&str) -> Result<T, SaveFileError>) -> LoadOutcome<T> {
+    let backups: Vec<PathBuf> = (1..=BACKUP_COUNT)
+        .map(|generation| backup_path(path, generation))
+        .filter(|backup| backup.exists())
+        .collect();
+    if !path.exists() && backups.is_empty() {
+        return LoadOutcome::Missing;
+    }
+
+    let err = match read_one(path, parse) {
+        Ok(value) => return LoadOutcome::Loaded(value),
+        Err(SaveFileError::UnsupportedVersion(version)) => {
+            log::warn!(target: "save_file", "{:?} is from a newer version (format {}); leaving it alone", path, version);
+            return LoadOutcome::TooNew(version);
+        }
+        Err(err) => err,
+    };
+    log::error!(target: "save_file", "Failed to read {:?}: {}", path, err);
+
+    let mut unreadable_backups = vec![];
+    for backup in backups.iter() {
+        match read_one(backup, parse) {
+            Ok(value) => {
+                log::warn!(target: "save_file", "Restored {:?} from {:?}", path, backup);
+                // so the next load doesn't have to restore it again
+                move_aside(path);
+                if let Err(copy_err) = fs::copy(backup, path) {
+                    log::error!(target: "save_file", "Failed to restore {:?}: {}", path, copy_err);
+                }
+                return LoadOutcome::Restored(value, err);
+            }
+            Err(backup_err) => {
+                log::error!(target: "save_file", "Failed to read {:?}: {}", backup, backup_err);
+                if !matches!(backup_err, SaveFileError::UnsupportedVersion(_)) {
+                    unreadable_backups.push(backup);
+                }
+            }
+        }
+    }
+
+    move_aside(path);
+    for backup in unreadable_backups {
+        move_aside(backup);
+    }
+    LoadOutcome::Unreadable(err)
+}
+
+/// Keeps an unreadable file out of the way of the next save, in case it can be recovered
+fn move_aside(path: &Path) {
+    if path.exists() {
+        if let Err(err) = fs::rename(path, unreadable_path(path)) {
+            log::error!(target: "save_file", "Failed to move {:?} aside: {}", path, err);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::TempDir;
+
+    fn temp_file() -> (TempDir, PathBuf) {
+        let dir = TempDir::new("save-file");
+        let path = dir.path().join("data.json");
+        (dir, path)
+    }
+
+    fn parse_number(contents: &str) -> Result<u32, SaveFileError> {
+        Ok(serde_json::from_str(contents)?)
+    }
+
+    #[test]
+    fn test_write_rotates_backups() {
+        let (_dir, path) = temp_file();
+        for n in 1..=4 {
+            write_atomic(&path, &n.to_string()).unwrap();
+        }
+        assert_eq!(fs::read_to_string(&path).unwrap(), "4");
+        assert_eq!(fs::read_to_string(backup_path(&path, 1)).unwrap(), "3");
+        assert_eq!(fs::read_to_string(backup_path(&path, 2)).unwrap(), "2");
+        assert!(!backup_path(&path, 3).exists());
+        assert!(!with_suffix(&path, ".tmp").exists());
+    }
+
+    #[test]
+    fn test_load_falls_back_to_backup() {
+        let (_dir, path) = temp_file();
+        assert!(matches!(load(&path, &parse_number), LoadOutcome::Missing));
+
+        write_atomic(&path, "1").unwrap();
+        write_atomic(&path, "2").unwrap();
+        assert!(matches!(load(&path, &parse_number), LoadOutcome::Loaded(2)));
+
+        // as if the game crashed halfway through writing
+        fs::write(&path, "{\"trunc").unwrap();
+        assert!(matches!(
+            load(&path, &parse_number),
+            LoadOutcome::Restored(1, SaveFileError::Parse(_))
+        ));
+        assert!(matches!(load(&path, &parse_number), LoadOutcome::Loaded(1)));
+        assert!(unreadable_path(&path).exists());
+    }
+
+    #[test]
+    fn test_unreadable_file_is_moved_aside() {
+        let (_dir, path) = temp_file();
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, "not a number").unwrap();
+
+        assert!(matches!(
+            load(&path, &parse_number),
+            LoadOutcome::Unreadable(SaveFileError::Parse(_))
+        ));
+        assert!(!path.exists());
+        assert_eq!(
+            fs::read_to_string(unreadable_path(&path)).unwrap(),
+            "not a number"
+        );
+        // reported once, then it's out of the way
+        assert!(matches!(load(&path, &parse_number), LoadOutcome::Missing));
+    }
+
+    #[test]
+    fn test_newer_file_is_left_in_place() {
+        let (_dir, path) = temp_file();
+        // as if numbers above 9 were a format only a newer version understands
+        let parse = |contents: &str| match parse_number(contents)? {
+            number if number > 9 => Err(SaveFileError::UnsupportedVersion(number)),
+            number => Ok(number),
+        };
+        write_atomic(&path, "1").unwrap();
+        write_atomic(&path, "10").unwrap();
+
+        for _ in 0..2 {
+            assert!(matches!(load(&path, &parse), LoadOutcome::TooNew(10)));
+        }
+        assert_eq!(fs::read_to_string(&path).unwrap(), "10");
+        assert_eq!(fs::read_to_string(backup_path(&path, 1)).unwrap(), "1");
+        assert!(!unreadable_path(&path).exists());
+    }
+}
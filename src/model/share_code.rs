@@ -0,0 +1,279 @@
+use std::fmt::Display;
+
+use super::{is_supported_generator, Difficulty, GENERATOR_VERSION};
+
+/// Bumped whenever the share code layout changes
+const FORMAT_VERSION: u8 = 1;
+/// Crockford's base32 alphabet: no I, L, O or U, so codes survive being read aloud or retyped
+const ALPHABET: &[u8; 32] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+const PAYLOAD_LEN: usize = 10;
+const CODE_BYTES: usize = PAYLOAD_LEN + 2;
+const CODE_CHARS: usize = (CODE_BYTES * 8).div_ceil(5);
+const GROUP_SIZE: usize = 5;
+
+/// A short, copy-pasteable code identifying a generated puzzle.
+///
+/// Layout (before base32 encoding):
+/// - byte 0: format version (high nibble), difficulty index (low nibble)
+/// - byte 1: generator version
+/// - bytes 2..10: seed, big-endian
+/// - bytes 10..12: CRC-16/CCITT of bytes 0..10
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ShareCode {
+    pub difficulty: Difficulty,
+    pub seed: u64,
+    pub generator_version: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ShareCodeError {
+    InvalidCharacter(char),
+    WrongLength(usize),
+    ChecksumMismatch,
+    /// The unused low bits of the last character are set, so the code isn't one we'd write
+    InvalidPadding,
+    UnsupportedFormat(u8),
+    UnknownDifficulty(u8),
+    /// The code was made by a newer generator than this version of the game knows
+    IncompatibleGenerator(u32),
+}
+
+impl Display for ShareCodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ShareCodeError::InvalidCharacter(c) => {
+                write!(f, "'{}' is not a valid share code character", c)
+            }
+            ShareCodeError::WrongLength(len) => write!(
+                f,
+                "Share codes have {} characters, this one has {}",
+                CODE_CHARS, len
+            ),
+            ShareCodeError::ChecksumMismatch => {
+                write!(f, "Share code checksum does not match; check for typos")
+            }
+            ShareCodeError::InvalidPadding => {
+                write!(f, "Share code ends in an invalid character; check for typos")
+            }
+            ShareCodeError::UnsupportedFormat(version) => write!(
+                f,
+                "Share code format {} is not supported by this version of Mind Hunt",
+                version
+            ),
+            ShareCodeError::UnknownDifficulty(index) => {
+                write!(f, "Share code has unknown difficulty {}", index)
+            }
+            ShareCodeError::IncompatibleGenerator(version) => write!(
+                f,
+                "Share code is for puzzle generator {}, this version of Mind Hunt supports up to generator {}",
+                version, GENERATOR_VERSION
+            ),
+        }
+    }
+}
+
+fn crc16(bytes: &[u8]) -> u16 {
+    let mut crc: u16 = 0xFFFF;
+    for byte in bytes {
+        crc ^= (*byte as u16) << 8;
+        for _ in 0..8 {
+            crc = if crc & 0x8000 != 0 {
+                (crc << 1) ^ 0x1021
+            } else {
+                crc << 1
+            };
+        }
+    }
+    crc
+}
+
+fn encode_base32(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(CODE_CHARS);
+    let mut buffer: u32 = 0;
+    let mut bits = 0;
+    for byte in bytes {
+        buffer = (buffer << 8) | *byte as u32;
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            out.push(ALPHABET[((buffer >> bits) & 0x1F) as usize] as char);
+        }
+    }
+    if bits > 0 {
+        out.push(ALPHABET[((buffer << (5 - bits)) & 0x1F) as usize] as char);
+    }
+    out
+}
+
+fn decode_char(c: char) -> Option<u8> {
+    let c = match c.to_ascii_uppercase() {
+        'O' => '0',
+        'I' | 'L' => '1',
+        c => c,
+    };
+    ALPHABET
+        .iter()
+        .position(|a| *a as char == c)
+        .map(|i| i as u8)
+}
+
+fn decode_base32(chars: &[char]) -> Result<Vec<u8>, ShareCodeError> {
+    let mut out = Vec::with_capacity(CODE_BYTES);
+    let mut buffer: u32 = 0;
+    let mut bits = 0;
+    for c in chars {
+        let value = decode_char(*c).ok_or(ShareCodeError::InvalidCharacter(*c))?;
+        buffer = (buffer << 5) | value as u32;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((buffer >> bits) as u8);
+        }
+    }
+    // the bits left over must be the zeros encode_base32 pads with, or two codes would decode
+    // to the same puzzle
+    if buffer & ((1 << bits) - 1) != 0 {
+        return Err(ShareCodeError::InvalidPadding);
+    }
+    Ok(out)
+}
+
+impl ShareCode {
+    pub fn new(difficulty: Difficulty, seed: u64) -> Self {
+        Self {
+            difficulty,
+            seed,
+            generator_version: GENERATOR_VERSION,
+        }
+    }
+
+    /// Parses a code as produced by `to_string`. Case, dashes and whitespace are ignored, and
+    /// the commonly confused letters O, I and L are read as digits.
+    pub fn parse(code: &str) -> Result<Self, ShareCodeError> {
+        let chars: Vec<char> = code
+            .chars()
+            .filter(|c| *c != '-' && !c.is_whitespace())
+            .collect();
+        if chars.len() != CODE_CHARS {
+            return Err(ShareCodeError::WrongLength(chars.len()));
+        }
+        let bytes = decode_base32(&chars)?;
+        let (payload, checksum) = bytes.split_at(PAYLOAD_LEN);
+        if crc16(payload).to_be_bytes() != checksum {
+            return Err(ShareCodeError::ChecksumMismatch);
+        }
+
+        let format_version = payload[0] >> 4;
+        if format_version != FORMAT_VERSION {
+            return Err(ShareCodeError::UnsupportedFormat(format_version));
+        }
+        let difficulty_index = payload[0] & 0x0F;
+        if difficulty_index as usize >= Difficulty::all().len() {
+            return Err(ShareCodeError::UnknownDifficulty(difficulty_index));
+        }
+        let generator_version = payload[1] as u32;
+        if !is_supported_generator(generator_version) {
+            return Err(ShareCodeError::IncompatibleGenerator(generator_version));
+        }
+        let seed = u64::from_be_bytes(payload[2..PAYLOAD_LEN].try_into().unwrap());
+
+        Ok(Self {
+            difficulty: Difficulty::from_index(difficulty_index as usize),
+            seed,
+            generator_version,
+        })
+    }
+}
+
+impl Display for ShareCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut bytes = Vec::with_capacity(CODE_BYTES);
+        bytes.push((FORMAT_VERSION << 4) | self.difficulty.index() as u8);
+        bytes.push(self.generator_version as u8);
+        bytes.extend_from_slice(&self.seed.to_be_bytes());
+        bytes.extend_from_slice(&crc16(&bytes).to_be_bytes());
+
+        let encoded: Vec<char> = encode_base32(&bytes).chars().collect();
+        let groups = encoded
+            .chunks(GROUP_SIZE)
+            .map(|group| group.iter().collect::<String>())
+            .collect::<Vec<String>>();
+        write!(f, "{}", groups.join("-"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::FIRST_GENERATOR_VERSION;
+
+    #[test]
+    fn test_round_trip() {
+        for difficulty in Difficulty::all() {
+            for seed in [0, 1, 42, 0xDEAD_BEEF, u64::MAX] {
+                let code = ShareCode::new(difficulty, seed);
+                let encoded = code.to_string();
+                assert_eq!(encoded.len(), CODE_CHARS + CODE_CHARS / GROUP_SIZE - 1);
+                assert_eq!(ShareCode::parse(&encoded), Ok(code));
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_is_forgiving_about_formatting() {
+        let code = ShareCode::new(Difficulty::Hard, 123456789);
+        let encoded = code.to_string();
+        let sloppy = encoded
+            .replace('-', " ")
+            .to_lowercase()
+            .replace('0', "o")
+            .replace('1', "l");
+        assert_eq!(ShareCode::parse(&sloppy), Ok(code));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let encoded = ShareCode::new(Difficulty::Moderate, 987654321).to_string();
+
+        assert_eq!(ShareCode::parse("ABC"), Err(ShareCodeError::WrongLength(3)));
+        assert_eq!(
+            ShareCode::parse(&encoded.replacen(&encoded[0..1], "U", 1)),
+            Err(ShareCodeError::InvalidCharacter('U'))
+        );
+
+        // changing a single character must be caught by the checksum
+        let mut typo: Vec<char> = encoded.chars().collect();
+        typo[7] = if typo[7] == 'A' { 'B' } else { 'A' };
+        assert_eq!(
+            ShareCode::parse(&typo.into_iter().collect::<String>()),
+            Err(ShareCodeError::ChecksumMismatch)
+        );
+
+        // the last character only carries one bit; the rest is padding the checksum can't see
+        let last = decode_char(encoded.chars().last().unwrap()).unwrap();
+        let mut padded: Vec<char> = encoded.chars().collect();
+        *padded.last_mut().unwrap() = ALPHABET[(last ^ 1) as usize] as char;
+        assert_eq!(
+            ShareCode::parse(&padded.into_iter().collect::<String>()),
+            Err(ShareCodeError::InvalidPadding)
+        );
+
+        let legacy_generator = ShareCode {
+            generator_version: FIRST_GENERATOR_VERSION,
+            ..ShareCode::new(Difficulty::Easy, 1)
+        };
+        assert_eq!(
+            ShareCode::parse(&legacy_generator.to_string()),
+            Ok(legacy_generator)
+        );
+
+        let future_generator = ShareCode {
+            generator_version: GENERATOR_VERSION + 1,
+            ..ShareCode::new(Difficulty::Easy, 1)
+        };
+        assert_eq!(
+            ShareCode::parse(&future_generator.to_string()),
+            Err(ShareCodeError::IncompatibleGenerator(GENERATOR_VERSION + 1))
+        );
+    }
+}
@@ -1,15 +1,16 @@
 use std::fmt::Display;
 use std::ops::RangeInclusive;
 
+use crate::model::puzzle_rng::{GeneratorRng, GENERATOR_VERSION, UNKNOWN_GENERATOR_VERSION};
 use crate::model::{Difficulty, Tile};
 use log::trace;
 use rand::rngs::OsRng;
+use rand::seq::SliceRandom;
 use rand::TryRngCore;
-use rand::{seq::SliceRandom, SeedableRng};
 
 pub const MAX_GRID_SIZE: usize = 8;
 
-#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
 pub struct Solution {
     pub variants: Vec<char>,
     pub variants_range: RangeInclusive<char>,
@@ -18,6 +19,13 @@ pub struct Solution {
     pub n_variants: usize,
     pub difficulty: Difficulty,
     pub seed: u64,
+    /// The generator that made this puzzle from `seed`; absent in saves from before it was recorded
+    #[serde(default = "unknown_generator_version")]
+    pub generator_version: u32,
+}
+
+fn unknown_generator_version() -> u32 {
+    UNKNOWN_GENERATOR_VERSION
 }
 
 impl Default for Solution {
@@ -30,6 +38,7 @@ impl Default for Solution {
             n_variants: 0,
             difficulty: Difficulty::default(),
             seed: 0,
+            generator_version: GENERATOR_VERSION,
         }
     }
 }
@@ -41,6 +50,15 @@ impl Solution {
     }
 
     pub fn new(difficulty: Difficulty, seed: Option<u64>) -> Self {
+        Self::new_with_generator(difficulty, seed, GENERATOR_VERSION)
+    }
+
+    /// Generates the solution as `generator_version` did, so older seeds keep their puzzles
+    pub fn new_with_generator(
+        difficulty: Difficulty,
+        seed: Option<u64>,
+        generator_version: u32,
+    ) -> Self {
         let n_rows = difficulty.n_rows();
         let n_variants = n_rows;
 
@@ -62,7 +80,7 @@ impl Solution {
 
         let seed = seed.unwrap_or(OsRng.try_next_u64().expect("Failed to generate seed"));
 
-        let mut rng = rand::rngs::StdRng::seed_from_u64(seed);
+        let mut rng = GeneratorRng::new(generator_version, seed);
 
         let variants_range = Self::variants_range(n_variants);
         let variants = variants_range.clone().collect::<Vec<char>>();
@@ -89,6 +107,7 @@ impl Solution {
             n_variants,
             difficulty,
             seed,
+            generator_version,
         }
     }
 
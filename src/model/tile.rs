@@ -13,14 +13,21 @@ impl Tile {
 
     /// Parse a tile from a string of the form "0a" or "1b" etc.
     pub fn parse(s: &str) -> Self {
-        let row = s
-            .chars()
+        Self::try_parse(s).unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    /// Like [`Tile::parse`], but returns an error for malformed input instead of panicking.
+    pub fn try_parse(s: &str) -> Result<Self, String> {
+        let mut chars = s.chars();
+        let row = chars
             .next()
             .and_then(|c| c.to_digit(10))
             .map(|d| d as usize)
-            .expect("Invalid row number");
-        let variant = s.chars().nth(1).expect("Missing variant character");
-        Self { row, variant }
+            .ok_or_else(|| format!("Invalid row number in tile '{}'", s))?;
+        let variant = chars
+            .next()
+            .ok_or_else(|| format!("Missing variant character in tile '{}'", s))?;
+        Ok(Self { row, variant })
     }
 
     pub fn variant_to_u8(variant: char) -> u8 {
@@ -19,19 +19,24 @@ impl TileAssertion {
     /// - '-' for negative assertion
     /// - '?' for maybe assertion (used in one_matches_either)
     pub fn parse(s: &str) -> Self {
-        let is_positive = match s.chars().next() {
+        Self::try_parse(s).unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    /// Like [`TileAssertion::parse`], but returns an error for malformed input instead of
+    /// panicking.
+    pub fn try_parse(s: &str) -> Result<Self, String> {
+        let mut chars = s.chars();
+        let is_positive = match chars.next() {
             Some('+') => true,
             Some('-') => false,
             Some('?') => true, // maybe assertions are treated as positive
-            _ => panic!("Invalid assertion prefix, must be +, -, or ?"),
+            _ => return Err("Invalid assertion prefix, must be +, -, or ?".to_string()),
         };
 
-        let tile_str = &s[1..];
-
-        Self {
-            tile: Tile::parse(tile_str),
+        Ok(Self {
+            tile: Tile::try_parse(chars.as_str())?,
             assertion: is_positive,
-        }
+        })
     }
 
     pub(crate) fn is_positive(&self) -> bool {
@@ -2,14 +2,14 @@ use std::collections::{HashMap, HashSet};
 
 use crate::{
     model::{
-        Clue, ClueType, Deduction, DeductionKind, GameBoard, HorizontalClueType, Tile,
-        TileAssertion, VerticalClueType,
+        Clue, ClueType, Deduction, DeductionKind, GameBoard, HorizontalClueType, NakedSet,
+        RowState, Tile, TileAssertion, VerticalClueType,
     },
     solver::clue_constraint::create_clue_constraint,
 };
 use log::trace;
 
-use super::hidden_pair_finder::{find_hidden_pairs_in_row, find_naked_pairs_in_row};
+use super::hidden_pair_finder::{find_hidden_pairs_in_row, find_naked_pairs_in_row, SubsetResult};
 
 fn is_known_deduction(board: &GameBoard, deduction: &Deduction) -> bool {
     let result = if deduction.tile_assertion.assertion {
@@ -384,78 +384,65 @@ fn deduce_one_matches_either(board: &GameBoard, tiles: &[Tile]) -> Vec<Deduction
     synthesize_deductions(board, &possible_columns)
 }
 
-pub fn deduce_hidden_sets_in_row(board: &GameBoard, row: usize) -> Vec<Deduction> {
+/// Eliminations implied by a set of `columns` that between them hold all of `variants`: no other
+/// variant can go in those columns, and the set's variants can't go anywhere else in the row.
+fn deduce_subset_in_row(
+    board: &GameBoard,
+    row: usize,
+    subset: &SubsetResult,
+    deduction_kind: DeductionKind,
+) -> Vec<Deduction> {
     let mut deductions = Vec::new();
-
-    let mut hidden_sets = Vec::new();
-    hidden_sets.extend(find_hidden_pairs_in_row(row, board));
-    if hidden_sets.len() == 0 {
-        hidden_sets.extend(find_naked_pairs_in_row(row, board));
-    }
-    if hidden_sets.len() == 0 {
-        return deductions;
+    for column in 0..board.solution.n_variants {
+        let col_in_set = subset.columns.contains(&column);
+        for variant in board.solution.variants.iter() {
+            let variant_in_set = subset.variants.contains(variant);
+            if col_in_set != variant_in_set && board.is_candidate_available(row, column, *variant) {
+                deductions.push(Deduction::new_with_kind(
+                    column,
+                    TileAssertion {
+                        tile: Tile::new(row, *variant),
+                        assertion: false,
+                    },
+                    deduction_kind.clone(),
+                ));
+            }
+        }
     }
+    deductions
+}
 
+/// Finds the smallest of the given sets that still yields deductions.
+fn smallest_productive_subset(
+    board: &GameBoard,
+    row: usize,
+    mut subsets: Vec<SubsetResult>,
+    deduction_kind: DeductionKind,
+) -> Option<(SubsetResult, Vec<Deduction>)> {
+    subsets.sort_by_key(|set| set.variants.len());
     trace!(
         target: "solver",
-        "Found {} hidden sets: {:?}",
-        hidden_sets.len(),
-        hidden_sets
+        "Found {} {:?} sets in row {}: {:?}",
+        subsets.len(),
+        deduction_kind,
+        row,
+        subsets
     );
-
-    // find the smallest one
-    let smallest_hidden_set = hidden_sets.iter().min_by_key(|set| set.variants.len());
-    if let Some(smallest_hidden_set) = smallest_hidden_set {
-        trace!(
-            target: "solver",
-            "Smallest hidden set: {:?}",
-            smallest_hidden_set
-        );
-        // add negative deductions for the variants that are not in the smallest hidden set
-        let hidden_set_inverse = board
-            .solution
-            .variants
-            .clone()
-            .into_iter()
-            .filter(|v| !smallest_hidden_set.variants.contains(v))
-            .collect::<Vec<_>>();
-
-        for column in 0..board.solution.n_variants {
-            let col_in_set = smallest_hidden_set.columns.contains(&column);
-            if col_in_set {
-                // only the hidden set variants can go here
-                // not in hidden set, but on board? get rid of it.
-                for not_in_set_variant in hidden_set_inverse.iter() {
-                    if board.is_candidate_available(row, column, *not_in_set_variant) {
-                        deductions.push(Deduction::new_with_kind(
-                            column,
-                            TileAssertion {
-                                tile: Tile::new(row, *not_in_set_variant),
-                                assertion: false,
-                            },
-                            DeductionKind::HiddenSet,
-                        ));
-                    }
-                }
-            } else {
-                // hidden set variants cannot go here
-                for hidden_set_variant in smallest_hidden_set.variants.iter() {
-                    // eliminate hidden variants from columns not part of set
-                    if board.is_candidate_available(row, column, *hidden_set_variant) {
-                        // remove it! you don't belong here
-                        deductions.push(Deduction::new(
-                            column,
-                            TileAssertion {
-                                tile: Tile::new(row, *hidden_set_variant),
-                                assertion: false,
-                            },
-                        ));
-                    }
-                }
-            }
+    subsets.into_iter().find_map(|subset| {
+        let deductions = deduce_subset_in_row(board, row, &subset, deduction_kind.clone());
+        if deductions.is_empty() {
+            None
+        } else {
+            Some((subset, deductions))
         }
-    }
-    deductions
+    })
+}
+
+pub fn deduce_hidden_sets_in_row(board: &GameBoard, row: usize) -> Vec<Deduction> {
+    let hidden_sets = find_hidden_pairs_in_row(row, board);
+    smallest_productive_subset(board, row, hidden_sets, DeductionKind::HiddenSet)
+        .map(|(_, deductions)| deductions)
+        .unwrap_or_default()
 }
 
 pub fn deduce_hidden_sets(board: &GameBoard) -> Vec<Deduction> {
@@ -464,6 +451,35 @@ pub fn deduce_hidden_sets(board: &GameBoard) -> Vec<Deduction> {
         .collect()
 }
 
+/// Finds a naked set in the row that yields deductions, returning it along with its deductions.
+pub fn find_naked_set_in_row(board: &GameBoard, row: usize) -> Option<(NakedSet, Vec<Deduction>)> {
+    let naked_sets = find_naked_pairs_in_row(row, board);
+    smallest_productive_subset(board, row, naked_sets, DeductionKind::NakedSet).map(
+        |(subset, deductions)| {
+            (
+                NakedSet {
+                    row,
+                    columns: subset.columns,
+                    variants: subset.variants,
+                },
+                deductions,
+            )
+        },
+    )
+}
+
+pub fn deduce_naked_sets_in_row(board: &GameBoard, row: usize) -> Vec<Deduction> {
+    find_naked_set_in_row(board, row)
+        .map(|(_, deductions)| deductions)
+        .unwrap_or_default()
+}
+
+pub fn deduce_naked_sets(board: &GameBoard) -> Vec<Deduction> {
+    (0..board.solution.n_rows)
+        .flat_map(|row| deduce_naked_sets_in_row(board, row))
+        .collect()
+}
+
 pub fn deduce_clue(board: &GameBoard, clue: &Clue) -> Vec<Deduction> {
     let tiles = clue.assertions.iter().map(|a| a.tile).collect::<Vec<_>>();
     match &clue.clue_type {
@@ -487,6 +503,20 @@ pub fn deduce_clue(board: &GameBoard, clue: &Clue) -> Vec<Deduction> {
             deduce_clue_with_candidate_finder(board, &clue)
         }
 
+        ClueType::Horizontal(HorizontalClueType::Between) => {
+            deduce_clue_with_candidate_finder(board, &clue)
+        }
+
+        ClueType::Horizontal(HorizontalClueType::NApart(_)) => {
+            deduce_clue_with_candidate_finder(board, &clue)
+        }
+
+        ClueType::Horizontal(
+            HorizontalClueType::OnEdge
+            | HorizontalClueType::NotOnEdge
+            | HorizontalClueType::LeftHalf,
+        ) => deduce_clue_with_candidate_finder(board, &clue),
+
         ClueType::Vertical(VerticalClueType::ThreeInColumn)
         | ClueType::Vertical(VerticalClueType::TwoInColumn) => {
             deduce_clue_with_candidate_finder(board, &clue)
@@ -510,30 +540,98 @@ pub fn deduce_clue(board: &GameBoard, clue: &Clue) -> Vec<Deduction> {
 pub enum EvaluationStepResult {
     Nothing,
     HiddenSetsFound,
+    NakedSetsFound,
     DeductionsFound(Clue),
 }
 
-/// note - does not mutate, does not auto-solve, caller must call auto-solve after applying evaluation
-pub fn perform_evaluation_step(board: &mut GameBoard, clues: &Vec<Clue>) -> EvaluationStepResult {
+/// Remembers what each clue deduced, for solving the same puzzle over and over, as when
+/// checking which clues can be dropped. A clue's deductions depend only on the rows its tiles
+/// are in, as long as the board is still a valid possibility, so those rows are the key.
+#[derive(Default)]
+pub struct DeductionCache {
+    deductions: HashMap<(Clue, Vec<RowState>), Vec<Deduction>>,
+}
+
+impl DeductionCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn deduce_clue(&mut self, board: &GameBoard, clue: &Clue) -> Vec<Deduction> {
+        let mut rows: Vec<usize> = clue.assertions.iter().map(|a| a.tile.row).collect();
+        rows.sort();
+        rows.dedup();
+        let row_states = rows.into_iter().map(|row| board.row_state(row)).collect();
+        self.deductions
+            .entry((clue.clone(), row_states))
+            .or_insert_with(|| deduce_clue(board, clue))
+            .clone()
+    }
+
+    /// Same as [`find_evaluation_step`]
+    pub fn find_evaluation_step(
+        &mut self,
+        board: &GameBoard,
+        clues: &[Clue],
+    ) -> (EvaluationStepResult, Vec<Deduction>) {
+        // on an invalid board, deductions can depend on other rows too
+        if !board.is_valid_possibility() {
+            return find_evaluation_step_with(board, clues, deduce_clue);
+        }
+        find_evaluation_step_with(board, clues, |board, clue| self.deduce_clue(board, clue))
+    }
+
+    /// Same as [`perform_evaluation_step`]
+    pub fn perform_evaluation_step(
+        &mut self,
+        board: &mut GameBoard,
+        clues: &[Clue],
+    ) -> EvaluationStepResult {
+        let (result, deductions) = self.find_evaluation_step(board, clues);
+        board.apply_deductions(&deductions);
+        result
+    }
+}
+
+/// Finds the deductions for the next step of solving the board, without applying them: the
+/// first clue that yields a deduction, else naked sets, else hidden sets.
+pub fn find_evaluation_step(
+    board: &GameBoard,
+    clues: &Vec<Clue>,
+) -> (EvaluationStepResult, Vec<Deduction>) {
+    find_evaluation_step_with(board, clues, deduce_clue)
+}
+
+fn find_evaluation_step_with(
+    board: &GameBoard,
+    clues: &[Clue],
+    mut deduce_clue: impl FnMut(&GameBoard, &Clue) -> Vec<Deduction>,
+) -> (EvaluationStepResult, Vec<Deduction>) {
     // nothing to do
     if board.is_complete() {
-        return EvaluationStepResult::Nothing;
+        return (EvaluationStepResult::Nothing, vec![]);
     }
 
     // apply clues
     for clue in clues.iter() {
         let deductions = deduce_clue(board, clue);
         if deductions.len() > 0 {
-            board.apply_deductions(&deductions);
-            return EvaluationStepResult::DeductionsFound(clue.clone());
+            return (
+                EvaluationStepResult::DeductionsFound(clue.clone()),
+                deductions,
+            );
         }
     }
 
-    // apply hidden sets
+    // apply naked sets, then the harder to spot hidden sets
+    let deductions = deduce_naked_sets(board);
+    if !deductions.is_empty() {
+        return (EvaluationStepResult::NakedSetsFound, deductions);
+    }
+
     let deductions = deduce_hidden_sets(board);
     if deductions.len() > 0 {
-        board.apply_deductions(&deductions);
-        return EvaluationStepResult::HiddenSetsFound;
+        return (EvaluationStepResult::HiddenSetsFound, deductions);
     }
     trace!(
         target: "solver",
@@ -543,7 +641,14 @@ pub fn perform_evaluation_step(board: &mut GameBoard, clues: &Vec<Clue>) -> Eval
     for clue in clues.iter() {
         trace!(target: "solver", "Clue: {:?}", clue);
     }
-    EvaluationStepResult::Nothing
+    (EvaluationStepResult::Nothing, vec![])
+}
+
+/// note - does not auto-solve, caller must call auto-solve after applying evaluation
+pub fn perform_evaluation_step(board: &mut GameBoard, clues: &Vec<Clue>) -> EvaluationStepResult {
+    let (result, deductions) = find_evaluation_step(board, clues);
+    board.apply_deductions(&deductions);
+    result
 }
 
 #[cfg(test)]
@@ -553,9 +658,11 @@ mod tests {
     use super::*;
     use crate::game::tests::create_test_solution;
     use crate::{
-        model::{Clue, GameBoard, Tile},
+        model::{Clue, Difficulty, GameBoard, Solution, Tile},
+        solver::generate_clues,
         tests::UsingLogger,
     };
+    use std::sync::Arc;
 
     #[test]
     fn test_deduce_three_adjacent_empty_board() {
@@ -805,6 +912,129 @@ mod tests {
         assert!(deductions.contains(&Deduction::parse("1a not col 1 (Constraint)")));
     }
 
+    #[test]
+    fn test_deduce_between_empty_board() {
+        let input = "\
+0|abcd|abcd|abcd|abcd|
+-----------------
+1|abcd|abcd|abcd|abcd|
+-----------------";
+
+        let board = GameBoard::parse(input, create_test_solution(2, 4));
+
+        let clue = Clue::between(Tile::new(0, 'a'), Tile::new(1, 'b'), Tile::new(0, 'c'));
+
+        let deductions = deduce_clue(&board, &clue);
+        println!("Deductions: {:?}", deductions);
+        assert_eq!(deductions.len(), 2);
+        assert!(deductions.contains(&Deduction::parse("1b not col 0 (Constraint)")));
+        assert!(deductions.contains(&Deduction::parse("1b not col 3 (Constraint)")));
+    }
+
+    #[test]
+    fn test_deduce_between_with_selection() {
+        let input = "\
+0|abcd|abcd|abcd|<C> |
+-----------------
+1|abcd|abcd|abcd|abcd|
+-----------------";
+
+        let board = GameBoard::parse(input, create_test_solution(2, 4));
+
+        let clue = Clue::between(Tile::new(0, 'a'), Tile::new(1, 'b'), Tile::new(0, 'c'));
+
+        let deductions = deduce_clue(&board, &clue);
+        println!("Deductions: {:?}", deductions);
+        assert_eq!(deductions.len(), 3);
+        assert!(deductions.contains(&Deduction::parse("0a not col 2 (Constraint)")));
+        assert!(deductions.contains(&Deduction::parse("1b not col 0 (Constraint)")));
+        assert!(deductions.contains(&Deduction::parse("1b not col 3 (Constraint)")));
+    }
+
+    #[test]
+    fn test_deduce_n_apart_empty_board() {
+        let input = "\
+0|abcd|abcd|abcd|abcd|
+-----------------
+1|abcd|abcd|abcd|abcd|
+-----------------";
+
+        let board = GameBoard::parse(input, create_test_solution(2, 4));
+
+        let clue = Clue::n_apart(Tile::new(0, 'a'), Tile::new(1, 'b'), 3);
+
+        let deductions = deduce_clue(&board, &clue);
+        println!("Deductions: {:?}", deductions);
+        assert_eq!(deductions.len(), 4);
+        assert!(deductions.contains(&Deduction::parse("0a not col 1 (Constraint)")));
+        assert!(deductions.contains(&Deduction::parse("0a not col 2 (Constraint)")));
+        assert!(deductions.contains(&Deduction::parse("1b not col 1 (Constraint)")));
+        assert!(deductions.contains(&Deduction::parse("1b not col 2 (Constraint)")));
+    }
+
+    #[test]
+    fn test_deduce_n_apart_solvable_board() {
+        let input = "\
+0|abcd|<A> |abcd|abcd|
+-----------------
+1|abcd|abcd|abcd|abcd|
+-----------------";
+
+        let board = GameBoard::parse(input, create_test_solution(2, 4));
+
+        let clue = Clue::n_apart(Tile::new(0, 'a'), Tile::new(1, 'b'), 2);
+
+        let deductions = deduce_clue(&board, &clue);
+        println!("Deductions: {:?}", deductions);
+        assert_eq!(deductions.len(), 1);
+        assert!(deductions.contains(&Deduction::parse("1b is col 3 (LastRemaining)")));
+    }
+
+    #[test]
+    fn test_deduce_positional_empty_board() {
+        let input = "\
+0|abcd|abcd|abcd|abcd|
+-----------------
+1|abcd|abcd|abcd|abcd|
+-----------------";
+
+        let board = GameBoard::parse(input, create_test_solution(2, 4));
+
+        let deductions = deduce_clue(&board, &Clue::on_edge(Tile::new(0, 'a')));
+        println!("Deductions: {:?}", deductions);
+        assert_eq!(deductions.len(), 2);
+        assert!(deductions.contains(&Deduction::parse("0a not col 1 (Constraint)")));
+        assert!(deductions.contains(&Deduction::parse("0a not col 2 (Constraint)")));
+
+        let deductions = deduce_clue(&board, &Clue::not_on_edge(Tile::new(0, 'a')));
+        println!("Deductions: {:?}", deductions);
+        assert_eq!(deductions.len(), 2);
+        assert!(deductions.contains(&Deduction::parse("0a not col 0 (Constraint)")));
+        assert!(deductions.contains(&Deduction::parse("0a not col 3 (Constraint)")));
+
+        let deductions = deduce_clue(&board, &Clue::left_half(Tile::new(1, 'b')));
+        println!("Deductions: {:?}", deductions);
+        assert_eq!(deductions.len(), 2);
+        assert!(deductions.contains(&Deduction::parse("1b not col 2 (Constraint)")));
+        assert!(deductions.contains(&Deduction::parse("1b not col 3 (Constraint)")));
+    }
+
+    #[test]
+    fn test_deduce_on_edge_solvable_board() {
+        let input = "\
+0| bcd|abcd|abcd|abcd|
+-----------------
+1|abcd|abcd|abcd|abcd|
+-----------------";
+
+        let board = GameBoard::parse(input, create_test_solution(2, 4));
+
+        let deductions = deduce_clue(&board, &Clue::on_edge(Tile::new(0, 'a')));
+        println!("Deductions: {:?}", deductions);
+        assert_eq!(deductions.len(), 1);
+        assert!(deductions.contains(&Deduction::parse("0a is col 3 (LastRemaining)")));
+    }
+
     #[test]
     fn test_deduce_not_adjacent_empty_board() {
         let input = "\
@@ -1107,6 +1337,31 @@ mod tests {
         assert!(deductions.contains(&Deduction::parse("0b not col 2 (HiddenSet)")));
     }
 
+    #[test_context(UsingLogger)]
+    #[test]
+    fn test_deduce_naked_sets(_: &mut UsingLogger) {
+        let input = "\
+0|ab  |ab  |abcd|abcd|
+----------------------
+1|abcd|abcd|abcd|abcd|
+----------------------
+";
+        let board = GameBoard::parse(input, create_test_solution(2, 4));
+
+        let (naked_set, deductions) = find_naked_set_in_row(&board, 0).unwrap();
+        println!("Naked set: {:?}; deductions: {:?}", naked_set, deductions);
+        assert_eq!(naked_set.columns, vec![0, 1]);
+        assert_eq!(naked_set.variants, vec!['a', 'b']);
+        assert_eq!(deductions.len(), 4);
+        assert!(deductions.contains(&Deduction::parse("0a not col 2 (NakedSet)")));
+        assert!(deductions.contains(&Deduction::parse("0a not col 3 (NakedSet)")));
+        assert!(deductions.contains(&Deduction::parse("0b not col 2 (NakedSet)")));
+        assert!(deductions.contains(&Deduction::parse("0b not col 3 (NakedSet)")));
+
+        assert!(find_naked_set_in_row(&board, 1).is_none());
+        assert_eq!(deduce_naked_sets(&board), deductions);
+    }
+
     #[test]
     fn test_left_of_handler_empty_board() {
         let input = "\
@@ -1192,4 +1447,30 @@ mod tests {
         assert!(deductions.contains(&Deduction::parse("0a not col 3 (Constraint)")));
         assert!(deductions.contains(&Deduction::parse("0b not col 3 (Constraint)")));
     }
+
+    #[test]
+    fn test_deduction_cache_takes_the_same_steps() {
+        let solution = Arc::new(Solution::new(Difficulty::Hard, Some(7)));
+        let puzzle = generate_clues(&GameBoard::new(solution));
+        let mut cache = DeductionCache::new();
+        // the second solve is answered from the cache
+        for _ in 0..2 {
+            let mut board = puzzle.board.clone();
+            let mut cached_board = puzzle.board.clone();
+            loop {
+                let step = perform_evaluation_step(&mut board, &puzzle.clues);
+                assert_eq!(
+                    cache.perform_evaluation_step(&mut cached_board, &puzzle.clues),
+                    step
+                );
+                assert_eq!(cached_board, board);
+                if step == EvaluationStepResult::Nothing {
+                    break;
+                }
+                board.auto_solve_all();
+                cached_board.auto_solve_all();
+            }
+            assert!(board.is_complete());
+        }
+    }
 }
@@ -26,7 +26,7 @@ pub trait TernaryConstraint: std::fmt::Debug {
     /// Returns the three tiles involved in the constraint.
     fn vars(&self) -> Vec<Tile>;
     /// Checks whether the constraint is satisfied for the given assignment of values.
-    fn valid(&self, values: &Vec<usize>) -> bool;
+    fn valid(&self, values: &[usize]) -> bool;
 }
 
 #[derive(Debug, Clone, Hash)]
@@ -70,6 +70,39 @@ impl UnaryConstraint for EdgeConstraint {
     }
 }
 
+#[derive(Debug, Clone, Hash)]
+pub struct OnEdgeConstraint {
+    pub tile: Tile,
+    pub difficulty: Difficulty,
+}
+
+impl UnaryConstraint for OnEdgeConstraint {
+    fn var(&self) -> Tile {
+        self.tile
+    }
+
+    fn valid(&self, value: usize) -> bool {
+        value == 0 || value == self.difficulty.n_cols() - 1
+    }
+}
+
+#[derive(Debug, Clone, Hash)]
+pub struct LeftHalfConstraint {
+    pub tile: Tile,
+    pub difficulty: Difficulty,
+}
+
+impl UnaryConstraint for LeftHalfConstraint {
+    fn var(&self) -> Tile {
+        self.tile
+    }
+
+    fn valid(&self, value: usize) -> bool {
+        // with an odd number of columns, the middle column belongs to neither half
+        value < self.difficulty.n_cols() / 2
+    }
+}
+
 #[derive(Debug, Clone, Hash)]
 pub struct InSameColumnConstraint {
     pub tile_a: Tile,
@@ -131,7 +164,7 @@ impl TernaryConstraint for TwoApartNotMiddleConstraint {
         vec![self.tile_a, self.tile_not_b, self.tile_c]
     }
 
-    fn valid(&self, values: &Vec<usize>) -> bool {
+    fn valid(&self, values: &[usize]) -> bool {
         let a = values[0] as isize;
         let b = values[1] as isize;
         let c = values[2] as isize;
@@ -151,6 +184,28 @@ impl TernaryConstraint for TwoApartNotMiddleConstraint {
     }
 }
 
+#[derive(Debug, Clone, Hash)]
+pub struct BetweenConstraint {
+    pub tile_a: Tile,
+    pub tile_b: Tile,
+    pub tile_c: Tile,
+}
+
+impl TernaryConstraint for BetweenConstraint {
+    fn vars(&self) -> Vec<Tile> {
+        vec![self.tile_a, self.tile_b, self.tile_c]
+    }
+
+    fn valid(&self, values: &[usize]) -> bool {
+        let a = values[0];
+        let b = values[1];
+        let c = values[2];
+
+        // b should be strictly between a and c, in either direction
+        (a < b && b < c) || (c < b && b < a)
+    }
+}
+
 #[derive(Debug, Clone, Hash)]
 pub struct LessThanConstraint {
     pub tile_a: Tile,
@@ -179,7 +234,7 @@ impl TernaryConstraint for OneMatchesEitherConstraint {
         vec![self.tile_a, self.tile_b, self.tile_c]
     }
 
-    fn valid(&self, values: &Vec<usize>) -> bool {
+    fn valid(&self, values: &[usize]) -> bool {
         let a = values[0];
         let b = values[1];
         let c = values[2];
@@ -340,6 +395,266 @@ impl ClueConstraint for LeftOfHandler {
     }
 }
 
+#[derive(Clone, Debug)]
+struct BetweenHandler {
+    outer_tiles: [Tile; 2],
+    middle_tile: Tile,
+}
+
+impl BetweenHandler {
+    fn new(clue: &Clue) -> Self {
+        assert_eq!(
+            clue.assertions.len(),
+            3,
+            "Clue assertions must have exactly 3 elements"
+        );
+        Self {
+            outer_tiles: [clue.assertions[0].tile, clue.assertions[2].tile],
+            middle_tile: clue.assertions[1].tile,
+        }
+    }
+}
+
+impl ClueConstraint for BetweenHandler {
+    fn potential_solutions(
+        &self,
+        board: &GameBoard,
+        column: usize,
+    ) -> Vec<Vec<(usize, TileAssertion)>> {
+        let max_column = board.solution.n_variants - 1;
+        let mut solutions = Vec::new();
+
+        // the leftmost tile goes in this column; need room for the middle and rightmost tiles
+        if column + 2 > max_column {
+            return solutions;
+        }
+
+        // forward (A ... B ... C) and backward (C ... B ... A)
+        for (left_tile, right_tile) in [
+            (self.outer_tiles[0], self.outer_tiles[1]),
+            (self.outer_tiles[1], self.outer_tiles[0]),
+        ] {
+            if !board.is_candidate_available(left_tile.row, column, left_tile.variant) {
+                continue;
+            }
+            for middle_col in (column + 1)..max_column {
+                for right_col in (middle_col + 1)..=max_column {
+                    solutions.push(vec![
+                        (
+                            column,
+                            TileAssertion {
+                                tile: left_tile,
+                                assertion: true,
+                            },
+                        ),
+                        (
+                            middle_col,
+                            TileAssertion {
+                                tile: self.middle_tile,
+                                assertion: true,
+                            },
+                        ),
+                        (
+                            right_col,
+                            TileAssertion {
+                                tile: right_tile,
+                                assertion: true,
+                            },
+                        ),
+                    ]);
+                }
+            }
+        }
+
+        solutions.retain(|solution| is_partial_solution_valid(board, solution));
+        solutions
+    }
+
+    fn constraints(&self, difficulty: Difficulty) -> ConstraintSet {
+        let mut constraints = ConstraintSet::default();
+        constraints.unary_constraints.push(Box::new(EdgeConstraint {
+            tile: self.middle_tile,
+            difficulty,
+            allow_left: false,
+            allow_right: false,
+        }));
+        constraints
+            .ternary_constraints
+            .push(Box::new(BetweenConstraint {
+                tile_a: self.outer_tiles[0],
+                tile_b: self.middle_tile,
+                tile_c: self.outer_tiles[1],
+            }));
+        constraints
+    }
+}
+
+#[derive(Clone, Debug)]
+struct NApartHandler {
+    tile_a: Tile,
+    tile_b: Tile,
+    distance: usize,
+}
+
+impl NApartHandler {
+    fn new(clue: &Clue, distance: usize) -> Self {
+        assert_eq!(
+            clue.assertions.len(),
+            2,
+            "Clue assertions must have exactly 2 elements"
+        );
+        Self {
+            tile_a: clue.assertions[0].tile,
+            tile_b: clue.assertions[1].tile,
+            distance,
+        }
+    }
+}
+
+impl ClueConstraint for NApartHandler {
+    fn potential_solutions(
+        &self,
+        board: &GameBoard,
+        column: usize,
+    ) -> Vec<Vec<(usize, TileAssertion)>> {
+        let max_column = board.solution.n_variants - 1;
+        let right_col = column + self.distance;
+        if right_col > max_column {
+            return Vec::new();
+        }
+
+        // forward (A at this column) and backward (B at this column)
+        let mut solutions = vec![
+            vec![
+                (
+                    column,
+                    TileAssertion {
+                        tile: self.tile_a,
+                        assertion: true,
+                    },
+                ),
+                (
+                    right_col,
+                    TileAssertion {
+                        tile: self.tile_b,
+                        assertion: true,
+                    },
+                ),
+            ],
+            vec![
+                (
+                    column,
+                    TileAssertion {
+                        tile: self.tile_b,
+                        assertion: true,
+                    },
+                ),
+                (
+                    right_col,
+                    TileAssertion {
+                        tile: self.tile_a,
+                        assertion: true,
+                    },
+                ),
+            ],
+        ];
+
+        solutions.retain(|solution| is_partial_solution_valid(board, solution));
+        solutions
+    }
+
+    fn constraints(&self, _difficulty: Difficulty) -> ConstraintSet {
+        let mut constraints = ConstraintSet::default();
+        constraints
+            .binary_constraints
+            .push(Box::new(AdjacentConstraint {
+                tile_a: self.tile_a,
+                tile_b: self.tile_b,
+                distance: self.distance,
+            }));
+        constraints
+    }
+}
+
+#[derive(Clone, Debug)]
+struct PositionHandler {
+    tile: Tile,
+    position: HorizontalClueType,
+}
+
+impl PositionHandler {
+    fn new(clue: &Clue) -> Self {
+        assert_eq!(
+            clue.assertions.len(),
+            1,
+            "Clue assertions must have exactly 1 element"
+        );
+        let ClueType::Horizontal(position) = clue.clue_type else {
+            panic!("Positional clues must be horizontal");
+        };
+        Self {
+            tile: clue.assertions[0].tile,
+            position,
+        }
+    }
+
+    fn unary_constraint(&self, difficulty: Difficulty) -> Box<dyn UnaryConstraint> {
+        match self.position {
+            HorizontalClueType::OnEdge => Box::new(OnEdgeConstraint {
+                tile: self.tile,
+                difficulty,
+            }),
+            HorizontalClueType::NotOnEdge => Box::new(EdgeConstraint {
+                tile: self.tile,
+                difficulty,
+                allow_left: false,
+                allow_right: false,
+            }),
+            HorizontalClueType::LeftHalf => Box::new(LeftHalfConstraint {
+                tile: self.tile,
+                difficulty,
+            }),
+            _ => panic!("Not a positional clue type: {:?}", self.position),
+        }
+    }
+}
+
+impl ClueConstraint for PositionHandler {
+    fn potential_solutions(
+        &self,
+        board: &GameBoard,
+        column: usize,
+    ) -> Vec<Vec<(usize, TileAssertion)>> {
+        if !self
+            .unary_constraint(board.solution.difficulty)
+            .valid(column)
+        {
+            return Vec::new();
+        }
+
+        let solution = vec![(
+            column,
+            TileAssertion {
+                tile: self.tile,
+                assertion: true,
+            },
+        )];
+        if is_partial_solution_valid(board, &solution) {
+            vec![solution]
+        } else {
+            Vec::new()
+        }
+    }
+
+    fn constraints(&self, difficulty: Difficulty) -> ConstraintSet {
+        let mut constraints = ConstraintSet::default();
+        constraints
+            .unary_constraints
+            .push(self.unary_constraint(difficulty));
+        constraints
+    }
+}
+
 impl ClueConstraint for NotAdjacentHandler {
     fn potential_solutions(
         &self,
@@ -579,6 +894,11 @@ pub fn create_clue_constraint(clue: &Clue) -> Box<dyn ClueConstraint> {
             HorizontalClueType::TwoApartNotMiddle => Box::new(AdjacentHandler::new(clue)),
             HorizontalClueType::NotAdjacent => Box::new(NotAdjacentHandler::new(clue)),
             HorizontalClueType::LeftOf => Box::new(LeftOfHandler::new(clue)),
+            HorizontalClueType::Between => Box::new(BetweenHandler::new(clue)),
+            HorizontalClueType::NApart(distance) => Box::new(NApartHandler::new(clue, *distance)),
+            HorizontalClueType::OnEdge
+            | HorizontalClueType::NotOnEdge
+            | HorizontalClueType::LeftHalf => Box::new(PositionHandler::new(clue)),
         },
         ClueType::Vertical(v_type) => match v_type {
             VerticalClueType::OneMatchesEither => Box::new(OneMatchesEitherHandler::new(clue)),
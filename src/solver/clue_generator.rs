@@ -1,21 +1,37 @@
 use super::{
-    clue_generator_state::{ClueEvaluation, ClueGeneratorState},
-    puzzle_variants::{random_puzzle_variant, PuzzleVariant},
+    clue_generator_state::{ClueEvaluation, ClueGeneratorState, ClueGeneratorStats},
+    puzzle_variants::{random_puzzle_variant, PuzzleVariant, PuzzleVariantType},
 };
 
 use log::{info, trace, warn};
-use std::{collections::BTreeSet, rc::Rc};
+use rayon::{prelude::*, ThreadPool, ThreadPoolBuilder};
+use std::{
+    collections::BTreeSet,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
 
 use crate::{
-    model::{Clue, ClueSet, Difficulty, GameBoard, Tile},
-    solver::candidate_solver::{perform_evaluation_step, EvaluationStepResult},
+    model::{Clue, ClueSet, Difficulty, GameBoard, GenerationProgress, GeneratorRng, Tile},
+    solver::candidate_solver::EvaluationStepResult,
 };
 
 use super::deduce_clue;
 
+/// A look-ahead clue waiting to be evaluated, along with the generator state right after it was
+/// drawn.
+struct CandidateClue {
+    clue: Clue,
+    rng: GeneratorRng,
+    stats: ClueGeneratorStats,
+    clue_generation_loops: usize,
+}
+
 fn evaluate_clue(
     board: &GameBoard,
-    puzzle_variant: &Box<dyn PuzzleVariant>,
+    puzzle_variant: &dyn PuzzleVariant,
     clue: &Clue,
 ) -> ClueEvaluation {
     let deductions = deduce_clue(board, clue);
@@ -31,11 +47,33 @@ fn evaluate_clue(
     }
 }
 
+/// Evaluates the candidates on the thread pool if there is one; results keep the candidates' order.
+fn evaluate_clues(
+    pool: Option<&ThreadPool>,
+    board: &GameBoard,
+    puzzle_variant: &dyn PuzzleVariant,
+    candidates: &[CandidateClue],
+) -> Vec<ClueEvaluation> {
+    match pool {
+        Some(pool) => pool.install(|| {
+            candidates
+                .par_iter()
+                .map(|candidate| evaluate_clue(board, puzzle_variant, &candidate.clue))
+                .collect()
+        }),
+        None => candidates
+            .iter()
+            .map(|candidate| evaluate_clue(board, puzzle_variant, &candidate.clue))
+            .collect(),
+    }
+}
+
 pub struct ClueGeneratorResult {
     pub clues: Vec<Clue>,
     pub revealed_tiles: Vec<Tile>,
     /// The board after revealing initial tiles
     pub board: GameBoard,
+    pub variant_type: PuzzleVariantType,
 }
 
 pub fn apply_selections(board: &GameBoard, tiles: &BTreeSet<Tile>) -> GameBoard {
@@ -47,6 +85,47 @@ pub fn apply_selections(board: &GameBoard, tiles: &BTreeSet<Tile>) -> GameBoard
 }
 
 pub fn generate_clues(init_board: &GameBoard) -> ClueGeneratorResult {
+    generate_clues_in(init_board, None, &AtomicBool::new(false), &|_| {})
+        .expect("generation is never cancelled")
+}
+
+/// Same as `generate_clues`, but evaluates look-ahead clues on `n_threads` worker threads. The
+/// result for a given seed is identical whatever the thread count.
+pub fn generate_clues_parallel(init_board: &GameBoard, n_threads: usize) -> ClueGeneratorResult {
+    generate_clues_cancellable(init_board, n_threads, &AtomicBool::new(false), &|_| {})
+        .expect("generation is never cancelled")
+}
+
+/// Same as `generate_clues_parallel`, reporting progress each time a clue is placed. Returns
+/// `None` if `cancelled` is set before generation completes.
+pub fn generate_clues_cancellable(
+    init_board: &GameBoard,
+    n_threads: usize,
+    cancelled: &AtomicBool,
+    on_progress: &dyn Fn(GenerationProgress),
+) -> Option<ClueGeneratorResult> {
+    if n_threads <= 1 {
+        return generate_clues_in(init_board, None, cancelled, on_progress);
+    }
+    match ThreadPoolBuilder::new().num_threads(n_threads).build() {
+        Ok(pool) => generate_clues_in(init_board, Some(&pool), cancelled, on_progress),
+        Err(e) => {
+            warn!(
+                target: "clue_generator",
+                "Failed to start generation thread pool, generating on this thread: {:?}",
+                e
+            );
+            generate_clues_in(init_board, None, cancelled, on_progress)
+        }
+    }
+}
+
+fn generate_clues_in(
+    init_board: &GameBoard,
+    pool: Option<&ThreadPool>,
+    cancelled: &AtomicBool,
+    on_progress: &dyn Fn(GenerationProgress),
+) -> Option<ClueGeneratorResult> {
     trace!(
         target: "clue_generator",
         "Generating clues... for board: {:?}; solution is {:?}",
@@ -75,7 +154,20 @@ pub fn generate_clues(init_board: &GameBoard) -> ClueGeneratorResult {
     let seeded_tiles = state.revealed_tiles.clone();
     let init_board = apply_selections(&init_board, &seeded_tiles);
 
+    let mut progress = GenerationProgress::new(init_board.solution.difficulty);
     while !state.board.is_complete() {
+        if cancelled.load(Ordering::Relaxed) {
+            info!(
+                target: "clue_generator",
+                "Generation cancelled for seed {:?}",
+                init_board.solution.seed
+            );
+            return None;
+        }
+        progress.clues_placed = state.clues.len();
+        progress.tiles_solved = state.board.get_selected_tiles().len();
+        on_progress(progress);
+
         info!(
             target: "clue_generator",
             "Generating clues..."
@@ -83,48 +175,70 @@ pub fn generate_clues(init_board: &GameBoard) -> ClueGeneratorResult {
         let mut possible_clues = Vec::new();
         let mut clue_generation_loops = 0;
         let clue_candidate_count = state.board.solution.difficulty.look_ahead_count();
+        let max_clue_generation_loops = clue_candidate_count * 1000;
         state.reset_stats();
         while possible_clues.len() < clue_candidate_count
-            && clue_generation_loops < clue_candidate_count * 1000
-        /* TODO - need to make the clue generation guided to try to choose at least one unsolved tile. */
+            && clue_generation_loops < max_clue_generation_loops
         {
-            clue_generation_loops += 1;
-            if let Some(clue) = state.generate_random_clue_type(&clue_weights, None) {
-                if state.would_exceed_usage_limits(&clue) {
-                    trace!(
-                        target: "clue_generator",
-                        "Skipping clue with usage limits exceeded: {:?}",
-                        clue
-                    );
-                    continue;
-                }
-                let non_singleton_intersecting_clues = state
-                    .clues
-                    .iter()
-                    .find(|c| clue.non_singleton_intersects(c));
+            // draw enough candidates to fill the look-ahead, then evaluate them as a batch
+            let mut candidates = Vec::new();
+            while candidates.len() < clue_candidate_count - possible_clues.len()
+                && clue_generation_loops < max_clue_generation_loops
+            /* TODO - need to make the clue generation guided to try to choose at least one unsolved tile. */
+            {
+                clue_generation_loops += 1;
+                if let Some(clue) = state.generate_random_clue_type(&clue_weights, None) {
+                    if state.would_exceed_usage_limits(&clue) {
+                        trace!(
+                            target: "clue_generator",
+                            "Skipping clue with usage limits exceeded: {:?}",
+                            clue
+                        );
+                        continue;
+                    }
+                    let non_singleton_intersecting_clues = state
+                        .clues
+                        .iter()
+                        .find(|c| clue.non_singleton_intersects(c));
 
-                if non_singleton_intersecting_clues.is_some() {
-                    state.stats.n_rejected_non_singleton_intersecting_clues += 1;
-                    trace!(
-                        target: "clue_generator",
-                        "Skipping clue with non-singleton intersecting clues: {:?} - {:?}",
+                    if non_singleton_intersecting_clues.is_some() {
+                        state.stats.n_rejected_non_singleton_intersecting_clues += 1;
+                        trace!(
+                            target: "clue_generator",
+                            "Skipping clue with non-singleton intersecting clues: {:?} - {:?}",
+                            clue,
+                            non_singleton_intersecting_clues.unwrap()
+                        );
+                        trace!(
+                            target: "clue_generator",
+                            "Board state was {:?}",
+                            state.board
+                        );
+                        continue;
+                    }
+                    candidates.push(CandidateClue {
                         clue,
-                        non_singleton_intersecting_clues.unwrap()
-                    );
+                        rng: state.rng.clone(),
+                        stats: state.stats.clone(),
+                        clue_generation_loops,
+                    });
+                } else {
                     trace!(
                         target: "clue_generator",
-                        "Board state was {:?}",
-                        state.board
+                        "Failed to generate clue, trying again"
                     );
-                    continue;
                 }
-                let evaluation = evaluate_clue(&state.board.clone(), &puzzle_variant, &clue);
+            }
+
+            let evaluations =
+                evaluate_clues(pool, &state.board, puzzle_variant.as_ref(), &candidates);
+            for (candidate, evaluation) in candidates.into_iter().zip(evaluations) {
                 if evaluation.deductions.len() == 0 {
                     state.stats.n_rejected_no_deductions += 1;
                     trace!(
                         target: "clue_generator",
                         "Skipping clue with no deductions: {:?}",
-                        clue
+                        evaluation.clue
                     );
                     trace!(
                         target: "clue_generator",
@@ -136,15 +250,19 @@ pub fn generate_clues(init_board: &GameBoard) -> ClueGeneratorResult {
                 trace!(
                     target: "clue_generator",
                     "Considering clue {:?} with # deductions {:?}",
-                    clue,
+                    evaluation.clue,
                     evaluation.deductions.len()
                 );
                 possible_clues.push(evaluation);
-            } else {
-                trace!(
-                    target: "clue_generator",
-                    "Failed to generate clue, trying again"
-                );
+                if possible_clues.len() == clue_candidate_count {
+                    // rewind to where a one-at-a-time search would have stopped drawing clues
+                    let n_rejected_no_deductions = state.stats.n_rejected_no_deductions;
+                    state.rng = candidate.rng;
+                    state.stats = candidate.stats;
+                    state.stats.n_rejected_no_deductions = n_rejected_no_deductions;
+                    clue_generation_loops = candidate.clue_generation_loops;
+                    break;
+                }
             }
         }
         info!(
@@ -163,7 +281,9 @@ pub fn generate_clues(init_board: &GameBoard) -> ClueGeneratorResult {
             state.add_clue(&evaluated_clue.clue, &evaluated_clue.deductions);
 
             // re-evaluate clues from the beginning after applying new evidence, and re-solve any hidden pairs.
-            while perform_evaluation_step(&mut state.board, &state.clues)
+            while state
+                .deduction_cache
+                .perform_evaluation_step(&mut state.board, &state.clues)
                 != EvaluationStepResult::Nothing
             {}
             assert!(
@@ -182,6 +302,9 @@ pub fn generate_clues(init_board: &GameBoard) -> ClueGeneratorResult {
 
     ClueGeneratorState::merge_adjacent_clues(&mut state.clues);
     state.optimized_prune(&init_board);
+    if init_board.solution.difficulty.minimize_clues() {
+        state.minimize_clues(&init_board);
+    }
     trace!(
         target: "clue_generator",
         "Solved board: {:?}",
@@ -193,20 +316,23 @@ pub fn generate_clues(init_board: &GameBoard) -> ClueGeneratorResult {
         board_with_revealed_tiles.select_tile_from_solution(*tile);
     }
 
-    let clue_set = Rc::new(ClueSet::new(state.clues.clone()));
+    let clue_set = Arc::new(ClueSet::new(state.clues.clone()));
     board_with_revealed_tiles.set_clues(clue_set);
 
-    ClueGeneratorResult {
+    Some(ClueGeneratorResult {
         clues: state.clues,
         revealed_tiles: state.revealed_tiles.into_iter().collect(),
         board: board_with_revealed_tiles,
-    }
+        variant_type: puzzle_variant.get_variant_type(),
+    })
 }
 
 #[cfg(test)]
 mod tests {
     use crate::{
         model::{Difficulty, GameBoard, Solution},
+        solver::candidate_solver::perform_evaluation_step,
+        solver::uniqueness_verifier::{count_solutions, find_alternative_solutions},
         tests::UsingLogger,
     };
     use test_context::test_context;
@@ -233,6 +359,12 @@ mod tests {
                 result.clues
             );
             assert!(result.clues.len() > 0);
+            assert_eq!(
+                count_solutions(&result.board),
+                1,
+                "Puzzle has alternative solutions: {:?}",
+                find_alternative_solutions(&result.board, 1)
+            );
             // assert solvable
             let mut board = result.board.clone();
             let clue_set = ClueSet::new(result.clues.clone());
@@ -290,4 +422,47 @@ mod tests {
             assert_eq!(tile1, tile2);
         }
     }
+
+    #[test_context(UsingLogger)]
+    #[test]
+    fn test_generate_clues_cancellable(_: &mut UsingLogger) {
+        let solution = Solution::new(Difficulty::Moderate, Some(7));
+        let board = GameBoard::new(solution.into());
+
+        let cancelled = AtomicBool::new(false);
+        let reports = std::cell::RefCell::new(Vec::new());
+        let result = generate_clues_cancellable(&board, 1, &cancelled, &|progress| {
+            reports.borrow_mut().push(progress);
+            if progress.clues_placed == 3 {
+                cancelled.store(true, Ordering::Relaxed);
+            }
+        });
+        assert!(result.is_none());
+
+        let reports = reports.into_inner();
+        // progress is reported from the start, and nothing after the report that cancelled
+        assert!(reports.len() > 1);
+        assert!(reports[0].clues_placed < 3);
+        assert_eq!(reports.last().unwrap().clues_placed, 3);
+        assert!(reports
+            .windows(2)
+            .all(|pair| pair[0].tiles_solved <= pair[1].tiles_solved));
+        assert!(reports.iter().all(|p| p.completeness() < 1.0));
+    }
+
+    #[test_context(UsingLogger)]
+    #[test]
+    fn test_generate_clues_parallel_matches_sequential(_: &mut UsingLogger) {
+        for seed in 0..3 {
+            let solution = Solution::new(Difficulty::Moderate, Some(seed));
+            let board = GameBoard::new(solution.into());
+
+            let sequential = generate_clues(&board);
+            for n_threads in [2, 4] {
+                let parallel = generate_clues_parallel(&board, n_threads);
+                assert_eq!(sequential.clues, parallel.clues);
+                assert_eq!(sequential.revealed_tiles, parallel.revealed_tiles);
+            }
+        }
+    }
 }
@@ -1,17 +1,16 @@
 use log::{info, trace};
 use rand::{
-    rngs::StdRng,
     seq::{IndexedMutRandom, IndexedRandom, IteratorRandom, SliceRandom},
-    Rng, RngCore, SeedableRng,
+    Rng,
 };
 use std::collections::{BTreeMap, BTreeSet};
 
 use crate::{
     model::{
-        Clue, ClueOrientation, ClueType, Deduction, GameBoard, HorizontalClueType, Tile,
-        TileAssertion, VerticalClueType,
+        Clue, ClueOrientation, ClueType, Deduction, GameBoard, GeneratorRng, HorizontalClueType,
+        Tile, TileAssertion, VerticalClueType,
     },
-    solver::candidate_solver::{perform_evaluation_step, EvaluationStepResult},
+    solver::candidate_solver::{DeductionCache, EvaluationStepResult},
 };
 
 use super::puzzle_variants::WeightedClueType;
@@ -21,7 +20,7 @@ pub const MAX_VERT_CLUES: usize = 48;
 const MAX_HORIZONTAL_TILE_USAGE: usize = 4;
 const MAX_VERTICAL_TILE_USAGE: usize = 3;
 
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone)]
 pub struct ClueGeneratorStats {
     pub n_rejected_no_deductions: usize,
     pub n_rejected_tile_usage_horiz: usize,
@@ -44,7 +43,7 @@ pub struct ClueGeneratorState {
     pub tiles_with_evidence: BTreeSet<(usize, Tile)>,
     pub tiles_without_evidence: BTreeSet<(usize, Tile)>,
     pub clues: Vec<Clue>,
-    pub rng: Box<dyn RngCore>,
+    pub rng: GeneratorRng,
     pub horizontal_clues: usize,
     pub vertical_clues: usize,
     pub unsolved_columns: BTreeSet<usize>,
@@ -57,6 +56,8 @@ pub struct ClueGeneratorState {
     pub tile_horiz_usage_remaining: BTreeMap<Tile, usize>,
     pub tile_vert_usage_remaining: BTreeMap<Tile, usize>,
     pub stats: ClueGeneratorStats,
+    /// Shared by every solve of the puzzle being generated, which starts from the same tiles
+    pub deduction_cache: DeductionCache,
 }
 
 impl ClueGeneratorState {
@@ -90,7 +91,7 @@ impl ClueGeneratorState {
             }
         }
 
-        let rng = Box::new(StdRng::seed_from_u64(board.solution.seed));
+        let rng = GeneratorRng::new(board.solution.generator_version, board.solution.seed);
 
         Self {
             selection_count_by_row,
@@ -111,6 +112,7 @@ impl ClueGeneratorState {
             tile_horiz_usage_remaining,
             tile_vert_usage_remaining,
             stats: ClueGeneratorStats::default(),
+            deduction_cache: DeductionCache::new(),
         }
     }
     pub fn reset_stats(&mut self) {
@@ -321,6 +323,51 @@ impl ClueGeneratorState {
         self.tiles_without_evidence.remove(&(column, tile));
     }
 
+    /// Drops every clue the puzzle can be solved without, and weakens the rest where possible (eg.
+    /// removing a negative assertion), checking each change against the full deduction set.
+    /// `board` is the board with only the revealed tiles selected.
+    pub fn minimize_clues(&mut self, board: &GameBoard) {
+        let initial_count = self.clues.len();
+        let mut index = 0;
+        while index < self.clues.len() {
+            let clue = self.clues.remove(index);
+            if is_solvable_with(board, &self.clues, &mut self.deduction_cache) {
+                trace!(
+                    target: "clue_generator",
+                    "Clue {:?} is redundant",
+                    clue
+                );
+                continue;
+            }
+
+            if let Some(weaker_clue) = clue.without_negative_assertions() {
+                if weaker_clue != clue {
+                    self.clues.insert(index, weaker_clue);
+                    if is_solvable_with(board, &self.clues, &mut self.deduction_cache) {
+                        trace!(
+                            target: "clue_generator",
+                            "Clue {:?} weakened to {:?}",
+                            clue,
+                            self.clues[index]
+                        );
+                        index += 1;
+                        continue;
+                    }
+                    self.clues.remove(index);
+                }
+            }
+
+            self.clues.insert(index, clue);
+            index += 1;
+        }
+        info!(
+            target: "clue_generator",
+            "Minimized clues from {} to {}",
+            initial_count,
+            self.clues.len()
+        );
+    }
+
     pub fn optimized_prune(&mut self, board: &GameBoard) {
         let mut required_clues: BTreeSet<Clue> = BTreeSet::new();
         let mut clues = self.clues.clone().into_iter().rev().collect::<Vec<_>>();
@@ -334,7 +381,9 @@ impl ClueGeneratorState {
             let mut used_clues = BTreeSet::new();
             let mut board = board.clone();
             while !board.is_complete() {
-                let deduction = perform_evaluation_step(&mut board, &clues);
+                let deduction = self
+                    .deduction_cache
+                    .perform_evaluation_step(&mut board, &clues);
                 match deduction {
                     EvaluationStepResult::Nothing => {
                         panic!("Puzzle not solvable! {:?}", board);
@@ -342,7 +391,8 @@ impl ClueGeneratorState {
                     EvaluationStepResult::DeductionsFound(clue) => {
                         used_clues.insert(clue);
                     }
-                    EvaluationStepResult::HiddenSetsFound => {
+                    EvaluationStepResult::NakedSetsFound
+                    | EvaluationStepResult::HiddenSetsFound => {
                         // nothing
                     }
                 }
@@ -557,6 +607,89 @@ impl ClueGeneratorState {
                             Some(Clue::left_of(tile, seed))
                         }
                     }
+
+                    HorizontalClueType::Between => {
+                        let (_, seed_col) = self.board.solution.find_tile(seed);
+                        let mut columns = (0..self.board.solution.n_variants)
+                            .filter(|&c| c != seed_col)
+                            .choose_multiple(&mut self.rng, 2);
+                        columns.push(seed_col);
+                        columns.sort();
+
+                        let mut tiles = Vec::new();
+                        for col in columns {
+                            if col == seed_col {
+                                tiles.push(seed);
+                            } else {
+                                let row = self.rng.random_range(0..self.board.solution.n_rows);
+                                tiles.push(self.board.solution.get(row, col));
+                            }
+                        }
+                        if self.rng.random_bool(0.5) {
+                            tiles.reverse();
+                        }
+
+                        Some(Clue::between(tiles[0], tiles[1], tiles[2]))
+                    }
+
+                    HorizontalClueType::NApart(distance) => {
+                        let (_, seed_col) = self.board.solution.find_tile(seed);
+                        let n_cols = self.board.solution.n_variants;
+                        let mut distance = *distance;
+                        if self.board.solution.generator_version < 3 {
+                            // older generators shortened the distance to fit the seed
+                            distance = distance.min(seed_col.max(n_cols - 1 - seed_col));
+                            if distance < 2 {
+                                return None;
+                            }
+                        }
+                        let possible_cols = [
+                            seed_col.checked_sub(distance),
+                            Some(seed_col + distance).filter(|&c| c < n_cols),
+                        ]
+                        .into_iter()
+                        .flatten()
+                        .collect::<Vec<_>>();
+                        if possible_cols.is_empty() {
+                            // the seed sits too close to the middle for this distance
+                            return None;
+                        }
+
+                        let row = self.rng.random_range(0..self.board.solution.n_rows);
+                        let col = *possible_cols.choose(&mut self.rng).unwrap();
+                        let tile = self.board.solution.get(row, col);
+
+                        Some(Clue::n_apart(seed, tile, distance))
+                    }
+
+                    HorizontalClueType::OnEdge
+                    | HorizontalClueType::NotOnEdge
+                    | HorizontalClueType::LeftHalf => {
+                        let n_cols = self.board.solution.n_variants;
+                        let possible_cols = (0..n_cols)
+                            .filter(|&col| match tpe {
+                                HorizontalClueType::OnEdge => col == 0 || col == n_cols - 1,
+                                HorizontalClueType::NotOnEdge => col != 0 && col != n_cols - 1,
+                                _ => col < n_cols / 2,
+                            })
+                            .collect::<Vec<_>>();
+
+                        // fall back to a random tile in an allowed column if the seed doesn't fit
+                        let (_, seed_col) = self.board.solution.find_tile(seed);
+                        let tile = if possible_cols.contains(&seed_col) {
+                            seed
+                        } else {
+                            let row = self.rng.random_range(0..self.board.solution.n_rows);
+                            let col = *possible_cols.choose(&mut self.rng).unwrap();
+                            self.board.solution.get(row, col)
+                        };
+
+                        match tpe {
+                            HorizontalClueType::OnEdge => Some(Clue::on_edge(tile)),
+                            HorizontalClueType::NotOnEdge => Some(Clue::not_on_edge(tile)),
+                            _ => Some(Clue::left_half(tile)),
+                        }
+                    }
                 }
             }
             ClueType::Vertical(tpe) => {
@@ -674,8 +807,31 @@ impl ClueGeneratorState {
     }
 }
 
+/// Whether repeatedly applying deductions from `clues` (plus naked / hidden sets) completes the board
+fn is_solvable_with(
+    board: &GameBoard,
+    clues: &[Clue],
+    deduction_cache: &mut DeductionCache,
+) -> bool {
+    let mut board = board.clone();
+    while !board.is_complete() {
+        if deduction_cache.perform_evaluation_step(&mut board, clues)
+            == EvaluationStepResult::Nothing
+        {
+            return false;
+        }
+        board.auto_solve_all();
+    }
+    true
+}
+
 #[cfg(test)]
 mod tests {
+    use crate::{
+        model::{Difficulty, Solution},
+        solver::generate_clues,
+    };
+
     use super::*;
 
     #[test]
@@ -705,4 +861,69 @@ mod tests {
         ClueGeneratorState::merge_adjacent_clues(&mut clues);
         assert_eq!(clues.len(), 2);
     }
+
+    #[test]
+    fn test_minimize_clues() {
+        let solution = Solution::new(Difficulty::Moderate, Some(11));
+        let result = generate_clues(&GameBoard::new(solution.into()));
+        let board = result.board.clone();
+
+        // pad the generated clues with ones that are true but unnecessary
+        let (t1, t2, t3) = (
+            board.solution.get(0, 0),
+            board.solution.get(1, 0),
+            board.solution.get(2, 1),
+        );
+        let mut state = ClueGeneratorState::new(board.clone());
+        state.clues = result.clues.clone();
+        state.clues.push(Clue::two_in_column_without(t1, t3, t2));
+        state.clues.push(Clue::two_in_column(t1, t2));
+
+        state.minimize_clues(&board);
+        assert!(state.clues.len() <= result.clues.len());
+        assert!(is_solvable_with(
+            &board,
+            &state.clues,
+            &mut DeductionCache::new()
+        ));
+        for index in 0..state.clues.len() {
+            let mut clues = state.clues.clone();
+            clues.remove(index);
+            assert!(
+                !is_solvable_with(&board, &clues, &mut DeductionCache::new()),
+                "Clue {:?} is not required",
+                state.clues[index]
+            );
+        }
+    }
+
+    /// Every clue is required and none can be weakened
+    fn is_minimal(board: &GameBoard, clues: &Vec<Clue>) -> bool {
+        (0..clues.len()).all(|index| {
+            let mut without = clues.clone();
+            without.remove(index);
+            let mut weakened = clues.clone();
+            weakened[index] = clues[index]
+                .without_negative_assertions()
+                .unwrap_or_else(|| clues[index].clone());
+            !is_solvable_with(board, &without, &mut DeductionCache::new())
+                && (weakened == *clues
+                    || !is_solvable_with(board, &weakened, &mut DeductionCache::new()))
+        })
+    }
+
+    #[test]
+    fn test_generate_clues_minimizes_per_difficulty() {
+        // this Moderate puzzle is left with a redundant clue
+        for (difficulty, seed) in [(Difficulty::Moderate, 2), (Difficulty::Veteran, 5)] {
+            let solution = Solution::new(difficulty, Some(seed));
+            let result = generate_clues(&GameBoard::new(solution.into()));
+            assert_eq!(
+                is_minimal(&result.board, &result.clues),
+                difficulty.minimize_clues(),
+                "{:?}",
+                difficulty
+            );
+        }
+    }
 }
@@ -1,324 +1,317 @@
-use std::{
-    collections::{BTreeMap, BTreeSet},
-    rc::Rc,
-};
+use std::collections::BTreeSet;
 
 use log::{trace, warn};
 
-use crate::{
-    model::{Clue, Deduction, DeductionKind, GameBoard, Tile, TileAssertion},
-    solver::candidate_solver::Coordinates,
-};
+use crate::model::{Clue, Deduction, DeductionKind, GameBoard, Tile, TileAssertion, MAX_GRID_SIZE};
 
 use super::{
     clue_constraint::{BinaryConstraint, TernaryConstraint, UnaryConstraint},
-    solver_helpers::get_domains_and_constraints,
+    solver_helpers::get_clue_constraints,
 };
 
-// / A simple Tile type.
+/// Bitmask of the columns a tile may still occupy; bit `n` is set if column `n` is possible.
+type Domain = u8;
+
+fn domain_columns(domain: Domain) -> impl Iterator<Item = usize> {
+    (0..MAX_GRID_SIZE).filter(move |col| domain & (1 << col) != 0)
+}
+
+/// A constraint compiled down to lookup tables over column bitmasks, so that revising a domain
+/// is a handful of bit operations rather than repeated calls into the constraint.
 #[derive(Debug)]
-/// A work item that the solver must process.
-enum WorkItem {
+enum CompiledConstraint {
     Unary {
-        constraint: Rc<dyn UnaryConstraint>,
         tile: Tile,
+        /// Columns the tile may occupy
+        allowed: Domain,
     },
     Binary {
-        constraint: Rc<dyn BinaryConstraint>,
         x: Tile,
         y: Tile,
+        /// `x_supports[vx]` holds the values of `y` that are valid alongside `x = vx`
+        x_supports: [Domain; MAX_GRID_SIZE],
+        /// `y_supports[vy]` holds the values of `x` that are valid alongside `y = vy`
+        y_supports: [Domain; MAX_GRID_SIZE],
+    },
+    Ternary {
+        vars: [Tile; 3],
+        /// `supports[v0][v1]` holds the values of the third tile valid alongside the first two
+        supports: [[Domain; MAX_GRID_SIZE]; MAX_GRID_SIZE],
     },
-    Ternary(Rc<dyn TernaryConstraint>),
-}
-
-/// The solver state.
-/// - `domains` maps each tile to its set of possible column values.
-/// - `binary_constraints` and `ternary_constraints` store our constraints.
-pub struct ConstraintSolver {
-    domains: BTreeMap<Tile, BTreeSet<usize>>,
-    binary_constraints: Vec<Rc<dyn BinaryConstraint>>,
-    unary_constraints: Vec<Rc<dyn UnaryConstraint>>,
-    ternary_constraints: Vec<Rc<dyn TernaryConstraint>>,
-    worklist: Vec<WorkItem>,
 }
 
-impl ConstraintSolver {
-    fn new(
-        domains: BTreeMap<Tile, BTreeSet<usize>>,
-        unary_constraints: Vec<Rc<dyn UnaryConstraint>>,
-        binary_constraints: Vec<Rc<dyn BinaryConstraint>>,
-        ternary_constraints: Vec<Rc<dyn TernaryConstraint>>,
-    ) -> Self {
-        let mut solver = Self {
-            domains,
-            binary_constraints,
-            unary_constraints,
-            ternary_constraints,
-            worklist: Vec::new(),
-        };
-        solver.worklist = solver.initialize_worklist();
-        solver
+impl CompiledConstraint {
+    fn unary(constraint: &dyn UnaryConstraint, n_cols: usize) -> Self {
+        let allowed = (0..n_cols)
+            .filter(|&col| constraint.valid(col))
+            .fold(0, |acc, col| acc | (1 << col));
+        CompiledConstraint::Unary {
+            tile: constraint.var(),
+            allowed,
+        }
     }
 
-    fn reduce_domains(&mut self) {
-        trace!(target: "constraint_solver", "AC3 iteration");
-        while let Some(item) = self.worklist.pop() {
-            trace!(target: "constraint_solver", "Domains: {:?}", self.domains);
-            trace!(target: "constraint_solver", "Processing item: {:?}", item);
-            match item {
-                WorkItem::Unary { constraint, tile } => {
-                    self.process_unary_item(constraint.as_ref(), tile);
-                }
-                WorkItem::Binary { constraint, x, y } => {
-                    self.process_binary_item(constraint.as_ref(), x, y);
+    fn binary(constraint: &dyn BinaryConstraint, n_cols: usize) -> Self {
+        let (x, y) = constraint.vars();
+        let mut x_supports = [0; MAX_GRID_SIZE];
+        let mut y_supports = [0; MAX_GRID_SIZE];
+        for (vx, x_support) in x_supports.iter_mut().enumerate().take(n_cols) {
+            for (vy, y_support) in y_supports.iter_mut().enumerate().take(n_cols) {
+                if constraint.valid(vx, vy) {
+                    *x_support |= 1 << vy;
+                    *y_support |= 1 << vx;
                 }
-                WorkItem::Ternary(constraint) => {
-                    self.process_ternary_item(constraint.as_ref());
+            }
+        }
+        CompiledConstraint::Binary {
+            x,
+            y,
+            x_supports,
+            y_supports,
+        }
+    }
+
+    /// Ternary constraints that don't relate exactly three tiles are dropped.
+    fn ternary(constraint: &dyn TernaryConstraint, n_cols: usize) -> Option<Self> {
+        let vars: [Tile; 3] = constraint.vars().try_into().ok()?;
+        let mut supports = [[0; MAX_GRID_SIZE]; MAX_GRID_SIZE];
+        for (v0, v0_supports) in supports.iter_mut().enumerate().take(n_cols) {
+            for (v1, support) in v0_supports.iter_mut().enumerate().take(n_cols) {
+                for v2 in 0..n_cols {
+                    if constraint.valid(&[v0, v1, v2]) {
+                        *support |= 1 << v2;
+                    }
                 }
             }
         }
+        Some(CompiledConstraint::Ternary { vars, supports })
     }
 
-    /// Builds the initial worklist with all binary (both directions) and ternary constraints.
-    fn initialize_worklist(&self) -> Vec<WorkItem> {
-        let mut worklist = Vec::new();
-        // worklist is in reverse order
-        for constraint in &self.ternary_constraints {
-            worklist.push(WorkItem::Ternary(Rc::clone(constraint)));
+    fn involves(&self, tile: Tile) -> bool {
+        match self {
+            CompiledConstraint::Unary { tile: t, .. } => *t == tile,
+            CompiledConstraint::Binary { x, y, .. } => *x == tile || *y == tile,
+            CompiledConstraint::Ternary { vars, .. } => vars.contains(&tile),
         }
-        for constraint in &self.binary_constraints {
-            let (x, y) = constraint.vars();
-            worklist.push(WorkItem::Binary {
-                constraint: Rc::clone(constraint),
-                x,
-                y,
-            });
-            worklist.push(WorkItem::Binary {
-                constraint: Rc::clone(constraint),
-                x: y,
-                y: x,
-            });
+    }
+}
+
+/// The solver state.
+/// - `domains` holds the possible columns of every tile, indexed by (row, variant).
+/// - `constraints` are the clue's constraints plus the rule that tiles in the same row can't
+///   share a column.
+pub struct ConstraintSolver {
+    domains: [[Domain; MAX_GRID_SIZE]; MAX_GRID_SIZE],
+    tiles: Vec<Tile>,
+    constraints: Vec<CompiledConstraint>,
+    worklist: Vec<usize>,
+    queued: Vec<bool>,
+}
+
+impl std::fmt::Debug for ConstraintSolver {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_map()
+            .entries(
+                self.tiles
+                    .iter()
+                    .map(|tile| (tile, domain_columns(self.domain(*tile)).collect::<Vec<_>>())),
+            )
+            .finish()
+    }
+}
+
+impl ConstraintSolver {
+    fn new(board: &GameBoard, clue: &Clue) -> Self {
+        let tiles: Vec<Tile> = clue
+            .assertions
+            .iter()
+            .map(|assertion| assertion.tile)
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect();
+
+        let mut domains = [[0; MAX_GRID_SIZE]; MAX_GRID_SIZE];
+        for tile in tiles.iter() {
+            domains[tile.row][Tile::variant_to_usize(tile.variant)] = board
+                .get_possible_cols_for_tile(*tile)
+                .fold(0, |acc, col| acc | (1 << col));
         }
-        for constraint in &self.unary_constraints {
-            worklist.push(WorkItem::Unary {
-                constraint: Rc::clone(constraint),
-                tile: constraint.var(),
-            });
+
+        let n_cols = board.solution.n_variants;
+        let constraint_set = get_clue_constraints(clue, board, &tiles);
+        let mut constraints = Vec::new();
+        constraints.extend(
+            constraint_set
+                .unary_constraints
+                .iter()
+                .map(|c| CompiledConstraint::unary(c.as_ref(), n_cols)),
+        );
+        constraints.extend(
+            constraint_set
+                .binary_constraints
+                .iter()
+                .map(|c| CompiledConstraint::binary(c.as_ref(), n_cols)),
+        );
+        constraints.extend(
+            constraint_set
+                .ternary_constraints
+                .iter()
+                .filter_map(|c| CompiledConstraint::ternary(c.as_ref(), n_cols)),
+        );
+
+        // worklist is in reverse order
+        let worklist = (0..constraints.len()).rev().collect();
+        let queued = vec![true; constraints.len()];
+        Self {
+            domains,
+            tiles,
+            constraints,
+            worklist,
+            queued,
         }
-        worklist
     }
 
-    /// Processes a single binary work item.
-    /// For each value in x’s domain, checks for support in y’s domain.
-    /// If a value is unsupported, it is removed and a reduction is returned.
-    fn process_binary_item<'a>(
-        &mut self,
-        constraint: &dyn BinaryConstraint,
-        eval_x: Tile,
-        eval_y: Tile,
-    ) {
-        let (x, _y) = constraint.vars();
-        let domain_x = self.domains.get(&eval_x).cloned().unwrap_or_default();
-        for &vx in &domain_x {
-            let domain_y = self.domains.get(&eval_y).unwrap();
-            let has_support = domain_y.iter().any(|&vy| {
-                if eval_x == x {
-                    constraint.valid(vx, vy)
-                } else {
-                    constraint.valid(vy, vx)
-                }
-            });
-            if !has_support {
-                trace!(
-                    target: "constraint_solver",
-                    "Removing value {} from domain of tile {:?}",
-                    vx,
-                    eval_x
-                );
-                self.domains.get_mut(&eval_x).unwrap().remove(&vx);
-                self.enqueue_related_constraints(eval_x);
-            }
-        }
+    fn domain(&self, tile: Tile) -> Domain {
+        self.domains[tile.row][Tile::variant_to_usize(tile.variant)]
     }
 
-    /// Processes a single ternary work item.
-    /// For each variable involved in the ternary constraint, and for each of its values,
-    /// checks whether there exists a valid complete assignment.
-    /// If not, the value is removed and a reduction is returned.
-    fn process_ternary_item(&mut self, constraint: &dyn TernaryConstraint) {
-        let vars = constraint.vars();
-        if vars.len() != 3 {
-            return;
-        }
-        for (i, &tile) in vars.iter().enumerate() {
-            let domain_tile = self.domains.get(&tile).cloned().unwrap_or_default();
-            for &val in &domain_tile {
-                if !self.has_valid_assignment(constraint, i, val, &vars) {
-                    trace!(
-                        target: "constraint_solver",
-                        "Removing value {} from domain of tile {:?}",
-                        val,
-                        tile
-                    );
-                    self.domains.get_mut(&tile).unwrap().remove(&val);
-                    self.enqueue_related_constraints(tile);
-                }
+    fn set_domain(&mut self, tile: Tile, domain: Domain) {
+        trace!(
+            target: "constraint_solver",
+            "Reducing domain of tile {:?} from {:08b} to {:08b}",
+            tile,
+            self.domain(tile),
+            domain
+        );
+        self.domains[tile.row][Tile::variant_to_usize(tile.variant)] = domain;
+        self.enqueue_related_constraints(tile);
+    }
+
+    fn reduce_domains(&mut self) {
+        trace!(target: "constraint_solver", "AC3 iteration");
+        while let Some(index) = self.worklist.pop() {
+            self.queued[index] = false;
+            trace!(target: "constraint_solver", "Domains: {:?}", self);
+            trace!(
+                target: "constraint_solver",
+                "Processing constraint: {:?}",
+                self.constraints[index]
+            );
+            for (tile, domain) in self.revise(index) {
+                self.set_domain(tile, domain);
             }
         }
     }
 
-    /// Checks whether, for a ternary constraint, a fixed value for one variable
-    /// can be extended to a full valid assignment for all three variables.
-    /// `fixed_index` is the position of the variable in the constraint's variable list,
-    /// and `fixed_val` is the candidate value.
-    fn has_valid_assignment(
-        &self,
-        constraint: &dyn TernaryConstraint,
-        fixed_index: usize,
-        fixed_val: usize,
-        vars: &Vec<Tile>,
-    ) -> bool {
-        assert!(vars.len() == 3);
-        // For a ternary constraint, assume exactly 3 variables.
-        let mut assignment = vec![0; 3];
-        assignment[fixed_index] = fixed_val;
-        // Identify the other two variables.
-        let other_indices: Vec<usize> = (0..3).filter(|&i| i != fixed_index).collect();
-        let domain0 = self.domains.get(&vars[other_indices[0]]).unwrap();
-        let domain1 = self.domains.get(&vars[other_indices[1]]).unwrap();
-        // Iterate over the Cartesian product of the other two domains.
-        for &val0 in domain0 {
-            for &val1 in domain1 {
-                if fixed_index == 0 {
-                    assignment[1] = val0;
-                    assignment[2] = val1;
-                } else if fixed_index == 1 {
-                    assignment[0] = val0;
-                    assignment[2] = val1;
-                } else {
-                    assignment[0] = val0;
-                    assignment[1] = val1;
+    /// Removes every value that has no support under the given constraint, returning the
+    /// domains that changed.
+    fn revise(&self, index: usize) -> Vec<(Tile, Domain)> {
+        let mut changes = Vec::new();
+        match &self.constraints[index] {
+            CompiledConstraint::Unary { tile, allowed } => {
+                let domain = self.domain(*tile);
+                if domain & allowed != domain {
+                    changes.push((*tile, domain & allowed));
+                }
+            }
+            CompiledConstraint::Binary {
+                x,
+                y,
+                x_supports,
+                y_supports,
+            } => {
+                let (domain_x, domain_y) = (self.domain(*x), self.domain(*y));
+                let supported_x = domain_columns(domain_x)
+                    .filter(|&vx| x_supports[vx] & domain_y != 0)
+                    .fold(0, |acc, vx| acc | (1 << vx));
+                let supported_y = domain_columns(domain_y)
+                    .filter(|&vy| y_supports[vy] & domain_x != 0)
+                    .fold(0, |acc, vy| acc | (1 << vy));
+                if supported_x != domain_x {
+                    changes.push((*x, supported_x));
                 }
-                if constraint.valid(&assignment) {
-                    return true;
+                if supported_y != domain_y {
+                    changes.push((*y, supported_y));
+                }
+            }
+            CompiledConstraint::Ternary { vars, supports } => {
+                let domains = vars.map(|tile| self.domain(tile));
+                let mut supported = [0; 3];
+                for v0 in domain_columns(domains[0]) {
+                    for v1 in domain_columns(domains[1]) {
+                        let v2s = supports[v0][v1] & domains[2];
+                        if v2s != 0 {
+                            supported[0] |= 1 << v0;
+                            supported[1] |= 1 << v1;
+                            supported[2] |= v2s;
+                        }
+                    }
+                }
+                for i in 0..3 {
+                    if supported[i] != domains[i] {
+                        changes.push((vars[i], supported[i]));
+                    }
                 }
             }
         }
-        false
+        changes
     }
 
-    /// Enqueues all binary and ternary constraints that involve the given tile.
+    /// Enqueues all constraints that involve the given tile.
     fn enqueue_related_constraints(&mut self, tile: Tile) {
-        for constraint in &self.binary_constraints {
-            let (a, b) = constraint.vars();
-            if a == tile || b == tile {
-                self.worklist.push(WorkItem::Binary {
-                    constraint: Rc::clone(constraint),
-                    x: a,
-                    y: b,
-                });
-                self.worklist.push(WorkItem::Binary {
-                    constraint: Rc::clone(constraint),
-                    x: b,
-                    y: a,
-                });
-            }
-        }
-        for constraint in &self.ternary_constraints {
-            if constraint.vars().contains(&tile) {
-                self.worklist.push(WorkItem::Ternary(Rc::clone(constraint)));
+        for (index, constraint) in self.constraints.iter().enumerate() {
+            if !self.queued[index] && constraint.involves(tile) {
+                self.queued[index] = true;
+                self.worklist.push(index);
             }
         }
     }
 
-    fn process_unary_item(&mut self, as_ref: &dyn UnaryConstraint, tile: Tile) {
-        let domain = self.domains.get(&tile).cloned().unwrap_or_default();
-        for &val in &domain {
-            if !as_ref.valid(val) {
-                trace!(
-                    target: "constraint_solver",
-                    "Removing value {} from domain of tile {:?}",
-                    val,
-                    tile
-                );
-                self.domains.get_mut(&tile).unwrap().remove(&val);
-                self.enqueue_related_constraints(tile);
+    pub fn deduce_clue(board: &GameBoard, clue: &Clue) -> Vec<Deduction> {
+        let mut solver = ConstraintSolver::new(board, clue);
+        let initial_domains = solver.domains;
+        trace!(target: "constraint_solver", "Domains before: {:?}", solver);
+        solver.reduce_domains();
+        trace!(target: "constraint_solver", "Domains after: {:?}", solver);
+
+        // columns claimed by a tile that was narrowed down to a single column, per row
+        let mut solved_columns = [0 as Domain; MAX_GRID_SIZE];
+        for tile in solver.tiles.iter() {
+            let domain = solver.domain(*tile);
+            if domain.count_ones() == 1 {
+                solved_columns[tile.row] |= domain;
             }
         }
-    }
 
-    pub fn deduce_clue(board: &GameBoard, clue: &Clue) -> Vec<Deduction> {
-        let (domains, constraint_set) = get_domains_and_constraints(clue, board);
-        let unary_constraints: Vec<Rc<dyn UnaryConstraint>> = constraint_set
-            .unary_constraints
-            .into_iter()
-            .map(|c| c.into())
-            .collect::<Vec<_>>();
-
-        let binary_constraints: Vec<Rc<dyn BinaryConstraint>> = constraint_set
-            .binary_constraints
-            .into_iter()
-            .map(|c| c.into())
-            .collect::<Vec<_>>();
-        let ternary_constraints: Vec<Rc<dyn TernaryConstraint>> = constraint_set
-            .ternary_constraints
-            .into_iter()
-            .map(|c| c.into())
-            .collect::<Vec<_>>();
-
-        let mut solver = ConstraintSolver::new(
-            domains.clone(),
-            unary_constraints,
-            binary_constraints,
-            ternary_constraints,
-        );
-        trace!(target: "constraint_solver", "Domains before: {:?}", solver.domains);
-        solver.reduce_domains();
-        trace!(target: "constraint_solver", "Domains after: {:?}", solver.domains);
         let mut deductions = Vec::new();
-        let solved_coordinates: BTreeMap<Coordinates, Tile> = solver
-            .domains
-            .iter()
-            .filter(|(_, domain)| domain.len() == 1)
-            .map(|(tile, domain)| {
-                (
-                    Coordinates::new(tile.row, *domain.iter().next().unwrap()),
-                    tile.clone(),
-                )
-            })
-            .collect();
-
-        for (tile, domain) in domains.iter() {
-            let domain_after = solver.domains.get(tile).unwrap();
-            if domain.len() == domain_after.len() {
+        for tile in solver.tiles.iter() {
+            let domain = initial_domains[tile.row][Tile::variant_to_usize(tile.variant)];
+            let domain_after = solver.domain(*tile);
+            if domain == domain_after {
                 continue;
             }
-            if domain_after.len() == 0 {
+            if domain_after == 0 {
                 warn!("Domain for tile {:?} is empty", tile);
-            } else if domain_after.len() == 1 {
-                let val = domain_after.iter().next().unwrap();
+            } else if domain_after.count_ones() == 1 {
                 deductions.push(Deduction::new_with_kind(
-                    *val,
+                    domain_after.trailing_zeros() as usize,
                     TileAssertion {
-                        tile: tile.clone(),
+                        tile: *tile,
                         assertion: true,
                     },
                     DeductionKind::LastRemaining,
                 ));
             } else {
-                let removed_values: Vec<usize> = domain.difference(domain_after).cloned().collect();
-                for val in removed_values {
-                    let coordinates = Coordinates::new(tile.row, val);
-                    if !solved_coordinates.contains_key(&coordinates) {
-                        deductions.push(Deduction::new_with_kind(
-                            val,
-                            TileAssertion {
-                                tile: tile.clone(),
-                                assertion: false,
-                            },
-                            DeductionKind::Constraint,
-                        ));
-                    }
+                let removed_values = domain & !domain_after & !solved_columns[tile.row];
+                for val in domain_columns(removed_values) {
+                    deductions.push(Deduction::new_with_kind(
+                        val,
+                        TileAssertion {
+                            tile: *tile,
+                            assertion: false,
+                        },
+                        DeductionKind::Constraint,
+                    ));
                 }
             }
         }
@@ -383,6 +376,25 @@ mod tests {
         assert!(deductions.contains(&Deduction::parse("1b not col 1 (Constraint)")));
     }
 
+    #[test]
+    fn test_between() {
+        let input = "
+0|abcd|abcd|abcd|<C> |
+-----------------
+1|abcd|abcd|abcd|abcd|
+-----------------";
+
+        let board = GameBoard::parse(input, create_test_solution(2, 4));
+        let clue = Clue::between(Tile::new(0, 'a'), Tile::new(1, 'b'), Tile::new(0, 'c'));
+
+        let deductions = ConstraintSolver::deduce_clue(&board, &clue);
+        println!("Deductions: {:?}", deductions);
+        assert_eq!(deductions.len(), 3);
+        assert!(deductions.contains(&Deduction::parse("0a not col 2 (Constraint)")));
+        assert!(deductions.contains(&Deduction::parse("1b not col 0 (Constraint)")));
+        assert!(deductions.contains(&Deduction::parse("1b not col 3 (Constraint)")));
+    }
+
     #[test_context(UsingLogger)]
     #[test]
     fn test_left_of(_: &mut UsingLogger) {
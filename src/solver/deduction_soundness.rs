@@ -0,0 +1,269 @@
+//! Randomized soundness checks for the deduction engines: on any board that is consistent with
+//! the solution, no engine may deduce something the solution contradicts.
+//!
+//! Each case builds a random solution, a random true clue and a random partial board that keeps
+//! the solution possible. Proptest shrinks failures and reports the minimal case along with the
+//! board. Run more cases with `PROPTEST_CASES=10000 cargo test --release deduction_soundness`.
+
+use std::sync::Arc;
+
+use proptest::prelude::*;
+
+use crate::model::{
+    Clue, ClueType, Deduction, Difficulty, GameBoard, HorizontalClueType, Solution, Tile,
+    TileAssertion, VerticalClueType,
+};
+use crate::solver::candidate_solver::{self, deduce_hidden_sets, deduce_naked_sets};
+use crate::solver::ConstraintSolver;
+
+/// Raw choices a test case is built from. Every field is reduced modulo whatever it indexes, so
+/// any value is valid and shrinking moves towards the first row, column and variant.
+type Picks = [usize; 6];
+
+fn all_clue_types() -> Vec<ClueType> {
+    vec![
+        ClueType::Horizontal(HorizontalClueType::ThreeAdjacent),
+        ClueType::Horizontal(HorizontalClueType::TwoApartNotMiddle),
+        ClueType::Horizontal(HorizontalClueType::LeftOf),
+        ClueType::Horizontal(HorizontalClueType::TwoAdjacent),
+        ClueType::Horizontal(HorizontalClueType::NotAdjacent),
+        ClueType::Horizontal(HorizontalClueType::Between),
+        // the distance is drawn from the picks
+        ClueType::Horizontal(HorizontalClueType::NApart(2)),
+        ClueType::Horizontal(HorizontalClueType::OnEdge),
+        ClueType::Horizontal(HorizontalClueType::NotOnEdge),
+        ClueType::Horizontal(HorizontalClueType::LeftHalf),
+        ClueType::Vertical(VerticalClueType::ThreeInColumn),
+        ClueType::Vertical(VerticalClueType::TwoInColumn),
+        ClueType::Vertical(VerticalClueType::OneMatchesEither),
+        ClueType::Vertical(VerticalClueType::NotInSameColumn),
+        ClueType::Vertical(VerticalClueType::TwoInColumnWithout),
+    ]
+}
+
+/// Removes `count` distinct values from `options`, chosen by the picks
+fn pick_distinct(mut options: Vec<usize>, picks: &[usize], count: usize) -> Vec<usize> {
+    (0..count)
+        .map(|i| options.remove(picks[i] % options.len()))
+        .collect()
+}
+
+fn pick_tile_where(
+    solution: &Solution,
+    pick: usize,
+    allowed: impl Fn(usize, Tile) -> bool,
+) -> Tile {
+    let options = (0..solution.n_rows)
+        .flat_map(|row| (0..solution.n_variants).map(move |col| (row, col)))
+        .map(|(row, col)| (col, solution.get(row, col)))
+        .filter(|(col, tile)| allowed(*col, *tile))
+        .map(|(_, tile)| tile)
+        .collect::<Vec<_>>();
+    options[pick % options.len()]
+}
+
+/// Builds a clue of `clue_type` that holds for `solution`
+fn true_clue(solution: &Solution, clue_type: ClueType, p: &Picks, reverse: bool) -> Clue {
+    let n_rows = solution.n_rows;
+    let n_cols = solution.n_variants;
+    let at = |row: usize, col: usize| solution.get(row % n_rows, col);
+    let ordered = |mut tiles: Vec<Tile>| {
+        if reverse {
+            tiles.reverse();
+        }
+        tiles
+    };
+
+    match clue_type {
+        ClueType::Horizontal(tpe) => match tpe {
+            HorizontalClueType::ThreeAdjacent => {
+                let col = p[0] % (n_cols - 2);
+                let t = ordered(vec![at(p[1], col), at(p[2], col + 1), at(p[3], col + 2)]);
+                Clue::three_adjacent(t[0], t[1], t[2])
+            }
+            HorizontalClueType::TwoApartNotMiddle => {
+                let col = p[0] % (n_cols - 2);
+                let t = ordered(vec![at(p[1], col), at(p[2], col + 2)]);
+                let not_middle =
+                    pick_tile_where(solution, p[3], |c, tile| c != col + 1 && !t.contains(&tile));
+                Clue::two_apart_not_middle(t[0], not_middle, t[1])
+            }
+            HorizontalClueType::LeftOf => {
+                let mut cols = pick_distinct((0..n_cols).collect(), p, 2);
+                cols.sort();
+                Clue::left_of(at(p[2], cols[0]), at(p[3], cols[1]))
+            }
+            HorizontalClueType::TwoAdjacent => {
+                let col = p[0] % (n_cols - 1);
+                let t = ordered(vec![at(p[1], col), at(p[2], col + 1)]);
+                Clue::adjacent(t[0], t[1])
+            }
+            HorizontalClueType::NotAdjacent => {
+                let col = p[0] % n_cols;
+                let tile = at(p[1], col);
+                let not_next_to = pick_tile_where(solution, p[2], |c, t| {
+                    c + 1 != col && c != col + 1 && t != tile
+                });
+                Clue::not_adjacent(tile, not_next_to)
+            }
+            HorizontalClueType::Between => {
+                let mut cols = pick_distinct((0..n_cols).collect(), p, 3);
+                cols.sort();
+                let t = ordered(vec![
+                    at(p[3], cols[0]),
+                    at(p[4], cols[1]),
+                    at(p[5], cols[2]),
+                ]);
+                Clue::between(t[0], t[1], t[2])
+            }
+            HorizontalClueType::NApart(_) => {
+                let distance = 2 + p[5] % (n_cols - 2);
+                let col = p[0] % (n_cols - distance);
+                let t = ordered(vec![at(p[1], col), at(p[2], col + distance)]);
+                Clue::n_apart(t[0], t[1], distance)
+            }
+            HorizontalClueType::OnEdge => Clue::on_edge(at(p[1], (n_cols - 1) * (p[0] % 2))),
+            HorizontalClueType::NotOnEdge => Clue::not_on_edge(at(p[1], 1 + p[0] % (n_cols - 2))),
+            HorizontalClueType::LeftHalf => Clue::left_half(at(p[1], p[0] % (n_cols / 2))),
+        },
+        ClueType::Vertical(tpe) => {
+            let col = p[0] % n_cols;
+            let rows = pick_distinct((0..n_rows).collect(), &p[1..], 3);
+            let other_col = (col + 1 + p[4] % (n_cols - 1)) % n_cols;
+            match tpe {
+                VerticalClueType::ThreeInColumn => {
+                    Clue::three_in_column(at(rows[0], col), at(rows[1], col), at(rows[2], col))
+                }
+                VerticalClueType::TwoInColumn => {
+                    Clue::two_in_column(at(rows[0], col), at(rows[1], col))
+                }
+                VerticalClueType::OneMatchesEither => {
+                    let options = ordered(vec![at(rows[1], col), at(rows[2], other_col)]);
+                    Clue::one_matches_either(at(rows[0], col), options[0], options[1])
+                }
+                VerticalClueType::NotInSameColumn => {
+                    Clue::two_not_in_same_column(at(rows[0], col), at(p[5], other_col))
+                }
+                VerticalClueType::TwoInColumnWithout => Clue::two_in_column_without(
+                    at(rows[0], col),
+                    at(rows[2], other_col),
+                    at(rows[1], col),
+                ),
+            }
+        }
+    }
+}
+
+/// A board with some tiles placed and some wrong candidates removed; the solution stays possible
+fn consistent_board(
+    solution: &Arc<Solution>,
+    reveals: &[(usize, usize)],
+    eliminations: &[(usize, usize, usize)],
+) -> GameBoard {
+    let mut board = GameBoard::new(Arc::clone(solution));
+    for (row, col) in reveals {
+        board.select_tile_from_solution(
+            solution.get(row % solution.n_rows, col % solution.n_variants),
+        );
+    }
+    for (row, col, variant) in eliminations {
+        let (row, column) = (row % solution.n_rows, col % solution.n_variants);
+        let tile = Tile::new(row, Tile::usize_to_variant(variant % solution.n_variants));
+        if tile != solution.get(row, column) && board.get_selection(row, column).is_none() {
+            board.apply_deduction(&Deduction {
+                column,
+                tile_assertion: TileAssertion {
+                    tile,
+                    assertion: false,
+                },
+                deduction_kind: None,
+            });
+        }
+    }
+    board
+}
+
+fn contradicts(solution: &Solution, deduction: &Deduction) -> bool {
+    let tile = deduction.tile_assertion.tile;
+    let in_column = solution.get(tile.row, deduction.column) == tile;
+    in_column != deduction.tile_assertion.assertion
+}
+
+fn check_deductions(
+    engine: &str,
+    solution: &Solution,
+    board: &GameBoard,
+    clue: Option<&Clue>,
+    deductions: Vec<Deduction>,
+) -> Result<(), TestCaseError> {
+    for deduction in deductions {
+        prop_assert!(
+            !contradicts(solution, &deduction),
+            "{} deduced {:?}, contradicting the solution\nclue: {:?}\nboard:\n{:?}",
+            engine,
+            deduction,
+            clue,
+            board
+        );
+    }
+    Ok(())
+}
+
+fn scenario() -> impl Strategy<
+    Value = (
+        Difficulty,
+        u64,
+        ClueType,
+        Picks,
+        bool,
+        Vec<(usize, usize)>,
+        Vec<(usize, usize, usize)>,
+    ),
+> {
+    (
+        (0..Difficulty::all().len()).prop_map(Difficulty::from_index),
+        any::<u64>(),
+        prop::sample::select(all_clue_types()),
+        any::<Picks>(),
+        any::<bool>(),
+        prop::collection::vec(any::<(usize, usize)>(), 0..4),
+        prop::collection::vec(any::<(usize, usize, usize)>(), 0..32),
+    )
+}
+
+proptest! {
+    #[test]
+    fn test_clue_deductions_agree_with_solution(
+        (difficulty, seed, clue_type, picks, reverse, reveals, eliminations) in scenario()
+    ) {
+        let solution = Arc::new(Solution::new(difficulty, Some(seed)));
+        let clue = true_clue(&solution, clue_type, &picks, reverse);
+        let board = consistent_board(&solution, &reveals, &eliminations);
+
+        check_deductions(
+            "ConstraintSolver::deduce_clue",
+            &solution,
+            &board,
+            Some(&clue),
+            ConstraintSolver::deduce_clue(&board, &clue),
+        )?;
+        check_deductions(
+            "candidate_solver::deduce_clue",
+            &solution,
+            &board,
+            Some(&clue),
+            candidate_solver::deduce_clue(&board, &clue),
+        )?;
+    }
+
+    #[test]
+    fn test_set_deductions_agree_with_solution(
+        (difficulty, seed, _clue_type, _picks, _reverse, reveals, eliminations) in scenario()
+    ) {
+        let solution = Arc::new(Solution::new(difficulty, Some(seed)));
+        let board = consistent_board(&solution, &reveals, &eliminations);
+
+        check_deductions("deduce_hidden_sets", &solution, &board, None, deduce_hidden_sets(&board))?;
+        check_deductions("deduce_naked_sets", &solution, &board, None, deduce_naked_sets(&board))?;
+    }
+}
@@ -0,0 +1,177 @@
+use log::trace;
+
+use crate::model::{Clue, Deduction, DeductionKind, DifficultyRating, GameBoard};
+
+use super::{
+    candidate_solver::{deduce_clue, deduce_hidden_sets, deduce_naked_sets},
+    ConstraintSolver,
+};
+
+/// Safety valve in case the solver keeps producing deductions without completing the board
+const MAX_STEPS: u32 = 1000;
+
+/// Multiplier applied to the technique weight of each step, so that the technique needed
+/// dominates the time spent scanning clues
+const STEP_WEIGHT: u32 = 10;
+
+fn technique_weight(kind: &DeductionKind) -> u32 {
+    match kind {
+        DeductionKind::Constraint => 1,
+        DeductionKind::LastRemaining => 1,
+        DeductionKind::Converging => 4,
+        DeductionKind::NakedSet => 5,
+        DeductionKind::HiddenSet => 6,
+    }
+}
+
+struct SolveStep {
+    deductions: Vec<Deduction>,
+    /// Kind to attribute to deductions that don't carry one
+    default_kind: DeductionKind,
+    clues_scanned: u32,
+}
+
+/// Finds the next step the way the hint system does: the simple solver over every clue first,
+/// then the advanced solver, then naked sets, then hidden sets.
+fn next_step(board: &GameBoard, clues: &[Clue]) -> Option<SolveStep> {
+    let mut clues_scanned = 0;
+    for clue in clues {
+        clues_scanned += 1;
+        let deductions = ConstraintSolver::deduce_clue(board, clue);
+        if !deductions.is_empty() {
+            return Some(SolveStep {
+                deductions,
+                default_kind: DeductionKind::Constraint,
+                clues_scanned,
+            });
+        }
+    }
+
+    for clue in clues {
+        clues_scanned += 1;
+        let deductions = deduce_clue(board, clue);
+        if !deductions.is_empty() {
+            return Some(SolveStep {
+                deductions,
+                default_kind: DeductionKind::Converging,
+                clues_scanned,
+            });
+        }
+    }
+
+    let deductions = deduce_naked_sets(board);
+    if !deductions.is_empty() {
+        return Some(SolveStep {
+            deductions,
+            default_kind: DeductionKind::NakedSet,
+            clues_scanned,
+        });
+    }
+
+    let deductions = deduce_hidden_sets(board);
+    if !deductions.is_empty() {
+        return Some(SolveStep {
+            deductions,
+            default_kind: DeductionKind::HiddenSet,
+            clues_scanned,
+        });
+    }
+    None
+}
+
+/// Rates a puzzle by replaying the hint solver over it, starting from the given board (which
+/// should hold only the revealed tiles). Each step scores the weight of the hardest technique
+/// it needed plus the number of clues scanned to find it.
+pub fn rate_puzzle(board: &GameBoard) -> DifficultyRating {
+    let clues: Vec<Clue> = board.clue_set.all_clues().map(|c| c.clue.clone()).collect();
+    let mut board = board.clone();
+    let mut rating = DifficultyRating::default();
+
+    while !board.is_complete() && rating.steps < MAX_STEPS {
+        let Some(step) = next_step(&board, &clues) else {
+            break;
+        };
+
+        let mut hardest_technique = 0;
+        for deduction in step.deductions.iter() {
+            let kind = deduction
+                .deduction_kind
+                .as_ref()
+                .unwrap_or(&step.default_kind);
+            hardest_technique = hardest_technique.max(technique_weight(kind));
+            match kind {
+                DeductionKind::Constraint => rating.constraint_deductions += 1,
+                DeductionKind::Converging => rating.converging_deductions += 1,
+                DeductionKind::LastRemaining => rating.last_remaining_deductions += 1,
+                DeductionKind::NakedSet => rating.naked_set_deductions += 1,
+                DeductionKind::HiddenSet => rating.hidden_set_deductions += 1,
+            }
+        }
+        rating.steps += 1;
+        rating.clues_scanned += step.clues_scanned;
+        rating.score += hardest_technique * STEP_WEIGHT + step.clues_scanned;
+
+        board.apply_deductions(&step.deductions);
+        board.auto_solve_all();
+    }
+
+    rating.solved = board.is_complete();
+    trace!(
+        target: "difficulty_rater",
+        "Rated puzzle with seed {}: {:?}",
+        board.solution.seed,
+        rating
+    );
+    rating
+}
+
+#[cfg(test)]
+mod tests {
+    use test_context::test_context;
+
+    use crate::{
+        model::{Difficulty, GameBoard, Solution},
+        solver::generate_clues,
+        tests::UsingLogger,
+    };
+
+    use super::*;
+
+    #[test_context(UsingLogger)]
+    #[test]
+    fn test_rating_is_stable(_: &mut UsingLogger) {
+        let solution = Solution::new(Difficulty::Moderate, Some(42));
+        let result = generate_clues(&GameBoard::new(solution.into()));
+
+        let rating = rate_puzzle(&result.board);
+        assert!(rating.solved);
+        assert!(rating.steps > 0);
+        assert!(rating.score >= rating.steps * STEP_WEIGHT);
+        assert_eq!(rating, rate_puzzle(&result.board));
+    }
+
+    #[test_context(UsingLogger)]
+    #[test]
+    fn test_solved_board_rates_zero(_: &mut UsingLogger) {
+        let solution = Solution::new(Difficulty::Easy, Some(1));
+        let mut board = GameBoard::new(solution.into());
+        for tile in board.solution.all_tiles() {
+            board.select_tile_from_solution(tile);
+        }
+
+        let rating = rate_puzzle(&board);
+        assert!(rating.solved);
+        assert_eq!(rating.steps, 0);
+        assert_eq!(rating.score, 0);
+    }
+
+    #[test_context(UsingLogger)]
+    #[test]
+    fn test_unsolvable_board_is_flagged(_: &mut UsingLogger) {
+        // no clues and nothing revealed
+        let solution = Solution::new(Difficulty::Easy, Some(1));
+        let rating = rate_puzzle(&GameBoard::new(solution.into()));
+        assert!(!rating.solved);
+        assert_eq!(rating.steps, 0);
+    }
+}
@@ -0,0 +1,143 @@
+//! Regression corpus for the clue generator.
+//!
+//! `golden_corpus.txt` pins the puzzle generated for a set of (generator version, difficulty,
+//! seed) triples by a fingerprint of its `.mindhunt` file, which covers the solution, revealed
+//! tiles and clues. The test regenerates each puzzle, compares fingerprints and checks that the
+//! puzzle can be solved step by step without guessing.
+//!
+//! When a generator change is meant to alter puzzles, bump `GENERATOR_VERSION`, keep the old
+//! behaviour for older versions, add entries for the new version and bless them:
+//!
+//! ```text
+//! MINDHUNT_BLESS=1 cargo test --release golden_corpus
+//! ```
+//!
+//! Veteran puzzles are only checked in release builds; they take too long unoptimised.
+
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use crate::model::puzzle_file::write_puzzle;
+use crate::model::{is_supported_generator, Clue, Difficulty, GameBoard, Solution};
+use crate::solver::candidate_solver::{perform_evaluation_step, EvaluationStepResult};
+use crate::solver::generate_clues;
+
+fn corpus_path() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("src/solver/golden_corpus.txt")
+}
+
+/// FNV-1a over the puzzle's solution, revealed tiles and clues as written to a `.mindhunt` file.
+/// Other file lines are left out so that format changes don't disturb the corpus; FNV-1a rather
+/// than `DefaultHasher` as it is guaranteed not to change between Rust releases.
+fn fingerprint(board: &GameBoard) -> String {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    let puzzle = write_puzzle(board);
+    let content = puzzle.lines().filter(|line| {
+        ["row ", "reveal ", "clue "]
+            .iter()
+            .any(|k| line.starts_with(k))
+    });
+    for line in content {
+        for byte in line.bytes().chain(std::iter::once(b'\n')) {
+            hash ^= byte as u64;
+            hash = hash.wrapping_mul(0x100000001b3);
+        }
+    }
+    format!("{:016x}", hash)
+}
+
+fn solves_without_guessing(board: &GameBoard) -> bool {
+    let mut board = board.clone();
+    let clues: Vec<Clue> = board.clue_set.all_clues().map(|c| c.clue.clone()).collect();
+    while !board.is_complete() {
+        if perform_evaluation_step(&mut board, &clues) == EvaluationStepResult::Nothing {
+            return false;
+        }
+        board.auto_solve_all();
+    }
+    !board.is_incorrect()
+}
+
+fn parse_difficulty(name: &str) -> Difficulty {
+    Difficulty::all()
+        .into_iter()
+        .find(|d| d.to_string() == name)
+        .unwrap_or_else(|| panic!("Unknown difficulty {} in golden corpus", name))
+}
+
+#[test]
+fn test_golden_corpus() {
+    let bless = std::env::var("MINDHUNT_BLESS").is_ok();
+    let contents = fs::read_to_string(corpus_path()).expect("golden corpus should be readable");
+
+    let mut blessed = Vec::new();
+    let mut mismatches = Vec::new();
+    let mut unsolvable = Vec::new();
+    for line in contents.lines() {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let [generator_version, difficulty_name, seed, expected] = fields.as_slice() else {
+            // comments and blank lines
+            blessed.push(line.to_string());
+            continue;
+        };
+        let difficulty = parse_difficulty(difficulty_name);
+        let seed = seed.parse::<u64>().expect("golden corpus seed");
+        let generator_version = generator_version
+            .parse::<u32>()
+            .ok()
+            .filter(|v| is_supported_generator(*v))
+            .unwrap_or_else(|| {
+                panic!(
+                    "Unsupported generator {} in golden corpus",
+                    generator_version
+                )
+            });
+        if cfg!(debug_assertions) && difficulty == Difficulty::Veteran && !bless {
+            blessed.push(line.to_string());
+            continue;
+        }
+
+        let solution = Arc::new(Solution::new_with_generator(
+            difficulty,
+            Some(seed),
+            generator_version,
+        ));
+        let board = generate_clues(&GameBoard::new(solution)).board;
+        let actual = fingerprint(&board);
+        if actual != *expected {
+            mismatches.push(format!(
+                "generator {} {} {}: expected {}, got {}",
+                generator_version, difficulty_name, seed, expected, actual
+            ));
+        }
+        if !solves_without_guessing(&board) {
+            unsolvable.push(format!(
+                "generator {} {} {}",
+                generator_version, difficulty_name, seed
+            ));
+        }
+        blessed.push(format!(
+            "{} {} {} {}",
+            generator_version, difficulty_name, seed, actual
+        ));
+    }
+
+    assert!(
+        unsolvable.is_empty(),
+        "Corpus puzzles not solvable without guessing: {:?}",
+        unsolvable
+    );
+    if bless {
+        blessed.push(String::new());
+        fs::write(corpus_path(), blessed.join("\n")).expect("golden corpus should be writable");
+        return;
+    }
+    assert!(
+        mismatches.is_empty(),
+        "Generator output changed for:\n{}\nPuzzles of released generator versions must not \
+         change. If this is intended, bump GENERATOR_VERSION, add entries for it and run \
+         MINDHUNT_BLESS=1 cargo test --release golden_corpus",
+        mismatches.join("\n")
+    );
+}
@@ -4,11 +4,17 @@ mod clue_constraint;
 pub mod clue_generator;
 pub mod clue_generator_state;
 pub mod constraint_solver;
+#[cfg(test)]
+mod deduction_soundness;
+pub mod difficulty_rater;
+#[cfg(test)]
+mod golden_corpus;
 pub mod hidden_pair_finder;
 mod puzzle_variants;
 pub use candidate_solver::deduce_clue;
 pub use clue_generator::generate_clues;
 mod solver_helpers;
+pub mod uniqueness_verifier;
 
 pub use constraint_solver::ConstraintSolver;
 pub use solver_helpers::simplify_deductions;
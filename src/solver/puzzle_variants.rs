@@ -1,16 +1,20 @@
 use crate::{
     model::{
-        Clue, ClueType, Deduction, Difficulty, GameBoard, HorizontalClueType, Tile,
+        Clue, ClueType, Deduction, Difficulty, GameBoard, GeneratorRng, HorizontalClueType, Tile,
         VerticalClueType,
     },
     solver::candidate_solver::deduce_clue,
 };
 use log::{info, trace};
-use rand::{seq::IndexedRandom, Rng, RngCore};
-use std::{fmt::Debug, ops::RangeInclusive};
+use rand::{seq::IndexedRandom, Rng};
+use std::{
+    fmt::{Debug, Display},
+    ops::RangeInclusive,
+};
 
 use super::{
-    candidate_solver::deduce_hidden_sets_in_row, clue_generator_state::ClueGeneratorState,
+    candidate_solver::{deduce_hidden_sets_in_row, deduce_naked_sets_in_row},
+    clue_generator_state::ClueGeneratorState,
 };
 
 const MAX_BOOST: usize = 100;
@@ -26,6 +30,19 @@ pub enum PuzzleVariantType {
     Standard,
     Narrowing,
     Striping,
+    Positional,
+}
+
+impl Display for PuzzleVariantType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            PuzzleVariantType::Standard => "Standard",
+            PuzzleVariantType::Narrowing => "Narrowing",
+            PuzzleVariantType::Striping => "Striping",
+            PuzzleVariantType::Positional => "Positional",
+        };
+        write!(f, "{}", name)
+    }
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -36,6 +53,8 @@ struct NarrowingPuzzleVariant {
 }
 #[derive(Debug, Clone, Copy)]
 struct StripingPuzzleVariant {}
+#[derive(Debug, Clone, Copy)]
+struct PositionalPuzzleVariant {}
 
 pub trait PuzzleVariantCloneBox {
     fn clone_box(&self) -> Box<dyn PuzzleVariant>;
@@ -103,6 +122,11 @@ fn compute_base_score(
         })
         .count();
 
+    let n_rows_with_naked_set_deductions = deduction_rows
+        .iter()
+        .filter(|row| !deduce_naked_sets_in_row(board, **row).is_empty())
+        .count();
+
     let n_tiles_revealed = deducations
         .iter()
         .filter(|deduction| deduction.is_positive())
@@ -110,11 +134,18 @@ fn compute_base_score(
 
     let base_score = (n_deductions + (n_tiles_revealed * 10)) * 100;
 
-    let boosts = vec![ScoreBoost {
-        amount: n_rows_with_hidden_pair_deductions,
-        expected_range: 0..=1,
-        weight: 20,
-    }];
+    let boosts = vec![
+        ScoreBoost {
+            amount: n_rows_with_naked_set_deductions,
+            expected_range: 0..=1,
+            weight: 10,
+        },
+        ScoreBoost {
+            amount: n_rows_with_hidden_pair_deductions,
+            expected_range: 0..=1,
+            weight: 20,
+        },
+    ];
 
     (base_score, boosts)
 }
@@ -151,7 +182,8 @@ fn generic_starter_evidence(state: &mut ClueGeneratorState, init_board: &GameBoa
     }
 }
 
-pub trait PuzzleVariant: Debug + PuzzleVariantCloneBox {
+/// Variants are shared with the worker threads that score look-ahead clues, hence `Send + Sync`
+pub trait PuzzleVariant: Debug + PuzzleVariantCloneBox + Send + Sync {
     fn get_clue_weights(&self) -> Vec<WeightedClueType>;
 
     /// score a clue based on the deductions; smaller score = better
@@ -189,6 +221,18 @@ impl PuzzleVariant for StandardPuzzleVariant {
                 weight: 1,
                 clue_type: ClueType::Horizontal(HorizontalClueType::LeftOf),
             },
+            WeightedClueType {
+                weight: 1,
+                clue_type: ClueType::Horizontal(HorizontalClueType::Between),
+            },
+            WeightedClueType {
+                weight: 2,
+                clue_type: ClueType::Horizontal(HorizontalClueType::NApart(2)),
+            },
+            WeightedClueType {
+                weight: 1,
+                clue_type: ClueType::Horizontal(HorizontalClueType::NApart(3)),
+            },
             WeightedClueType {
                 weight: 6,
                 clue_type: ClueType::Vertical(VerticalClueType::TwoInColumn),
@@ -241,6 +285,18 @@ impl PuzzleVariant for NarrowingPuzzleVariant {
                 weight: 6,
                 clue_type: ClueType::Horizontal(HorizontalClueType::LeftOf),
             },
+            WeightedClueType {
+                weight: 3,
+                clue_type: ClueType::Horizontal(HorizontalClueType::Between),
+            },
+            WeightedClueType {
+                weight: 1,
+                clue_type: ClueType::Horizontal(HorizontalClueType::NApart(2)),
+            },
+            WeightedClueType {
+                weight: 1,
+                clue_type: ClueType::Horizontal(HorizontalClueType::NApart(3)),
+            },
             WeightedClueType {
                 weight: 3,
                 clue_type: ClueType::Vertical(VerticalClueType::TwoInColumn),
@@ -310,6 +366,18 @@ impl PuzzleVariant for StripingPuzzleVariant {
                 weight: 1,
                 clue_type: ClueType::Horizontal(HorizontalClueType::LeftOf),
             },
+            WeightedClueType {
+                weight: 1,
+                clue_type: ClueType::Horizontal(HorizontalClueType::Between),
+            },
+            WeightedClueType {
+                weight: 2,
+                clue_type: ClueType::Horizontal(HorizontalClueType::NApart(2)),
+            },
+            WeightedClueType {
+                weight: 1,
+                clue_type: ClueType::Horizontal(HorizontalClueType::NApart(3)),
+            },
             WeightedClueType {
                 weight: 1,
                 clue_type: ClueType::Vertical(VerticalClueType::TwoInColumn),
@@ -424,14 +492,72 @@ impl PuzzleVariant for StripingPuzzleVariant {
     }
 }
 
+/// anchor tiles to edges and halves of the board; gentler, so favored for smaller puzzles
+impl PuzzleVariant for PositionalPuzzleVariant {
+    fn get_clue_weights(&self) -> Vec<WeightedClueType> {
+        vec![
+            WeightedClueType {
+                weight: 3,
+                clue_type: ClueType::Horizontal(HorizontalClueType::OnEdge),
+            },
+            WeightedClueType {
+                weight: 2,
+                clue_type: ClueType::Horizontal(HorizontalClueType::NotOnEdge),
+            },
+            WeightedClueType {
+                weight: 3,
+                clue_type: ClueType::Horizontal(HorizontalClueType::LeftHalf),
+            },
+            WeightedClueType {
+                weight: 3,
+                clue_type: ClueType::Horizontal(HorizontalClueType::TwoAdjacent),
+            },
+            WeightedClueType {
+                weight: 1,
+                clue_type: ClueType::Horizontal(HorizontalClueType::LeftOf),
+            },
+            WeightedClueType {
+                weight: 4,
+                clue_type: ClueType::Vertical(VerticalClueType::TwoInColumn),
+            },
+            WeightedClueType {
+                weight: 1,
+                clue_type: ClueType::Vertical(VerticalClueType::NotInSameColumn),
+            },
+            WeightedClueType {
+                weight: 2,
+                clue_type: ClueType::Horizontal(HorizontalClueType::ThreeAdjacent),
+            },
+        ]
+    }
+
+    fn score_clue(&self, board: &GameBoard, clue: &Clue, deducations: &Vec<Deduction>) -> usize {
+        let (base_score, boosts) = compute_base_score(board, clue, deducations);
+        reduce_score(base_score, &boosts)
+    }
+
+    fn get_variant_type(&self) -> PuzzleVariantType {
+        PuzzleVariantType::Positional
+    }
+
+    fn populate_starter_evidence(&self, state: &mut ClueGeneratorState, init_board: &GameBoard) {
+        generic_starter_evidence(state, init_board);
+    }
+}
+
 pub fn random_puzzle_variant(
     difficulty: Difficulty,
-    rng: &mut Box<dyn RngCore>,
+    rng: &mut GeneratorRng,
 ) -> Box<dyn PuzzleVariant> {
+    let positional_weight = match difficulty {
+        Difficulty::Tutorial | Difficulty::Easy => 4,
+        _ => 1,
+    };
     let puzzle_variants: Vec<(Box<dyn PuzzleVariant>, i32)> = vec![
         (Box::new(StandardPuzzleVariant {}), 3),
         (Box::new(NarrowingPuzzleVariant { difficulty }), 1),
         (Box::new(StripingPuzzleVariant {}), 3),
+        (Box::new(PositionalPuzzleVariant {}), positional_weight),
     ];
     let lol = puzzle_variants
         .choose_weighted(rng, |(_, weight)| *weight)
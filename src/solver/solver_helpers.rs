@@ -84,13 +84,19 @@ pub fn get_domains_and_constraints(
             board.get_possible_cols_for_tile(assertion.tile).collect();
         domains.insert(assertion.tile, possible_cols);
     }
-    let mut constraint_set = ConstraintSet::default();
-    // let mut binary_constraints: Vec<Rc<dyn BinaryConstraint>> = Vec::new();
-    // let mut ternary_constraints: Vec<Rc<dyn TernaryConstraint>> = Vec::new();
 
     let mut tiles: Vec<Tile> = domains.keys().cloned().collect();
     tiles.sort_by_key(|tile| domains[tile].len());
 
+    let constraint_set = get_clue_constraints(clue, board, &tiles);
+    (domains, constraint_set)
+}
+
+/// Builds the clue's constraints over the given tiles, plus the board's rule that tiles in the
+/// same row cannot occupy the same column.
+pub fn get_clue_constraints(clue: &Clue, board: &GameBoard, tiles: &[Tile]) -> ConstraintSet {
+    let mut constraint_set = ConstraintSet::default();
+
     // add binary constraints for board (tiles cannot occupy the same column)
     for i in 0..tiles.len() {
         for j in i + 1..tiles.len() {
@@ -118,7 +124,7 @@ pub fn get_domains_and_constraints(
         .ternary_constraints
         .extend(clue_constraint_set.ternary_constraints);
 
-    (domains, constraint_set)
+    constraint_set
 }
 
 #[cfg(test)]
@@ -0,0 +1,429 @@
+use log::trace;
+
+use crate::model::{GameBoard, Solution, Tile, MAX_GRID_SIZE};
+
+use super::clue_constraint::{
+    create_clue_constraint, BinaryConstraint, TernaryConstraint, UnaryConstraint,
+};
+
+/// The verifier never needs to know more than "one" or "more than one".
+pub const SOLUTION_COUNT_CAP: usize = 2;
+
+/// A clue constraint, with its tiles resolved to indices into the search's tile list.
+#[derive(Debug)]
+enum Check {
+    Unary(Box<dyn UnaryConstraint>, usize),
+    Binary(Box<dyn BinaryConstraint>, usize, usize),
+    Ternary(Box<dyn TernaryConstraint>, Vec<usize>),
+}
+
+/// Column domains for every tile, plus the set of tiles whose column is settled.
+#[derive(Clone)]
+struct SearchState {
+    domains: Vec<u8>,
+    placed: u64,
+}
+
+impl SearchState {
+    fn is_placed(&self, idx: usize) -> bool {
+        self.placed & (1 << idx) != 0
+    }
+
+    fn column(&self, idx: usize) -> usize {
+        self.domains[idx].trailing_zeros() as usize
+    }
+}
+
+/// Exhaustive backtracking search over row permutations. Each step places the tile with the
+/// fewest remaining columns, then prunes the other tiles' columns using the clue constraints and
+/// the one-tile-per-column rule of each row before recursing.
+struct SolutionSearch {
+    n_variants: usize,
+    tiles: Vec<Tile>,
+    checks: Vec<Check>,
+    /// Indices into `checks` for every constraint that mentions the tile
+    tile_checks: Vec<Vec<usize>>,
+    limit: usize,
+    /// Grid that found solutions are written over, so cells outside the puzzle match the board
+    base_grid: [[char; MAX_GRID_SIZE]; MAX_GRID_SIZE],
+    found: Vec<[[char; MAX_GRID_SIZE]; MAX_GRID_SIZE]>,
+}
+
+impl SolutionSearch {
+    fn new(board: &GameBoard, limit: usize) -> (Self, Option<SearchState>) {
+        let n_variants = board.solution.n_variants;
+        let mut tiles = Vec::new();
+        let mut domains = Vec::new();
+        for row in 0..board.solution.n_rows {
+            for variant in board.solution.variants.iter() {
+                let tile = Tile::new(row, *variant);
+                let domain = board
+                    .get_possible_cols_for_tile(tile)
+                    .fold(0u8, |acc, col| acc | (1 << col));
+                tiles.push(tile);
+                domains.push(domain);
+            }
+        }
+
+        let index_of = |tile: Tile| tile.row * n_variants + Tile::variant_to_usize(tile.variant);
+        let mut checks = Vec::new();
+        let difficulty = board.solution.difficulty;
+        for clue in board.clue_set.all_clues() {
+            let constraint_set = create_clue_constraint(&clue.clue).constraints(difficulty);
+            for constraint in constraint_set.unary_constraints {
+                let idx = index_of(constraint.var());
+                checks.push(Check::Unary(constraint, idx));
+            }
+            for constraint in constraint_set.binary_constraints {
+                let (a, b) = constraint.vars();
+                checks.push(Check::Binary(constraint, index_of(a), index_of(b)));
+            }
+            for constraint in constraint_set.ternary_constraints {
+                let vars = constraint.vars().into_iter().map(index_of).collect();
+                checks.push(Check::Ternary(constraint, vars));
+            }
+        }
+
+        let mut tile_checks: Vec<Vec<usize>> = vec![Vec::new(); tiles.len()];
+        for (check_idx, check) in checks.iter().enumerate() {
+            match check {
+                Check::Unary(_, a) => tile_checks[*a].push(check_idx),
+                Check::Binary(_, a, b) => {
+                    tile_checks[*a].push(check_idx);
+                    tile_checks[*b].push(check_idx);
+                }
+                Check::Ternary(_, vars) => {
+                    for v in vars {
+                        tile_checks[*v].push(check_idx);
+                    }
+                }
+            }
+        }
+
+        let search = Self {
+            n_variants,
+            tiles,
+            checks,
+            tile_checks,
+            limit,
+            base_grid: board.solution.grid,
+            found: Vec::new(),
+        };
+
+        // unary constraints only need to be applied once
+        let mut state = SearchState { domains, placed: 0 };
+        for check in search.checks.iter() {
+            if let Check::Unary(constraint, a) = check {
+                state.domains[*a] =
+                    search.filter_domain(state.domains[*a], |col| constraint.valid(col));
+            }
+        }
+        if state.domains.contains(&0) {
+            return (search, None);
+        }
+        let all_tiles = (0..search.tiles.len()).collect();
+        let state = if search.propagate(&mut state, all_tiles) {
+            Some(state)
+        } else {
+            None
+        };
+        (search, state)
+    }
+
+    fn filter_domain(&self, domain: u8, mut valid: impl FnMut(usize) -> bool) -> u8 {
+        (0..self.n_variants)
+            .filter(|col| domain & (1 << col) != 0 && valid(*col))
+            .fold(0u8, |acc, col| acc | (1 << col))
+    }
+
+    /// Narrows a tile's domain, queueing the tile if anything changed. Returns false if the tile
+    /// has nowhere left to go.
+    fn narrow(
+        &self,
+        state: &mut SearchState,
+        idx: usize,
+        domain: u8,
+        queue: &mut Vec<usize>,
+    ) -> bool {
+        if domain != state.domains[idx] {
+            state.domains[idx] = domain;
+            queue.push(idx);
+        }
+        domain != 0
+    }
+
+    /// Keeps only the columns of each constrained tile that some assignment of the other tiles
+    /// still supports.
+    fn revise(&self, state: &mut SearchState, check: &Check, queue: &mut Vec<usize>) -> bool {
+        match check {
+            Check::Unary(..) => true,
+            Check::Binary(constraint, a, b) => {
+                let (a, b) = (*a, *b);
+                let domain_b = state.domains[b];
+                let domain_a = self.filter_domain(state.domains[a], |x| {
+                    (0..self.n_variants).any(|y| domain_b & (1 << y) != 0 && constraint.valid(x, y))
+                });
+                if !self.narrow(state, a, domain_a, queue) {
+                    return false;
+                }
+                let domain_b = self.filter_domain(domain_b, |y| {
+                    (0..self.n_variants).any(|x| domain_a & (1 << x) != 0 && constraint.valid(x, y))
+                });
+                self.narrow(state, b, domain_b, queue)
+            }
+            Check::Ternary(constraint, vars) => {
+                let mut supported = [0u8; 3];
+                let columns = |idx: usize| {
+                    let domain = state.domains[idx];
+                    (0..self.n_variants).filter(move |col| domain & (1 << col) != 0)
+                };
+                for x in columns(vars[0]) {
+                    for y in columns(vars[1]) {
+                        for z in columns(vars[2]) {
+                            if constraint.valid(&[x, y, z]) {
+                                supported[0] |= 1 << x;
+                                supported[1] |= 1 << y;
+                                supported[2] |= 1 << z;
+                            }
+                        }
+                    }
+                }
+                (0..3).all(|i| {
+                    let domain = state.domains[vars[i]] & supported[i];
+                    self.narrow(state, vars[i], domain, queue)
+                })
+            }
+        }
+    }
+
+    /// A column that only one tile in its row can still reach must hold that tile.
+    fn place_hidden_singles(&self, state: &mut SearchState, queue: &mut Vec<usize>) -> bool {
+        for row_start in (0..self.tiles.len()).step_by(self.n_variants) {
+            for col in 0..self.n_variants {
+                let mut candidates = (row_start..row_start + self.n_variants)
+                    .filter(|idx| state.domains[*idx] & (1 << col) != 0);
+                match (candidates.next(), candidates.next()) {
+                    (None, _) => return false,
+                    (Some(idx), None) if !self.narrow(state, idx, 1 << col, queue) => return false,
+                    _ => (),
+                }
+            }
+        }
+        true
+    }
+
+    /// Re-establishes arc consistency after the domains of the queued tiles changed. Tiles left
+    /// with a single column are placed, which removes that column from the rest of their row.
+    /// Returns false on a contradiction.
+    fn propagate(&self, state: &mut SearchState, mut queue: Vec<usize>) -> bool {
+        loop {
+            while let Some(idx) = queue.pop() {
+                if state.domains[idx] == 0 {
+                    return false;
+                }
+                if state.domains[idx].count_ones() == 1 && !state.is_placed(idx) {
+                    state.placed |= 1 << idx;
+                    let taken = state.domains[idx];
+                    let row_start = self.tiles[idx].row * self.n_variants;
+                    for other in row_start..row_start + self.n_variants {
+                        if other != idx {
+                            let domain = state.domains[other] & !taken;
+                            if !self.narrow(state, other, domain, &mut queue) {
+                                return false;
+                            }
+                        }
+                    }
+                }
+                for check_idx in self.tile_checks[idx].iter() {
+                    if !self.revise(state, &self.checks[*check_idx], &mut queue) {
+                        return false;
+                    }
+                }
+            }
+            if !self.place_hidden_singles(state, &mut queue) {
+                return false;
+            }
+            if queue.is_empty() {
+                return true;
+            }
+        }
+    }
+
+    fn search(&mut self, state: SearchState) {
+        if self.found.len() >= self.limit {
+            return;
+        }
+
+        let next = (0..self.tiles.len())
+            .filter(|idx| !state.is_placed(*idx))
+            .min_by_key(|idx| state.domains[*idx].count_ones());
+        let Some(idx) = next else {
+            self.record_solution(&state);
+            return;
+        };
+
+        for col in 0..self.n_variants {
+            if state.domains[idx] & (1 << col) == 0 {
+                continue;
+            }
+            let mut branch = state.clone();
+            branch.domains[idx] = 1 << col;
+            if self.propagate(&mut branch, vec![idx]) {
+                self.search(branch);
+            }
+            if self.found.len() >= self.limit {
+                return;
+            }
+        }
+    }
+
+    fn record_solution(&mut self, state: &SearchState) {
+        let mut grid = self.base_grid;
+        for (idx, tile) in self.tiles.iter().enumerate() {
+            grid[tile.row][state.column(idx)] = tile.variant;
+        }
+        trace!(target: "uniqueness_verifier", "Found solution: {:?}", grid);
+        self.found.push(grid);
+    }
+}
+
+/// Finds up to `limit` complete solutions consistent with the board's clue set, selected
+/// (revealed) tiles and remaining candidates.
+pub fn find_solutions(board: &GameBoard, limit: usize) -> Vec<Solution> {
+    let (mut search, state) = SolutionSearch::new(board, limit);
+    if let Some(state) = state {
+        search.search(state);
+    }
+    search
+        .found
+        .into_iter()
+        .map(|grid| Solution {
+            grid,
+            ..board.solution.as_ref().clone()
+        })
+        .collect()
+}
+
+/// Counts the solutions permitted by the board's clue set and revealed tiles, capped at
+/// [`SOLUTION_COUNT_CAP`]. A well-formed puzzle returns exactly 1.
+pub fn count_solutions(board: &GameBoard) -> usize {
+    find_solutions(board, SOLUTION_COUNT_CAP).len()
+}
+
+/// Returns up to `limit` solutions that satisfy every clue but differ from the board's
+/// intended solution.
+pub fn find_alternative_solutions(board: &GameBoard, limit: usize) -> Vec<Solution> {
+    let mut alternatives = find_solutions(board, limit + 1);
+    alternatives.retain(|solution| solution.grid != board.solution.grid);
+    alternatives.truncate(limit);
+    alternatives
+}
+
+/// Checks that the board's clues and revealed tiles lead to exactly one solution, and that
+/// the solution is the one stored with the board.
+pub fn is_uniquely_solvable(board: &GameBoard) -> bool {
+    let solutions = find_solutions(board, SOLUTION_COUNT_CAP);
+    solutions.len() == 1 && solutions[0].grid == board.solution.grid
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use test_context::test_context;
+
+    use crate::{
+        model::{Clue, ClueSet, Difficulty, GameBoard, Solution},
+        solver::generate_clues,
+        tests::UsingLogger,
+    };
+
+    use super::*;
+
+    fn board_with_clues(solution: Solution, clues: &[&str]) -> GameBoard {
+        let mut board = GameBoard::new(Arc::new(solution));
+        let clues = clues.iter().map(|c| Clue::parse(c)).collect();
+        board.set_clues(Arc::new(ClueSet::new(clues)));
+        board
+    }
+
+    fn easy_solution() -> Solution {
+        let mut solution = Solution::new(Difficulty::Easy, Some(0));
+        for row in 0..4 {
+            for col in 0..4 {
+                solution.grid[row][col] = Tile::usize_to_variant(col);
+            }
+        }
+        solution
+    }
+
+    #[test_context(UsingLogger)]
+    #[test]
+    fn test_no_clues_is_ambiguous(_: &mut UsingLogger) {
+        let board = board_with_clues(easy_solution(), &[]);
+        assert_eq!(count_solutions(&board), SOLUTION_COUNT_CAP);
+        assert!(!is_uniquely_solvable(&board));
+    }
+
+    #[test_context(UsingLogger)]
+    #[test]
+    fn test_fully_revealed_board_is_unique(_: &mut UsingLogger) {
+        let mut board = board_with_clues(easy_solution(), &[]);
+        for tile in board.solution.all_tiles() {
+            board.select_tile_from_solution(tile);
+        }
+        assert_eq!(count_solutions(&board), 1);
+        assert!(is_uniquely_solvable(&board));
+        assert!(find_alternative_solutions(&board, 5).is_empty());
+    }
+
+    #[test_context(UsingLogger)]
+    #[test]
+    fn test_lists_alternative_solutions(_: &mut UsingLogger) {
+        // reveal everything except the last two columns of row 3
+        let mut board = board_with_clues(easy_solution(), &[]);
+        for tile in board.solution.all_tiles() {
+            if tile.row != 3 || tile.variant < 'c' {
+                board.select_tile_from_solution(tile);
+            }
+        }
+        let alternatives = find_alternative_solutions(&board, 5);
+        assert_eq!(alternatives.len(), 1);
+        assert_eq!(alternatives[0].grid[3][2], 'd');
+        assert_eq!(alternatives[0].grid[3][3], 'c');
+
+        // a clue that pins 3c to column 2 removes the alternative
+        let board = {
+            let mut b = board_with_clues(easy_solution(), &["|+0c,+3c|"]);
+            for tile in b.solution.all_tiles() {
+                if tile.row != 3 || tile.variant < 'c' {
+                    b.select_tile_from_solution(tile);
+                }
+            }
+            b
+        };
+        assert!(is_uniquely_solvable(&board));
+    }
+
+    #[test_context(UsingLogger)]
+    #[test]
+    fn test_generated_puzzles_are_unique(_: &mut UsingLogger) {
+        for (difficulty, seed) in [
+            (Difficulty::Easy, 42),
+            (Difficulty::Moderate, 7),
+            (Difficulty::Hard, 5505526811833291606),
+        ] {
+            let solution = Solution::new(difficulty, Some(seed));
+            let result = generate_clues(&GameBoard::new(solution.into()));
+            assert_eq!(
+                count_solutions(&result.board),
+                1,
+                "{:?} puzzle with seed {} has alternative solutions: {:?}",
+                difficulty,
+                seed,
+                find_alternative_solutions(&result.board, 1)
+            );
+            assert!(is_uniquely_solvable(&result.board));
+        }
+    }
+}
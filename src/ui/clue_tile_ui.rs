@@ -6,18 +6,20 @@ use crate::destroyable::Destroyable;
 use crate::model::{Clue, ClueType, CluesSizing, HorizontalClueType, Tile, VerticalClueType};
 use gtk4::glib::{timeout_add_local_once, SourceId};
 use gtk4::prelude::*;
-use gtk4::{Frame, Image, Overlay, Widget};
+use gtk4::{Frame, Image, Label, Overlay, Widget};
 
 use super::ImageSet;
 
 enum Decoration {
     Negative,
     Maybe,
+    Between,
 }
 
 enum ClueTileContents {
     TileAssertion(Tile, Option<Decoration>),
-    LeftOf, /* show the LeftOf icon */
+    LeftOf,          /* show the LeftOf icon */
+    Caption(String), /* show a short symbol, e.g. the number of columns between tiles */
     None,
 }
 
@@ -28,6 +30,7 @@ pub struct ClueTileUI {
     x_image: Image,     // Red X for negative assertions
     maybe_image: Image, // Question mark for maybe assertions
     left_of: Image,     // LeftOf clues
+    caption: Label,     // NApart and positional clues
     highlight_frame: Arc<Frame>,
     decoration_frame: Arc<Frame>, // For red border on negative assertions or yellow for maybe
     resources: Rc<ImageSet>,
@@ -68,6 +71,12 @@ impl ClueTileUI {
         left_of.set_halign(gtk4::Align::Center);
         left_of.set_valign(gtk4::Align::Center);
 
+        let caption = Label::new(None);
+        caption.set_visible(false);
+        caption.set_css_classes(&["clue-caption-label"]);
+        caption.set_halign(gtk4::Align::Center);
+        caption.set_valign(gtk4::Align::Center);
+
         let highlight_frame = Frame::new(None);
         highlight_frame.set_visible(false);
 
@@ -80,6 +89,7 @@ impl ClueTileUI {
         overlay.add_overlay(&x_image);
         overlay.add_overlay(&maybe_image);
         overlay.add_overlay(&left_of);
+        overlay.add_overlay(&caption);
         overlay.add_overlay(highlight_frame.upcast_ref::<Widget>());
         overlay.add_overlay(decoration_frame.upcast_ref::<Widget>());
 
@@ -92,6 +102,7 @@ impl ClueTileUI {
             x_image,
             maybe_image,
             left_of,
+            caption,
             highlight_frame: Arc::new(highlight_frame),
             decoration_frame: Arc::new(decoration_frame),
             resources,
@@ -121,6 +132,7 @@ impl ClueTileUI {
         self.maybe_image.set_visible(false);
         self.x_image.set_visible(false);
         self.left_of.set_visible(false);
+        self.caption.set_visible(false);
         self.decoration_frame.set_visible(false);
 
         self.sync_images();
@@ -148,6 +160,14 @@ impl ClueTileUI {
         self.decoration_frame.set_visible(true);
     }
 
+    fn set_between(&self) {
+        self.x_image.set_visible(false);
+        self.maybe_image.set_visible(false);
+        self.decoration_frame
+            .set_css_classes(&["between-assertion-frame"]);
+        self.decoration_frame.set_visible(true);
+    }
+
     pub(crate) fn highlight_for(&self, from_secs: std::time::Duration) {
         // Cancel any existing timeout
         if let Some(source_id) = self.highlight_timeout.take() {
@@ -197,6 +217,7 @@ impl ClueTileUI {
                         match decoration {
                             Decoration::Negative => self.set_negative(),
                             Decoration::Maybe => self.set_maybe(),
+                            Decoration::Between => self.set_between(),
                         }
                     }
                 }
@@ -206,6 +227,11 @@ impl ClueTileUI {
                     self.left_of.set_visible(true);
                     self.image.clear();
                 }
+                ClueTileContents::Caption(caption) => {
+                    self.caption.set_label(&caption);
+                    self.caption.set_visible(true);
+                    self.image.clear();
+                }
                 ClueTileContents::None => {
                     self.image.clear();
                 }
@@ -221,6 +247,35 @@ impl ClueTileUI {
                 2 => ClueTileContents::TileAssertion(clue.assertions[1].tile, None),
                 _ => ClueTileContents::None,
             },
+            ClueType::Horizontal(HorizontalClueType::NApart(distance)) => match idx {
+                0 => ClueTileContents::TileAssertion(clue.assertions[0].tile, None),
+                1 => ClueTileContents::Caption(format!("↔{}", distance)),
+                2 => ClueTileContents::TileAssertion(clue.assertions[1].tile, None),
+                _ => ClueTileContents::None,
+            },
+            ClueType::Horizontal(
+                position @ (HorizontalClueType::OnEdge
+                | HorizontalClueType::NotOnEdge
+                | HorizontalClueType::LeftHalf),
+            ) => match idx {
+                0 => ClueTileContents::TileAssertion(clue.assertions[0].tile, None),
+                1 => ClueTileContents::Caption(
+                    match position {
+                        HorizontalClueType::OnEdge => "⇤⇥",
+                        HorizontalClueType::NotOnEdge => "⇥⇤",
+                        _ => "◧",
+                    }
+                    .to_string(),
+                ),
+                _ => ClueTileContents::None,
+            },
+            ClueType::Horizontal(HorizontalClueType::Between) => match clue.assertions.get(idx) {
+                Some(assertion) if idx == 1 => {
+                    ClueTileContents::TileAssertion(assertion.tile, Some(Decoration::Between))
+                }
+                Some(assertion) => ClueTileContents::TileAssertion(assertion.tile, None),
+                None => ClueTileContents::None,
+            },
             ClueType::Vertical(VerticalClueType::OneMatchesEither) => match idx {
                 0 => ClueTileContents::TileAssertion(clue.assertions[0].tile, None),
                 1 => ClueTileContents::TileAssertion(
@@ -267,6 +322,7 @@ impl Drop for ClueTileUI {
         self.overlay.remove_overlay(&self.x_image);
         self.overlay.remove_overlay(&self.maybe_image);
         self.overlay.remove_overlay(&self.left_of);
+        self.overlay.remove_overlay(&self.caption);
         self.overlay.remove_overlay(self.highlight_frame.as_ref());
         self.overlay.remove_overlay(self.decoration_frame.as_ref());
 
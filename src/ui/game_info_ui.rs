@@ -3,12 +3,12 @@
 use std::{cell::RefCell, rc::Rc, time::Duration};
 
 use glib::{timeout_add_local, SourceId};
-use gtk4::{prelude::*, Box, Label};
+use gtk4::{prelude::*, Box, Label, Orientation, Spinner};
 
 use crate::{
     destroyable::Destroyable,
     events::{EventObserver, Unsubscriber},
-    model::{GameStateEvent, TimerState},
+    model::{GameStateEvent, GenerationProgress, TimerState},
 };
 
 pub struct GameInfoUI {
@@ -19,6 +19,11 @@ pub struct GameInfoUI {
     timer: Option<SourceId>,
     game_box: Rc<Box>,
     pause_screen: Rc<Box>,
+    /// Shown in place of the board while a new puzzle is generated
+    pub generation_screen: Box,
+    generation_spinner: Spinner,
+    generation_label: Label,
+    generation_progress: Option<GenerationProgress>,
     game_state_subscription: Option<Unsubscriber<GameStateEvent>>,
 }
 
@@ -50,6 +55,24 @@ impl GameInfoUI {
         let timer_state = TimerState::default();
         GameInfoUI::update_timer_label(&timer_label, &timer_state);
 
+        let generation_spinner = Spinner::builder()
+            .width_request(48)
+            .height_request(48)
+            .build();
+        let generation_label = Label::builder().css_classes(["generation-label"]).build();
+        let generation_screen = Box::builder()
+            .name("generation-screen")
+            .orientation(Orientation::Vertical)
+            .spacing(10)
+            .halign(gtk4::Align::Center)
+            .valign(gtk4::Align::Center)
+            .hexpand(true)
+            .vexpand(true)
+            .visible(false)
+            .build();
+        generation_screen.append(&generation_spinner);
+        generation_screen.append(&generation_label);
+
         let game_info = Rc::new(RefCell::new(Self {
             hints_used: 0,
             timer_state,
@@ -58,6 +81,10 @@ impl GameInfoUI {
             timer: None,
             game_box,
             pause_screen,
+            generation_screen,
+            generation_spinner,
+            generation_label,
+            generation_progress: None,
             game_state_subscription: None,
         }));
 
@@ -93,6 +120,20 @@ impl GameInfoUI {
             GameStateEvent::HintUsageChanged(hints_used) => {
                 self.update_hints_used(*hints_used);
             }
+            GameStateEvent::GenerationProgress(progress) => {
+                self.generation_progress = Some(*progress);
+                self.generation_label.set_text(&format!(
+                    "Generating {} puzzle... {} clues placed, {:.0}% solved",
+                    progress.difficulty.to_string(),
+                    progress.clues_placed,
+                    progress.completeness() * 100.0
+                ));
+                self.sync_screens();
+            }
+            GameStateEvent::GenerationFinished => {
+                self.generation_progress = None;
+                self.sync_screens();
+            }
             _ => {}
         }
     }
@@ -113,18 +154,24 @@ impl GameInfoUI {
         if is_paused {
             // stop the timer update
             self.pause_timer_label_handler();
-
-            // hide the game
-            self.game_box.set_visible(false);
-            // show the pause screen
-            self.pause_screen.set_visible(true);
         } else {
             self.start_timer_label_handler(game_info.clone());
-            // show the game
-            self.game_box.set_visible(true);
-            // hide the pause screen
-            self.pause_screen.set_visible(false);
         }
+        self.sync_screens();
+    }
+
+    /// Shows exactly one of the game, the pause screen, or the generation spinner
+    fn sync_screens(&self) {
+        let is_generating = self.generation_progress.is_some();
+        let is_paused = self.timer_state.paused_timestamp.is_some();
+        self.generation_screen.set_visible(is_generating);
+        if is_generating {
+            self.generation_spinner.start();
+        } else {
+            self.generation_spinner.stop();
+        }
+        self.pause_screen.set_visible(!is_generating && is_paused);
+        self.game_box.set_visible(!is_generating && !is_paused);
     }
 
     fn pause_timer_label_handler(&mut self) {
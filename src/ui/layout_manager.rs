@@ -196,14 +196,20 @@ impl LayoutManager {
 
     fn handle_game_action_event(&mut self, event: &GameActionEvent) {
         match event {
-            GameActionEvent::NewGame(difficulty, _) => self.update_difficulty(*difficulty),
+            GameActionEvent::NewGame(difficulty, _)
+            | GameActionEvent::RegenerateGame(difficulty, _, _)
+            | GameActionEvent::NewDailyGame(difficulty, _) => self.update_difficulty(*difficulty),
             _ => (),
         }
     }
 
     fn handle_game_state_event(&mut self, event: &GameStateEvent) {
         match event {
-            GameStateEvent::ClueSetUpdate(clue_set, _) => self.update_clue_stats(clue_set.as_ref()),
+            GameStateEvent::ClueSetUpdate(clue_set, difficulty) => {
+                // loaded puzzles can change the difficulty without a NewGame
+                self.update_difficulty(*difficulty);
+                self.update_clue_stats(clue_set.as_ref())
+            }
             _ => (),
         }
     }
@@ -10,11 +10,16 @@ mod input_translator;
 mod layout;
 mod layout_manager;
 mod not_quite_right_dialog;
+mod profile_ui;
 mod puzzle_cell_ui;
 mod puzzle_grid_ui;
+mod race_ui;
+mod replay_dialog;
 mod resource_manager;
+mod save_slots_dialog;
 mod seed_dialog;
 mod settings_menu_ui;
+mod stats_charts;
 mod stats_dialog;
 mod submit_ui;
 mod template;
@@ -35,7 +40,9 @@ pub use layout_manager::LayoutManager;
 pub use not_quite_right_dialog::NotQuiteRightDialog;
 pub use puzzle_cell_ui::PuzzleCellUI;
 pub use puzzle_grid_ui::PuzzleGridUI;
+pub use replay_dialog::ReplayLauncher;
 pub use resource_manager::ResourceManager;
+pub use save_slots_dialog::SaveSlotsDialog;
 pub use seed_dialog::SeedDialog;
 pub use settings_menu_ui::SettingsMenuUI;
 pub use stats_dialog::StatsDialog;
@@ -0,0 +1,282 @@
+use gio::{Menu, MenuItem, SimpleAction};
+use glib::prelude::ToVariant;
+use glib::{Propagation, Variant, VariantTy};
+use gtk4::{
+    gdk, prelude::*, AlertDialog, Align, ApplicationWindow, Box, Button, Entry, EventControllerKey,
+    Label, MenuButton, Orientation,
+};
+use std::cell::RefCell;
+use std::rc::Rc;
+use uuid::Uuid;
+
+use crate::game::profiles::{Profile, ProfileManager};
+
+/// The header bar's profile switcher. Changing profiles calls `on_switch`, which reopens the
+/// game on the new profile's settings, saved games and stats.
+pub struct ProfileUI;
+
+impl ProfileUI {
+    pub fn new<F>(
+        window: &Rc<ApplicationWindow>,
+        profiles: &Rc<RefCell<ProfileManager>>,
+        on_switch: F,
+    ) -> MenuButton
+    where
+        F: Fn() + 'static,
+    {
+        let on_switch: Rc<dyn Fn()> = Rc::new(on_switch);
+        let menu = Menu::new();
+        Self::fill_menu(&menu, &profiles.borrow());
+        let current = profiles.borrow().current().clone();
+        let button = MenuButton::builder()
+            .label(current.name.as_str())
+            .tooltip_text("Switch profile")
+            .menu_model(&menu)
+            .build();
+
+        let action_switch = SimpleAction::new_stateful(
+            "switch-profile",
+            Some(VariantTy::STRING),
+            &current.id.to_string().to_variant(),
+        );
+        action_switch.connect_activate({
+            let window = window.clone();
+            let profiles = Rc::clone(profiles);
+            let on_switch = Rc::clone(&on_switch);
+            move |action, parameter| {
+                let Some(id) = Self::profile_id(parameter) else {
+                    return;
+                };
+                if id == profiles.borrow().current().id {
+                    return;
+                }
+                let switched = profiles.borrow_mut().switch_to(id);
+                match switched {
+                    Ok(()) => {
+                        action.set_state(&id.to_string().to_variant());
+                        on_switch();
+                    }
+                    Err(e) => Self::show_error(&window, "Could not switch profile", &e),
+                }
+            }
+        });
+        window.add_action(&action_switch);
+
+        let action_new = SimpleAction::new("new-profile", None);
+        action_new.connect_activate({
+            let window = window.clone();
+            let profiles = Rc::clone(profiles);
+            move |_, _| Self::show_new_profile(&window, &profiles, Rc::clone(&on_switch))
+        });
+        window.add_action(&action_new);
+
+        let action_delete = SimpleAction::new("delete-profile", Some(VariantTy::STRING));
+        action_delete.connect_activate({
+            let window = window.clone();
+            let profiles = Rc::clone(profiles);
+            move |_, parameter| {
+                let profile = Self::profile_id(parameter).and_then(|id| {
+                    profiles
+                        .borrow()
+                        .profiles()
+                        .iter()
+                        .find(|profile| profile.id == id)
+                        .cloned()
+                });
+                if let Some(profile) = profile {
+                    Self::confirm_delete(&window, &profiles, &menu, profile);
+                }
+            }
+        });
+        window.add_action(&action_delete);
+
+        button
+    }
+
+    fn profile_id(parameter: Option<&Variant>) -> Option<Uuid> {
+        parameter
+            .and_then(|parameter| parameter.get::<String>())
+            .and_then(|id| Uuid::parse_str(&id).ok())
+    }
+
+    /// Menu labels treat underscores as mnemonics
+    fn menu_label(profile: &Profile) -> String {
+        profile.name.replace('_', "__")
+    }
+
+    fn fill_menu(menu: &Menu, profiles: &ProfileManager) {
+        menu.remove_all();
+
+        let switch_section = Menu::new();
+        for profile in profiles.profiles() {
+            let item = MenuItem::new(Some(&Self::menu_label(profile)), None);
+            item.set_action_and_target_value(
+                Some("win.switch-profile"),
+                Some(&profile.id.to_string().to_variant()),
+            );
+            switch_section.append_item(&item);
+        }
+        menu.append_section(None, &switch_section);
+
+        let manage_section = Menu::new();
+        manage_section.append(Some("New Profile…"), Some("win.new-profile"));
+        // the profile in use can't be deleted
+        let delete_menu = Menu::new();
+        for profile in profiles
+            .profiles()
+            .iter()
+            .filter(|profile| profile.id != profiles.current().id)
+        {
+            let item = MenuItem::new(Some(&Self::menu_label(profile)), None);
+            item.set_action_and_target_value(
+                Some("win.delete-profile"),
+                Some(&profile.id.to_string().to_variant()),
+            );
+            delete_menu.append_item(&item);
+        }
+        if delete_menu.n_items() > 0 {
+            manage_section.append_submenu(Some("Delete Profile"), &delete_menu);
+        }
+        menu.append_section(None, &manage_section);
+    }
+
+    fn show_error(window: &ApplicationWindow, message: &str, err: &dyn std::fmt::Display) {
+        log::error!(target: "profile_ui", "{}: {}", message, err);
+        AlertDialog::builder()
+            .message(message)
+            .detail(err.to_string())
+            .modal(true)
+            .build()
+            .show(Some(window));
+    }
+
+    fn confirm_delete(
+        window: &Rc<ApplicationWindow>,
+        profiles: &Rc<RefCell<ProfileManager>>,
+        menu: &Menu,
+        profile: Profile,
+    ) {
+        let dialog = AlertDialog::builder()
+            .message(format!("Delete the profile \"{}\"?", profile.name))
+            .detail("Its settings, saved games and statistics will be deleted for good.")
+            .buttons(["Cancel", "Delete"])
+            .cancel_button(0)
+            .default_button(0)
+            .modal(true)
+            .build();
+
+        let window_ref = window.clone();
+        let profiles = Rc::clone(profiles);
+        let menu = menu.clone();
+        dialog.choose(
+            Some(window.as_ref()),
+            None::<&gio::Cancellable>,
+            move |response| {
+                if !matches!(response, Ok(1)) {
+                    return;
+                }
+                let deleted = profiles.borrow_mut().delete(profile.id);
+                match deleted {
+                    Ok(()) => Self::fill_menu(&menu, &profiles.borrow()),
+                    Err(e) => Self::show_error(&window_ref, "Could not delete profile", &e),
+                }
+            },
+        );
+    }
+
+    /// Asks for a name, creates the profile and switches to it
+    fn show_new_profile(
+        window: &Rc<ApplicationWindow>,
+        profiles: &Rc<RefCell<ProfileManager>>,
+        on_switch: Rc<dyn Fn()>,
+    ) {
+        let content_area = Box::builder()
+            .orientation(Orientation::Vertical)
+            .spacing(10)
+            .margin_bottom(10)
+            .margin_top(10)
+            .margin_start(20)
+            .margin_end(20)
+            .build();
+        let dialog = gtk4::Window::builder()
+            .title("New Profile")
+            .transient_for(window.as_ref())
+            .modal(true)
+            .child(&content_area)
+            .default_width(300)
+            .build();
+
+        content_area.append(
+            &Label::builder()
+                .label("Profile name:")
+                .halign(Align::Start)
+                .build(),
+        );
+        let entry = Entry::new();
+        content_area.append(&entry);
+        let error_label = Label::builder()
+            .css_classes(["error"])
+            .halign(Align::Start)
+            .wrap(true)
+            .visible(false)
+            .build();
+        content_area.append(&error_label);
+
+        let button_box = Box::builder()
+            .orientation(Orientation::Horizontal)
+            .spacing(10)
+            .halign(Align::End)
+            .build();
+        let cancel_button = Button::with_label("Cancel");
+        let create_button = Button::with_label("Create");
+        button_box.append(&cancel_button);
+        button_box.append(&create_button);
+        content_area.append(&button_box);
+
+        cancel_button.connect_clicked({
+            let dialog = dialog.clone();
+            move |_| dialog.close()
+        });
+
+        let key_controller = EventControllerKey::new();
+        key_controller.connect_key_pressed({
+            let dialog = dialog.clone();
+            move |_, keyval, _, _| {
+                if keyval == gdk::Key::Escape {
+                    dialog.close();
+                    return Propagation::Stop;
+                }
+                Propagation::Proceed
+            }
+        });
+        dialog.add_controller(key_controller);
+
+        let accept: Rc<dyn Fn()> = Rc::new({
+            let dialog = dialog.clone();
+            let entry = entry.clone();
+            let profiles = Rc::clone(profiles);
+            move || {
+                let created = profiles.borrow_mut().create(&entry.text());
+                let switched =
+                    created.and_then(|profile| profiles.borrow_mut().switch_to(profile.id));
+                match switched {
+                    Ok(()) => {
+                        dialog.close();
+                        on_switch();
+                    }
+                    Err(e) => {
+                        error_label.set_label(&e.to_string());
+                        error_label.set_visible(true);
+                    }
+                }
+            }
+        });
+        create_button.connect_clicked({
+            let accept = accept.clone();
+            move |_| accept()
+        });
+        entry.connect_activate(move |_| accept());
+
+        dialog.present();
+    }
+}
@@ -1,24 +1,45 @@
+use glib::{timeout_add_local, ControlFlow, SourceId};
 use gtk4::{
     prelude::{GridExt, WidgetExt},
-    Grid,
+    Align, Grid, Overlay, ProgressBar,
 };
 use log::trace;
-use std::{cell::RefCell, collections::HashSet, rc::Rc, time::Duration};
+use std::{
+    cell::RefCell,
+    collections::HashSet,
+    rc::{Rc, Weak},
+    time::Duration,
+};
 
 use crate::{
     destroyable::Destroyable,
     events::{EventEmitter, EventObserver, Unsubscriber},
-    game::settings::Settings,
+    game::{ghost::Ghost, settings::Settings, stats_manager::StatsManager},
     model::{
-        ClueAddress, ClueWithAddress, Difficulty, GameStateEvent, GlobalEvent, InputEvent,
-        LayoutConfiguration, Solution,
+        ClueAddress, ClueWithAddress, Difficulty, GameBoard, GameStateEvent, GlobalEvent,
+        InputEvent, LayoutConfiguration, PuzzleCompletionState, Solution, TimerState,
     },
 };
 
-use super::{puzzle_cell_ui::PuzzleCellUI, ImageSet};
+use super::{puzzle_cell_ui::PuzzleCellUI, ImageSet, StatsDialog};
+
+/// Identifies a puzzle: its difficulty, seed and generator version
+type PuzzleKey = (Difficulty, u64, u32);
 
 pub struct PuzzleGridUI {
+    /// The grid, with the ghost's progress drawn over it
+    pub overlay: Overlay,
     pub grid: Grid,
+    ghost_bar: ProgressBar,
+    /// Where ghosts come from; grids that show something other than the game in progress have
+    /// none
+    stats_manager: Option<Rc<RefCell<StatsManager>>>,
+    /// The fastest earlier solve of the puzzle on the board, if there is one
+    ghost: Option<Ghost>,
+    ghost_puzzle: Option<PuzzleKey>,
+    ghost_ticker: Option<SourceId>,
+    timer_state: TimerState,
+    self_ref: Weak<RefCell<Self>>,
     cells: Vec<Vec<Rc<RefCell<PuzzleCellUI>>>>,
     input_event_emitter: EventEmitter<InputEvent>,
     resources: Rc<ImageSet>,
@@ -38,7 +59,10 @@ pub struct PuzzleGridUI {
 impl Destroyable for PuzzleGridUI {
     fn destroy(&mut self) {
         // Unparent all widgets
-        self.grid.unparent();
+        self.overlay.unparent();
+        if let Some(ticker) = self.ghost_ticker.take() {
+            ticker.remove();
+        }
         if let Some(subscription_id) = self.game_state_subscription_id.take() {
             subscription_id.unsubscribe();
         }
@@ -56,12 +80,32 @@ impl PuzzleGridUI {
         resources: Rc<ImageSet>,
         layout: LayoutConfiguration,
         settings: &Settings,
+        stats_manager: Option<&Rc<RefCell<StatsManager>>>,
     ) -> Rc<RefCell<Self>> {
         let grid = Grid::new();
         grid.set_css_classes(&["puzzle-grid"]);
 
+        // passes clicks through to the cells underneath
+        let ghost_bar = ProgressBar::builder()
+            .css_classes(["ghost-progress"])
+            .show_text(true)
+            .valign(Align::End)
+            .can_target(false)
+            .visible(false)
+            .build();
+        let overlay = Overlay::builder().child(&grid).build();
+        overlay.add_overlay(&ghost_bar);
+
         let puzzle_grid_ui = Rc::new(RefCell::new(Self {
+            overlay,
             grid,
+            ghost_bar,
+            stats_manager: stats_manager.cloned(),
+            ghost: None,
+            ghost_puzzle: None,
+            ghost_ticker: None,
+            timer_state: TimerState::default(),
+            self_ref: Weak::new(),
             cells: vec![],
             input_event_emitter,
             resources,
@@ -77,6 +121,7 @@ impl PuzzleGridUI {
             current_difficulty: settings.difficulty,
             settings: settings.clone(),
         }));
+        puzzle_grid_ui.borrow_mut().self_ref = Rc::downgrade(&puzzle_grid_ui);
 
         // Subscribe to layout changes
         Self::connect_global_observer(puzzle_grid_ui.clone(), global_event_observer);
@@ -150,6 +195,7 @@ impl PuzzleGridUI {
             GlobalEvent::SettingsChanged(settings) => {
                 self.settings = settings.clone();
                 self.sync_clue_spotlight_enabled();
+                self.sync_ghost();
             }
             _ => (),
         }
@@ -184,6 +230,16 @@ impl PuzzleGridUI {
                 }
                 self.completed_clues = board.completed_clues().clone();
                 self.sync_clue_spotlight_enabled();
+                self.load_ghost(board);
+            }
+            GameStateEvent::TimerStateChanged(timer_state) => {
+                self.timer_state = timer_state.clone();
+                self.sync_ghost_ticker();
+                self.sync_ghost();
+            }
+            GameStateEvent::PuzzleCompleted(PuzzleCompletionState::Correct(_)) => {
+                // this solve may be the new one to beat; look again when the puzzle is next shown
+                self.ghost_puzzle = None;
             }
             GameStateEvent::CellHintHighlight(deduction) => {
                 self.highlight_candidate(
@@ -192,6 +248,13 @@ impl PuzzleGridUI {
                     deduction.tile_assertion.tile.variant,
                 );
             }
+            GameStateEvent::NakedSetHintHighlight(naked_set) => {
+                for column in naked_set.columns.iter() {
+                    for variant in naked_set.variants.iter() {
+                        self.highlight_candidate(naked_set.row, *column, *variant);
+                    }
+                }
+            }
             GameStateEvent::ClueSelected(clue_selection) => {
                 if let Some(clue_selection) = clue_selection {
                     if clue_selection.is_focused {
@@ -301,6 +364,76 @@ impl PuzzleGridUI {
         // self.grid.set_vexpand(false);
     }
 
+    /// Looks up the ghost when a different puzzle is shown
+    fn load_ghost(&mut self, board: &GameBoard) {
+        let solution = &board.solution;
+        let puzzle = (
+            solution.difficulty,
+            solution.seed,
+            solution.generator_version,
+        );
+        if self.ghost_puzzle == Some(puzzle) {
+            return;
+        }
+        self.ghost_puzzle = Some(puzzle);
+        self.ghost = self
+            .stats_manager
+            .as_ref()
+            .and_then(|stats_manager| {
+                stats_manager
+                    .borrow()
+                    .get_ghost(puzzle.0, puzzle.1, puzzle.2)
+            })
+            .and_then(|stats| Ghost::from_stats(&stats));
+        self.sync_ghost_ticker();
+        self.sync_ghost();
+    }
+
+    /// Moves the ghost along once a second while the clock runs
+    fn sync_ghost_ticker(&mut self) {
+        let running = self.ghost.is_some()
+            && !self.timer_state.is_paused()
+            && self.timer_state.ended_timestamp.is_none();
+        match (running, self.ghost_ticker.is_some()) {
+            (true, false) => {
+                let puzzle_grid_ui = self.self_ref.clone();
+                self.ghost_ticker = Some(timeout_add_local(Duration::from_secs(1), move || {
+                    let Some(puzzle_grid_ui) = puzzle_grid_ui.upgrade() else {
+                        return ControlFlow::Break;
+                    };
+                    puzzle_grid_ui.borrow().sync_ghost();
+                    ControlFlow::Continue
+                }));
+            }
+            (false, true) => {
+                if let Some(ticker) = self.ghost_ticker.take() {
+                    ticker.remove();
+                }
+            }
+            _ => (),
+        }
+    }
+
+    fn sync_ghost(&self) {
+        let Some(ghost) = self.ghost.as_ref().filter(|_| self.settings.ghost_enabled) else {
+            self.ghost_bar.set_visible(false);
+            return;
+        };
+        let elapsed = self.timer_state.elapsed();
+        let progress = ghost.progress_at(elapsed);
+        self.ghost_bar.set_fraction(progress as f64 / 100.0);
+        let text = if elapsed >= ghost.completion_time() {
+            format!(
+                "Best solve finished in {}",
+                StatsDialog::format_duration(ghost.completion_time())
+            )
+        } else {
+            format!("Best solve: {}%", progress)
+        };
+        self.ghost_bar.set_text(Some(text.as_str()));
+        self.ghost_bar.set_visible(true);
+    }
+
     pub(crate) fn highlight_candidate(&self, row: usize, column: usize, variant: char) {
         self.cells[row][column]
             .borrow()
@@ -0,0 +1,650 @@
+use glib::{timeout_add_local, ControlFlow, SourceId};
+use gtk4::{
+    prelude::*, Align, ApplicationWindow, Box, Button, Entry, Label, Orientation, ProgressBar,
+    SpinButton,
+};
+use std::cell::RefCell;
+use std::rc::{Rc, Weak};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::destroyable::Destroyable;
+use crate::events::{EventEmitter, EventObserver, Unsubscriber};
+use crate::game::race::{
+    self, RaceError, RacePhase, RacePlayer, RaceResult, RaceSession, RaceUpdate, DEFAULT_PORT,
+};
+use crate::model::{
+    Difficulty, GameActionEvent, GameBoard, GameStateEvent, GameStateSnapshot,
+    PuzzleCompletionState, PuzzleSource, ShareCode, TimerState,
+};
+
+use super::StatsDialog;
+
+/// How often the main loop checks for news from the other players
+const RACE_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// The race window's widgets; it's built when first opened and dropped when closed
+struct RaceWidgets {
+    dialog: gtk4::Window,
+    setup_box: Box,
+    race_box: Box,
+    name_entry: Entry,
+    host_button: Button,
+    join_button: Button,
+    error_label: Label,
+    status_label: Label,
+    players_box: Box,
+    start_button: Button,
+}
+
+/// The race's puzzle, generated on a worker thread while in the lobby and kept hidden until
+/// the start
+struct PreparedPuzzle {
+    cancelled: Arc<AtomicBool>,
+    receiver: Receiver<GameStateSnapshot>,
+    snapshot: Option<GameStateSnapshot>,
+    /// The race started before the puzzle was ready; show it as soon as it is
+    show_when_ready: bool,
+}
+
+/// Hosting and joining races over the local network, and the live standings while one runs.
+/// Progress and the final time are taken from this player's own game.
+pub struct RaceUI {
+    window: Rc<ApplicationWindow>,
+    game_action_emitter: EventEmitter<GameActionEvent>,
+    subscription_id: Option<Unsubscriber<GameStateEvent>>,
+    session: Option<RaceSession>,
+    poller: Option<SourceId>,
+    /// Waits on the worker thread connecting to a race, while one does
+    connecting: Option<SourceId>,
+    prepared: Option<PreparedPuzzle>,
+    widgets: Option<RaceWidgets>,
+    player_name: String,
+    current_difficulty: Difficulty,
+    /// Whether the board shows the race's puzzle; only then does progress count
+    on_race_puzzle: bool,
+    self_ref: Weak<RefCell<Self>>,
+}
+
+impl Destroyable for RaceUI {
+    fn destroy(&mut self) {
+        if let Some(subscription_id) = self.subscription_id.take() {
+            subscription_id.unsubscribe();
+        }
+        self.leave();
+        if let Some(widgets) = self.widgets.take() {
+            // unlike close(), this doesn't call back into the close handler
+            widgets.dialog.destroy();
+        }
+    }
+}
+
+impl RaceUI {
+    pub fn new(
+        window: &Rc<ApplicationWindow>,
+        game_action_emitter: EventEmitter<GameActionEvent>,
+        game_state_observer: EventObserver<GameStateEvent>,
+        player_name: &str,
+    ) -> Rc<RefCell<Self>> {
+        let race_ui = Rc::new(RefCell::new(Self {
+            window: window.clone(),
+            game_action_emitter,
+            subscription_id: None,
+            session: None,
+            poller: None,
+            connecting: None,
+            prepared: None,
+            widgets: None,
+            player_name: player_name.to_string(),
+            current_difficulty: Difficulty::default(),
+            on_race_puzzle: false,
+            self_ref: Weak::new(),
+        }));
+        race_ui.borrow_mut().self_ref = Rc::downgrade(&race_ui);
+
+        let race_ui_handler = race_ui.clone();
+        let subscription_id = game_state_observer.subscribe(move |event| {
+            race_ui_handler.borrow_mut().handle_game_state_event(event);
+        });
+        race_ui.borrow_mut().subscription_id = Some(subscription_id);
+
+        race_ui
+    }
+
+    fn handle_game_state_event(&mut self, event: &GameStateEvent) {
+        match event {
+            GameStateEvent::GridUpdate(board) => {
+                self.current_difficulty = board.solution.difficulty;
+                self.on_race_puzzle = self.is_race_puzzle(board);
+                if let (true, Some(session)) = (self.on_race_puzzle, &mut self.session) {
+                    session.report_progress(board.completion_percent());
+                }
+            }
+            GameStateEvent::PuzzleCompleted(PuzzleCompletionState::Correct(stats)) => {
+                if let (true, Some(session)) = (self.on_race_puzzle, &mut self.session) {
+                    session.report_finished(RaceResult {
+                        completion_time: stats.completion_time,
+                        hints_used: stats.hints_used,
+                    });
+                }
+            }
+            _ => (),
+        }
+    }
+
+    fn is_race_puzzle(&self, board: &GameBoard) -> bool {
+        let Some(session) = &self.session else {
+            return false;
+        };
+        let share_code = ShareCode {
+            difficulty: board.solution.difficulty,
+            seed: board.solution.seed,
+            generator_version: board.solution.generator_version,
+        };
+        session.phase() != RacePhase::Lobby && session.share_code() == Some(share_code)
+    }
+
+    /// Opens the race window, or brings it forward if it's already open
+    pub fn show(&mut self) {
+        if self.widgets.is_none() {
+            self.widgets = Some(self.build_widgets());
+            self.refresh();
+        }
+        if let Some(widgets) = &self.widgets {
+            widgets.dialog.present();
+        }
+    }
+
+    fn build_widgets(&self) -> RaceWidgets {
+        let content_area = Box::builder()
+            .orientation(Orientation::Vertical)
+            .spacing(10)
+            .margin_bottom(10)
+            .margin_top(10)
+            .margin_start(20)
+            .margin_end(20)
+            .build();
+        // not modal, so the standings can stay up beside the puzzle
+        let dialog = gtk4::Window::builder()
+            .title("Race")
+            .transient_for(self.window.as_ref())
+            .child(&content_area)
+            .default_width(340)
+            .build();
+
+        let setup_box = Box::builder()
+            .orientation(Orientation::Vertical)
+            .spacing(10)
+            .build();
+        setup_box.append(
+            &Label::builder()
+                .label("Your name:")
+                .halign(Align::Start)
+                .build(),
+        );
+        let name_entry = Entry::builder().text(self.player_name.as_str()).build();
+        setup_box.append(&name_entry);
+
+        setup_box.append(
+            &Label::builder()
+                .label("Host a race on a new puzzle at the current difficulty, on port:")
+                .halign(Align::Start)
+                .wrap(true)
+                .build(),
+        );
+        let host_box = Box::builder()
+            .orientation(Orientation::Horizontal)
+            .spacing(10)
+            .build();
+        let port_spin = SpinButton::with_range(1024.0, 65535.0, 1.0);
+        port_spin.set_value(DEFAULT_PORT as f64);
+        port_spin.set_hexpand(true);
+        let host_button = Button::with_label("Host");
+        host_box.append(&port_spin);
+        host_box.append(&host_button);
+        setup_box.append(&host_box);
+
+        setup_box.append(
+            &Label::builder()
+                .label("Or join the race at:")
+                .halign(Align::Start)
+                .build(),
+        );
+        let join_box = Box::builder()
+            .orientation(Orientation::Horizontal)
+            .spacing(10)
+            .build();
+        let address_entry = Entry::builder()
+            .placeholder_text(format!("192.168.1.20:{}", DEFAULT_PORT))
+            .hexpand(true)
+            .build();
+        let join_button = Button::with_label("Join");
+        join_box.append(&address_entry);
+        join_box.append(&join_button);
+        setup_box.append(&join_box);
+
+        let error_label = Label::builder()
+            .css_classes(["error"])
+            .halign(Align::Start)
+            .wrap(true)
+            .visible(false)
+            .build();
+        setup_box.append(&error_label);
+        content_area.append(&setup_box);
+
+        let race_box = Box::builder()
+            .orientation(Orientation::Vertical)
+            .spacing(10)
+            .visible(false)
+            .build();
+        let status_label = Label::builder()
+            .halign(Align::Start)
+            .wrap(true)
+            .selectable(true)
+            .build();
+        race_box.append(&status_label);
+        let players_box = Box::builder()
+            .orientation(Orientation::Vertical)
+            .spacing(6)
+            .build();
+        race_box.append(&players_box);
+        let button_box = Box::builder()
+            .orientation(Orientation::Horizontal)
+            .spacing(10)
+            .halign(Align::End)
+            .build();
+        let leave_button = Button::with_label("Leave");
+        let start_button = Button::with_label("Start Race");
+        button_box.append(&leave_button);
+        button_box.append(&start_button);
+        race_box.append(&button_box);
+        content_area.append(&race_box);
+
+        host_button.connect_clicked({
+            let race_ui = self.self_ref.clone();
+            let name_entry = name_entry.clone();
+            let port_spin = port_spin.clone();
+            move |_| {
+                let Some(race_ui) = race_ui.upgrade() else {
+                    return;
+                };
+                let difficulty = race_ui.borrow().current_difficulty;
+                let share_code = ShareCode::new(difficulty, rand::random());
+                let session = RaceSession::host(
+                    &name_entry.text(),
+                    share_code,
+                    port_spin.value_as_int() as u16,
+                );
+                race_ui.borrow_mut().begin(session);
+            }
+        });
+
+        let join = {
+            let race_ui = self.self_ref.clone();
+            let name_entry = name_entry.clone();
+            let address_entry = address_entry.clone();
+            move || {
+                if let Some(race_ui) = race_ui.upgrade() {
+                    race_ui.borrow_mut().connect(
+                        name_entry.text().to_string(),
+                        address_entry.text().to_string(),
+                    );
+                }
+            }
+        };
+        let join = Rc::new(join);
+        join_button.connect_clicked({
+            let join = join.clone();
+            move |_| join()
+        });
+        address_entry.connect_activate(move |_| join());
+
+        start_button.connect_clicked({
+            let race_ui = self.self_ref.clone();
+            move |_| {
+                if let Some(race_ui) = race_ui.upgrade() {
+                    if let Some(session) = &mut race_ui.borrow_mut().session {
+                        session.start();
+                    }
+                }
+            }
+        });
+
+        leave_button.connect_clicked({
+            let race_ui = self.self_ref.clone();
+            move |_| {
+                if let Some(race_ui) = race_ui.upgrade() {
+                    let mut race_ui = race_ui.borrow_mut();
+                    race_ui.leave();
+                    race_ui.refresh();
+                }
+            }
+        });
+
+        dialog.connect_close_request({
+            let race_ui = self.self_ref.clone();
+            move |_| {
+                if let Some(race_ui) = race_ui.upgrade() {
+                    let mut race_ui = race_ui.borrow_mut();
+                    race_ui.leave();
+                    race_ui.widgets = None;
+                }
+                glib::Propagation::Proceed
+            }
+        });
+
+        RaceWidgets {
+            dialog,
+            setup_box,
+            race_box,
+            name_entry,
+            host_button,
+            join_button,
+            error_label,
+            status_label,
+            players_box,
+            start_button,
+        }
+    }
+
+    /// Joins the race at `address` on a worker thread, as resolving the name and waiting on an
+    /// unreachable address can take a while; the race begins once connected
+    fn connect(&mut self, name: String, address: String) {
+        if self.connecting.is_some() {
+            return;
+        }
+        let (sender, receiver) = mpsc::channel();
+        {
+            let address = address.clone();
+            std::thread::spawn(move || {
+                // the receiver is gone if the race window was closed meanwhile
+                let _ = sender.send(RaceSession::join(&name, &address));
+            });
+        }
+
+        let race_ui = self.self_ref.clone();
+        self.connecting = Some(timeout_add_local(RACE_POLL_INTERVAL, move || {
+            let Some(race_ui) = race_ui.upgrade() else {
+                return ControlFlow::Break;
+            };
+            let session = match receiver.try_recv() {
+                Ok(session) => session,
+                Err(TryRecvError::Empty) => return ControlFlow::Continue,
+                Err(TryRecvError::Disconnected) => {
+                    Err(RaceError::Unreachable(address.trim().to_string()))
+                }
+            };
+            let mut race_ui = race_ui.borrow_mut();
+            race_ui.connecting = None;
+            race_ui.begin(session);
+            ControlFlow::Break
+        }));
+        self.refresh();
+    }
+
+    fn begin(&mut self, session: Result<RaceSession, RaceError>) {
+        let session = match session {
+            Ok(session) => session,
+            Err(e) => {
+                log::error!(target: "race_ui", "Could not start the race: {}", e);
+                if let Some(widgets) = &self.widgets {
+                    widgets.error_label.set_label(&e.to_string());
+                    widgets.error_label.set_visible(true);
+                }
+                self.refresh();
+                return;
+            }
+        };
+        if let Some(widgets) = &self.widgets {
+            self.player_name = widgets.name_entry.text().to_string();
+            widgets.error_label.set_visible(false);
+        }
+        self.leave();
+        self.session = Some(session);
+        self.on_race_puzzle = false;
+
+        let race_ui = self.self_ref.clone();
+        self.poller = Some(timeout_add_local(RACE_POLL_INTERVAL, move || {
+            let Some(race_ui) = race_ui.upgrade() else {
+                return ControlFlow::Break;
+            };
+            race_ui.borrow_mut().poll();
+            ControlFlow::Continue
+        }));
+        self.refresh();
+    }
+
+    fn poll(&mut self) {
+        let Some(session) = &mut self.session else {
+            return;
+        };
+        let updates = session.poll();
+        let puzzle_arrived = self.poll_prepared();
+        if updates.is_empty() && !puzzle_arrived {
+            return;
+        }
+        for update in updates {
+            match update {
+                RaceUpdate::PlayersChanged => (),
+                RaceUpdate::PuzzleChosen(share_code) => self.prepare(share_code),
+                RaceUpdate::Started(_) => {
+                    self.on_race_puzzle = false;
+                    if let Some(prepared) = &mut self.prepared {
+                        prepared.show_when_ready = true;
+                    }
+                    self.show_prepared();
+                }
+                RaceUpdate::Closed(reason) => {
+                    self.leave();
+                    if let Some(widgets) = &self.widgets {
+                        widgets.error_label.set_label(&reason);
+                        widgets.error_label.set_visible(true);
+                    }
+                    break;
+                }
+            }
+        }
+        self.refresh();
+    }
+
+    /// Generates the race's puzzle in the background, so that it's ready when the race starts
+    fn prepare(&mut self, share_code: ShareCode) {
+        self.cancel_prepared();
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let (sender, receiver) = mpsc::channel();
+        {
+            let cancelled = Arc::clone(&cancelled);
+            std::thread::spawn(move || {
+                let snapshot = GameStateSnapshot::generate_new_cancellable(
+                    share_code.difficulty,
+                    Some(share_code.seed),
+                    share_code.generator_version,
+                    &cancelled,
+                    &|_| {},
+                );
+                if let Some(snapshot) = snapshot {
+                    // the receiver is gone if we left the race meanwhile
+                    let _ = sender.send(snapshot);
+                }
+            });
+        }
+        self.prepared = Some(PreparedPuzzle {
+            cancelled,
+            receiver,
+            snapshot: None,
+            show_when_ready: false,
+        });
+    }
+
+    /// Picks up the puzzle once it's generated and tells the others; returns whether it just
+    /// arrived
+    fn poll_prepared(&mut self) -> bool {
+        let Some(prepared) = &mut self.prepared else {
+            return false;
+        };
+        if prepared.snapshot.is_some() {
+            return false;
+        }
+        let Ok(snapshot) = prepared.receiver.try_recv() else {
+            return false;
+        };
+        prepared.snapshot = Some(snapshot);
+        if let Some(session) = &mut self.session {
+            session.report_ready();
+        }
+        self.show_prepared();
+        true
+    }
+
+    /// Shows the prepared puzzle with a fresh clock, once the race has started and the puzzle
+    /// is ready
+    fn show_prepared(&mut self) {
+        let Some(prepared) = &mut self.prepared else {
+            return;
+        };
+        if !prepared.show_when_ready {
+            return;
+        }
+        let Some(mut snapshot) = prepared.snapshot.take() else {
+            return;
+        };
+        prepared.show_when_ready = false;
+        snapshot.timer_state = TimerState::default();
+        snapshot.source = PuzzleSource::Seeded;
+        self.game_action_emitter
+            .emit(GameActionEvent::LoadState(snapshot));
+    }
+
+    fn cancel_prepared(&mut self) {
+        if let Some(prepared) = self.prepared.take() {
+            prepared.cancelled.store(true, Ordering::Relaxed);
+        }
+    }
+
+    /// Drops out of the race, if in one, or stops waiting to join one
+    fn leave(&mut self) {
+        if let Some(connecting) = self.connecting.take() {
+            connecting.remove();
+        }
+        if let Some(poller) = self.poller.take() {
+            poller.remove();
+        }
+        self.cancel_prepared();
+        self.session = None;
+        self.on_race_puzzle = false;
+    }
+
+    fn status(session: &RaceSession) -> String {
+        let share_code = session
+            .share_code()
+            .map(|code| code.to_string())
+            .unwrap_or_default();
+        match (session.phase(), session.port()) {
+            (RacePhase::Lobby, Some(port)) => format!(
+                "Hosting at {}:{}. Others join at that address; start once everyone's in and ready.\nPuzzle: {}",
+                race::local_address(),
+                port,
+                share_code
+            ),
+            (RacePhase::Lobby, None) if session.share_code().is_none() => {
+                "Connecting…".to_string()
+            }
+            (RacePhase::Lobby, None) => format!(
+                "Waiting for the host to start the race.\nPuzzle: {}",
+                share_code
+            ),
+            (RacePhase::Racing, _) => format!("Race on! Puzzle: {}", share_code),
+            (RacePhase::Over, _) => "Race over".to_string(),
+        }
+    }
+
+    fn player_row(place: usize, player: &RacePlayer, is_self: bool, in_lobby: bool) -> Box {
+        let row = Box::builder()
+            .orientation(Orientation::Horizontal)
+            .spacing(10)
+            .build();
+        let mut name = player.name.clone();
+        if is_self {
+            name.push_str(" (you)");
+        }
+        if !player.connected {
+            name.push_str(" (left)");
+        }
+        let place = if player.result.is_some() {
+            format!("{}.", place)
+        } else {
+            String::new()
+        };
+        row.append(&Label::builder().label(place).width_chars(3).build());
+        row.append(
+            &Label::builder()
+                .label(name)
+                .halign(Align::Start)
+                .width_chars(12)
+                .build(),
+        );
+
+        let text = match player.result {
+            Some(result) => format!(
+                "{}, {} hints",
+                StatsDialog::format_duration(result.completion_time),
+                result.hints_used
+            ),
+            None if in_lobby && player.ready => "Ready".to_string(),
+            None if in_lobby => "Preparing the puzzle…".to_string(),
+            None => format!("{}%", player.progress),
+        };
+        let progress_bar = ProgressBar::builder()
+            .fraction(player.progress as f64 / 100.0)
+            .text(text)
+            .show_text(true)
+            .hexpand(true)
+            .valign(Align::Center)
+            .build();
+        row.append(&progress_bar);
+        row
+    }
+
+    fn refresh(&self) {
+        let Some(widgets) = &self.widgets else {
+            return;
+        };
+        let Some(session) = &self.session else {
+            let connecting = self.connecting.is_some();
+            widgets.host_button.set_sensitive(!connecting);
+            widgets.join_button.set_sensitive(!connecting);
+            widgets
+                .join_button
+                .set_label(if connecting { "Connecting…" } else { "Join" });
+            if connecting {
+                widgets.error_label.set_visible(false);
+            }
+            widgets.setup_box.set_visible(true);
+            widgets.race_box.set_visible(false);
+            return;
+        };
+        widgets.setup_box.set_visible(false);
+        widgets.race_box.set_visible(true);
+        widgets.status_label.set_label(&Self::status(session));
+
+        while let Some(child) = widgets.players_box.first_child() {
+            widgets.players_box.remove(&child);
+        }
+        let in_lobby = session.phase() == RacePhase::Lobby;
+        for (i, player) in session.standings().iter().enumerate() {
+            let is_self = session.player_id() == Some(player.id);
+            widgets
+                .players_box
+                .append(&Self::player_row(i + 1, player, is_self, in_lobby));
+        }
+
+        widgets
+            .start_button
+            .set_visible(session.is_host() && in_lobby);
+        widgets
+            .start_button
+            .set_sensitive(session.players().len() > 1 && session.everyone_ready());
+    }
+}
@@ -0,0 +1,375 @@
+use glib::{Propagation, SourceId};
+use gtk4::{
+    gdk, prelude::*, Align, ApplicationWindow, Box, Button, DropDown, EventControllerKey, Label,
+    Orientation, Scale, ScrolledWindow,
+};
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::time::Duration;
+
+use crate::destroyable::Destroyable;
+use crate::events::{Channel, EventEmitter};
+use crate::game::replay::{build_replay, ReplayFrame};
+use crate::game::settings::Settings;
+use crate::model::{GameStateEvent, GlobalEvent, InputEvent, MoveLog};
+
+use super::{LayoutManager, PuzzleGridUI, ResourceManager, StatsDialog};
+
+const SPEEDS: [(&str, f64); 5] = [
+    ("1×", 1.0),
+    ("2×", 2.0),
+    ("4×", 4.0),
+    ("8×", 8.0),
+    ("16×", 16.0),
+];
+const DEFAULT_SPEED_INDEX: u32 = 2;
+/// Playback never waits longer than this between moves, so long thinks don't stall the replay
+const MAX_STEP_DELAY: Duration = Duration::from_secs(2);
+const MIN_STEP_DELAY: Duration = Duration::from_millis(50);
+
+/// Opens the replay viewer for finished games; handed to the dialogs that list them
+#[derive(Clone)]
+pub struct ReplayLauncher {
+    window: Rc<ApplicationWindow>,
+    resource_manager: Rc<RefCell<ResourceManager>>,
+    settings: Rc<RefCell<Settings>>,
+}
+
+impl ReplayLauncher {
+    pub fn new(
+        window: &Rc<ApplicationWindow>,
+        resource_manager: &Rc<RefCell<ResourceManager>>,
+        settings: &Rc<RefCell<Settings>>,
+    ) -> Self {
+        Self {
+            window: Rc::clone(window),
+            resource_manager: Rc::clone(resource_manager),
+            settings: Rc::clone(settings),
+        }
+    }
+
+    pub fn show(&self, move_log: &MoveLog) {
+        ReplayViewer::show(
+            &self.window,
+            &self.resource_manager,
+            &self.settings.borrow(),
+            move_log,
+        );
+    }
+}
+
+/// Steps or plays through a finished game, move by move
+struct ReplayViewer {
+    frames: Vec<ReplayFrame>,
+    position: usize,
+    speed: f64,
+    playback: Option<SourceId>,
+    game_state_emitter: EventEmitter<GameStateEvent>,
+    puzzle_grid_ui: Rc<RefCell<PuzzleGridUI>>,
+    position_label: Label,
+    description_label: Label,
+    counts_label: Label,
+    scale: Scale,
+    play_button: Button,
+}
+
+impl ReplayViewer {
+    fn show(
+        window: &ApplicationWindow,
+        resource_manager: &Rc<RefCell<ResourceManager>>,
+        settings: &Settings,
+        move_log: &MoveLog,
+    ) {
+        let frames = build_replay(move_log);
+        let difficulty = move_log.initial_board.solution.difficulty;
+
+        // the grid gets its own channels; the replay must not touch the game in progress
+        let (game_state_emitter, game_state_observer) = Channel::<GameStateEvent>::new();
+        let (_, global_event_observer) = Channel::<GlobalEvent>::new();
+        let (input_event_emitter, _) = Channel::<InputEvent>::new();
+        let puzzle_grid_ui = PuzzleGridUI::new(
+            input_event_emitter,
+            game_state_observer,
+            global_event_observer,
+            resource_manager.borrow().get_image_set(),
+            LayoutManager::calculate_layout(difficulty, None),
+            settings,
+            None,
+        );
+
+        let vbox = Box::builder()
+            .orientation(Orientation::Vertical)
+            .spacing(10)
+            .margin_start(20)
+            .margin_end(20)
+            .margin_top(20)
+            .margin_bottom(20)
+            .build();
+        let modal = gtk4::Window::builder()
+            .title("Replay")
+            .modal(true)
+            .default_width(600)
+            .default_height(700)
+            .child(&vbox)
+            .transient_for(window)
+            .build();
+
+        let scrolled_window = ScrolledWindow::builder()
+            .vexpand(true)
+            .hexpand(true)
+            .child(&puzzle_grid_ui.borrow().overlay)
+            .build();
+        vbox.append(&scrolled_window);
+
+        let position_label = Label::new(None);
+        let description_label = Label::new(None);
+        let counts_label = Label::new(None);
+        vbox.append(&position_label);
+        vbox.append(&description_label);
+        vbox.append(&counts_label);
+
+        let scale = Scale::with_range(
+            Orientation::Horizontal,
+            0.0,
+            (frames.len() - 1).max(1) as f64,
+            1.0,
+        );
+        scale.set_draw_value(false);
+        scale.set_hexpand(true);
+        vbox.append(&scale);
+
+        let controls = Box::builder()
+            .orientation(Orientation::Horizontal)
+            .spacing(10)
+            .halign(Align::Center)
+            .build();
+        let first_button = Button::from_icon_name("media-skip-backward-symbolic");
+        first_button.set_tooltip_text(Some("First move"));
+        let back_button = Button::from_icon_name("media-seek-backward-symbolic");
+        back_button.set_tooltip_text(Some("Previous move"));
+        let play_button = Button::from_icon_name("media-playback-start-symbolic");
+        play_button.set_tooltip_text(Some("Play"));
+        let forward_button = Button::from_icon_name("media-seek-forward-symbolic");
+        forward_button.set_tooltip_text(Some("Next move"));
+        let last_button = Button::from_icon_name("media-skip-forward-symbolic");
+        last_button.set_tooltip_text(Some("Last move"));
+        let speed_dropdown = DropDown::from_strings(&SPEEDS.map(|(name, _)| name));
+        speed_dropdown.set_selected(DEFAULT_SPEED_INDEX);
+        speed_dropdown.set_tooltip_text(Some("Playback speed"));
+        let close_button = Button::with_label("Close");
+        for widget in [
+            first_button.upcast_ref::<gtk4::Widget>(),
+            back_button.upcast_ref(),
+            play_button.upcast_ref(),
+            forward_button.upcast_ref(),
+            last_button.upcast_ref(),
+            speed_dropdown.upcast_ref(),
+            close_button.upcast_ref(),
+        ] {
+            controls.append(widget);
+        }
+        vbox.append(&controls);
+
+        let last = frames.len() - 1;
+        let viewer = Rc::new(RefCell::new(Self {
+            frames,
+            position: 0,
+            speed: SPEEDS[DEFAULT_SPEED_INDEX as usize].1,
+            playback: None,
+            game_state_emitter,
+            puzzle_grid_ui,
+            position_label,
+            description_label,
+            counts_label,
+            scale: scale.clone(),
+            play_button: play_button.clone(),
+        }));
+        Self::seek(&viewer, 0);
+
+        let on_step = |button: &Button, step: fn(usize) -> usize| {
+            let viewer = Rc::downgrade(&viewer);
+            button.connect_clicked(move |_| {
+                if let Some(viewer) = viewer.upgrade() {
+                    Self::pause(&viewer);
+                    let position = step(viewer.borrow().position);
+                    Self::seek(&viewer, position.min(last));
+                }
+            });
+        };
+        on_step(&first_button, |_| 0);
+        on_step(&back_button, |position| position.saturating_sub(1));
+        on_step(&forward_button, |position| position + 1);
+        on_step(&last_button, |_| usize::MAX);
+
+        play_button.connect_clicked({
+            let viewer = Rc::downgrade(&viewer);
+            move |_| {
+                if let Some(viewer) = viewer.upgrade() {
+                    Self::toggle_playback(&viewer);
+                }
+            }
+        });
+
+        scale.connect_value_changed({
+            let viewer = Rc::downgrade(&viewer);
+            move |scale| {
+                let Some(viewer) = viewer.upgrade() else {
+                    return;
+                };
+                let position = scale.value().round() as usize;
+                if position != viewer.borrow().position {
+                    Self::pause(&viewer);
+                    Self::seek(&viewer, position);
+                }
+            }
+        });
+
+        speed_dropdown.connect_selected_notify({
+            let viewer = Rc::downgrade(&viewer);
+            move |dropdown| {
+                if let Some(viewer) = viewer.upgrade() {
+                    viewer.borrow_mut().speed = SPEEDS
+                        .get(dropdown.selected() as usize)
+                        .map_or(1.0, |(_, speed)| *speed);
+                }
+            }
+        });
+
+        close_button.connect_clicked({
+            let modal = modal.clone();
+            move |_| modal.close()
+        });
+
+        let key_controller = EventControllerKey::new();
+        key_controller.connect_key_pressed({
+            let modal = modal.clone();
+            let viewer = Rc::downgrade(&viewer);
+            move |_, keyval, _, _| {
+                let Some(viewer) = viewer.upgrade() else {
+                    return Propagation::Proceed;
+                };
+                let position = viewer.borrow().position;
+                match keyval {
+                    gdk::Key::Escape => modal.close(),
+                    gdk::Key::Left => {
+                        Self::pause(&viewer);
+                        Self::seek(&viewer, position.saturating_sub(1));
+                    }
+                    gdk::Key::Right => {
+                        Self::pause(&viewer);
+                        Self::seek(&viewer, (position + 1).min(last));
+                    }
+                    gdk::Key::space => Self::toggle_playback(&viewer),
+                    _ => return Propagation::Proceed,
+                }
+                Propagation::Stop
+            }
+        });
+        modal.add_controller(key_controller);
+
+        modal.connect_close_request(move |_| {
+            Self::pause(&viewer);
+            viewer.borrow().puzzle_grid_ui.borrow_mut().destroy();
+            Propagation::Proceed
+        });
+
+        modal.present();
+    }
+
+    fn seek(viewer: &Rc<RefCell<Self>>, position: usize) {
+        let scale = {
+            let mut viewer = viewer.borrow_mut();
+            viewer.position = position.min(viewer.frames.len() - 1);
+            viewer.render();
+            viewer.scale.clone()
+        };
+        // after the borrow ends, as this calls back into the value-changed handler
+        scale.set_value(viewer.borrow().position as f64);
+    }
+
+    fn render(&self) {
+        let frame = &self.frames[self.position];
+        self.game_state_emitter
+            .emit(GameStateEvent::GridUpdate(frame.board.as_ref().clone()));
+
+        self.position_label.set_text(&format!(
+            "Move {} of {} · {}",
+            self.position,
+            self.frames.len() - 1,
+            StatsDialog::format_duration(frame.elapsed())
+        ));
+        self.description_label.set_text(&frame.description);
+        if frame.is_hint() || frame.is_undo() {
+            self.description_label.add_css_class("replay-marker");
+        } else {
+            self.description_label.remove_css_class("replay-marker");
+        }
+        self.counts_label.set_text(&format!(
+            "Hints used: {} · Undos: {}",
+            frame.hints_used, frame.undos
+        ));
+    }
+
+    fn toggle_playback(viewer: &Rc<RefCell<Self>>) {
+        if viewer.borrow().playback.is_some() {
+            Self::pause(viewer);
+            return;
+        }
+        let at_end = {
+            let viewer = viewer.borrow();
+            viewer.position + 1 >= viewer.frames.len()
+        };
+        if at_end {
+            Self::seek(viewer, 0);
+        }
+        {
+            let viewer = viewer.borrow();
+            viewer
+                .play_button
+                .set_icon_name("media-playback-pause-symbolic");
+            viewer.play_button.set_tooltip_text(Some("Pause"));
+        }
+        Self::schedule_next(viewer);
+    }
+
+    fn pause(viewer: &Rc<RefCell<Self>>) {
+        let mut viewer = viewer.borrow_mut();
+        if let Some(playback) = viewer.playback.take() {
+            playback.remove();
+        }
+        viewer
+            .play_button
+            .set_icon_name("media-playback-start-symbolic");
+        viewer.play_button.set_tooltip_text(Some("Play"));
+    }
+
+    /// Waits as long as the player did before the next move, sped up, then shows it
+    fn schedule_next(viewer: &Rc<RefCell<Self>>) {
+        let delay = {
+            let viewer = viewer.borrow();
+            viewer.frames.get(viewer.position + 1).map(|next| {
+                let gap = next
+                    .elapsed()
+                    .saturating_sub(viewer.frames[viewer.position].elapsed());
+                gap.div_f64(viewer.speed)
+                    .clamp(MIN_STEP_DELAY, MAX_STEP_DELAY)
+            })
+        };
+        let Some(delay) = delay else {
+            Self::pause(viewer);
+            return;
+        };
+
+        let weak_viewer = Rc::downgrade(viewer);
+        let playback = glib::timeout_add_local_once(delay, move || {
+            if let Some(viewer) = weak_viewer.upgrade() {
+                // this source is done; it must not be removed again
+                viewer.borrow_mut().playback = None;
+                let position = viewer.borrow().position + 1;
+                Self::seek(&viewer, position);
+                Self::schedule_next(&viewer);
+            }
+        });
+        viewer.borrow_mut().playback = Some(playback);
+    }
+}
@@ -0,0 +1,309 @@
+use chrono::{Local, TimeZone};
+use glib::Propagation;
+use gtk4::{
+    gdk, prelude::*, Align, ApplicationWindow, Box, Button, Entry, EventControllerKey, Grid, Image,
+    Label, ListBox, Orientation, ProgressBar, ScrolledWindow, SelectionMode,
+};
+use std::rc::Rc;
+
+use crate::game::save_slots::{SaveSlotId, SaveSlotManager, SaveSlotSummary};
+use crate::model::GameStateSnapshot;
+
+use super::{ImageSet, StatsDialog};
+
+const THUMBNAIL_TILE_SIZE: i32 = 10;
+
+pub struct SaveSlotsDialog;
+
+impl SaveSlotsDialog {
+    fn dialog(window: &ApplicationWindow, title: &str, content_area: &Box) -> gtk4::Window {
+        let dialog = gtk4::Window::builder()
+            .title(title)
+            .transient_for(window)
+            .modal(true)
+            .child(content_area)
+            .default_width(400)
+            .build();
+
+        let key_controller = EventControllerKey::new();
+        key_controller.connect_key_pressed({
+            let dialog = dialog.clone();
+            move |_, keyval, _, _| {
+                if keyval == gdk::Key::Escape {
+                    dialog.close();
+                    return Propagation::Stop;
+                }
+                Propagation::Proceed
+            }
+        });
+        dialog.add_controller(key_controller);
+        dialog
+    }
+
+    fn content_area() -> Box {
+        Box::builder()
+            .orientation(Orientation::Vertical)
+            .spacing(10)
+            .margin_bottom(10)
+            .margin_top(10)
+            .margin_start(20)
+            .margin_end(20)
+            .build()
+    }
+
+    fn error_label() -> Label {
+        Label::builder()
+            .css_classes(["error"])
+            .halign(Align::Start)
+            .wrap(true)
+            .visible(false)
+            .build()
+    }
+
+    /// Asks for a name and saves `snapshot` under it
+    pub fn show_save_as(
+        window: &ApplicationWindow,
+        save_slots: &Rc<SaveSlotManager>,
+        snapshot: GameStateSnapshot,
+    ) {
+        let content_area = Self::content_area();
+        let dialog = Self::dialog(window, "Save As", &content_area);
+
+        content_area.append(
+            &Label::builder()
+                .label("Save name:")
+                .halign(Align::Start)
+                .build(),
+        );
+        let entry = Entry::builder()
+            .text(format!(
+                "{} {}",
+                snapshot.board.solution.difficulty.to_string(),
+                Local::now().format("%Y-%m-%d %H:%M")
+            ))
+            .build();
+        content_area.append(&entry);
+        let error_label = Self::error_label();
+        content_area.append(&error_label);
+
+        let button_box = Box::builder()
+            .orientation(Orientation::Horizontal)
+            .spacing(10)
+            .halign(Align::End)
+            .build();
+        let cancel_button = Button::with_label("Cancel");
+        let save_button = Button::with_label("Save");
+        button_box.append(&cancel_button);
+        button_box.append(&save_button);
+        content_area.append(&button_box);
+
+        cancel_button.connect_clicked({
+            let dialog = dialog.clone();
+            move |_| dialog.close()
+        });
+
+        let accept: Rc<dyn Fn()> = Rc::new({
+            let dialog = dialog.clone();
+            let entry = entry.clone();
+            let save_slots = Rc::clone(save_slots);
+            move || {
+                let name = entry.text();
+                let name = name.trim();
+                if name.is_empty() {
+                    error_label.set_label("Please enter a name");
+                    error_label.set_visible(true);
+                    return;
+                }
+                match save_slots.save_as(name, &snapshot) {
+                    Ok(_) => dialog.close(),
+                    Err(e) => {
+                        log::error!(target: "save_slots", "Failed to save game as {:?}: {}", name, e);
+                        error_label.set_label(&format!("Could not save: {}", e));
+                        error_label.set_visible(true);
+                    }
+                }
+            }
+        });
+        save_button.connect_clicked({
+            let accept = accept.clone();
+            move |_| accept()
+        });
+        entry.connect_activate(move |_| accept());
+
+        dialog.present();
+    }
+
+    /// Lists the saved games; `on_continue` is called with the slot the player picks
+    pub fn show_continue<F>(
+        window: &ApplicationWindow,
+        save_slots: &Rc<SaveSlotManager>,
+        image_set: &Rc<ImageSet>,
+        on_continue: F,
+    ) where
+        F: Fn(SaveSlotId) + 'static,
+    {
+        let content_area = Self::content_area();
+        let dialog = Self::dialog(window, "Continue", &content_area);
+        dialog.set_default_size(400, 500);
+
+        let summaries = save_slots.list();
+        let empty_label = Label::builder()
+            .label("No saved games")
+            .visible(summaries.is_empty())
+            .build();
+        content_area.append(&empty_label);
+
+        let too_new = save_slots.count_too_new();
+        let too_new_label = Label::builder()
+            .label(format!(
+                "{} saved {} from a newer version of Mind Hunt and can't be continued here",
+                too_new,
+                if too_new == 1 { "game is" } else { "games are" }
+            ))
+            .visible(too_new > 0)
+            .wrap(true)
+            .build();
+        content_area.append(&too_new_label);
+
+        let list_box = ListBox::builder()
+            .selection_mode(SelectionMode::None)
+            .build();
+        let scrolled_window = ScrolledWindow::builder()
+            .vexpand(true)
+            .hexpand(true)
+            .child(&list_box)
+            .build();
+        content_area.append(&scrolled_window);
+
+        let on_continue = Rc::new(on_continue);
+        for summary in summaries {
+            let row = Self::slot_row(&summary, image_set);
+
+            let buttons = Box::builder()
+                .orientation(Orientation::Vertical)
+                .spacing(5)
+                .valign(Align::Center)
+                .build();
+            let continue_button = Button::with_label("Continue");
+            let delete_button = Button::with_label("Delete");
+            buttons.append(&continue_button);
+            buttons.append(&delete_button);
+            row.append(&buttons);
+            list_box.append(&row);
+
+            continue_button.connect_clicked({
+                let dialog = dialog.clone();
+                let on_continue = Rc::clone(&on_continue);
+                let id = summary.id;
+                move |_| {
+                    dialog.close();
+                    on_continue(id);
+                }
+            });
+            delete_button.connect_clicked({
+                let list_box = list_box.clone();
+                let empty_label = empty_label.clone();
+                let save_slots = Rc::clone(save_slots);
+                let id = summary.id;
+                move |_| {
+                    if let Err(e) = save_slots.delete(id) {
+                        log::error!(target: "save_slots", "Failed to delete {:?}: {}", id, e);
+                        return;
+                    }
+                    if let Some(list_row) = row.parent() {
+                        list_box.remove(&list_row);
+                    }
+                    empty_label.set_visible(list_box.first_child().is_none());
+                }
+            });
+        }
+
+        let button_box = Box::builder()
+            .orientation(Orientation::Horizontal)
+            .halign(Align::End)
+            .build();
+        let close_button = Button::with_label("Close");
+        button_box.append(&close_button);
+        content_area.append(&button_box);
+        close_button.connect_clicked({
+            let dialog = dialog.clone();
+            move |_| dialog.close()
+        });
+
+        dialog.present();
+    }
+
+    fn slot_row(summary: &SaveSlotSummary, image_set: &ImageSet) -> Box {
+        let row = Box::builder()
+            .orientation(Orientation::Horizontal)
+            .spacing(10)
+            .margin_top(5)
+            .margin_bottom(5)
+            .build();
+        row.append(&Self::thumbnail(summary, image_set));
+
+        let details = Box::builder()
+            .orientation(Orientation::Vertical)
+            .spacing(2)
+            .hexpand(true)
+            .valign(Align::Center)
+            .build();
+        let name_label = Label::builder().halign(Align::Start).build();
+        name_label.set_markup(&format!(
+            "<b>{}</b>",
+            glib::markup_escape_text(&summary.name)
+        ));
+        details.append(&name_label);
+
+        let saved_at = Local
+            .timestamp_opt(summary.saved_at, 0)
+            .single()
+            .map(|dt| dt.format("%Y-%m-%d %H:%M").to_string())
+            .unwrap_or_else(|| "Unknown".to_string());
+        let kind = match summary.id {
+            SaveSlotId::Auto(_) => "Last played",
+            SaveSlotId::Named(_) => "Saved",
+        };
+        details.append(
+            &Label::builder()
+                .label(format!(
+                    "{} · {} · {} {}",
+                    summary.difficulty.to_string(),
+                    StatsDialog::format_duration(summary.elapsed),
+                    kind,
+                    saved_at
+                ))
+                .halign(Align::Start)
+                .build(),
+        );
+
+        let progress = ProgressBar::builder()
+            .fraction(summary.progress)
+            .show_text(true)
+            .text(format!("{:.0}% solved", summary.progress * 100.0))
+            .build();
+        details.append(&progress);
+        row.append(&details);
+        row
+    }
+
+    /// The placed tiles of the saved board, in miniature
+    fn thumbnail(summary: &SaveSlotSummary, image_set: &ImageSet) -> Grid {
+        let grid = Grid::builder()
+            .css_classes(["save-slot-thumbnail"])
+            .valign(Align::Center)
+            .build();
+        for (row, tiles) in summary.thumbnail.iter().enumerate() {
+            for (col, tile) in tiles.iter().enumerate() {
+                let image = Image::new();
+                image.set_pixel_size(THUMBNAIL_TILE_SIZE);
+                image.set_size_request(THUMBNAIL_TILE_SIZE, THUMBNAIL_TILE_SIZE);
+                if let Some(texture) = tile.and_then(|tile| image_set.get_solution_icon(&tile)) {
+                    image.set_paintable(Some(texture.as_ref()));
+                }
+                grid.attach(&image, col as i32, row as i32, 1, 1);
+            }
+        }
+        grid
+    }
+}
@@ -1,15 +1,17 @@
-use std::cell::Cell;
 use std::{cell::RefCell, rc::Rc};
 
 use glib::Propagation;
 use gtk4::gdk;
 use gtk4::EventControllerKey;
-use gtk4::{prelude::*, ApplicationWindow, Entry};
+use gtk4::{prelude::*, ApplicationWindow, Entry, Label};
 
 use crate::{
     destroyable::Destroyable,
     events::{EventEmitter, EventObserver, Unsubscriber},
-    model::{Difficulty, GameActionEvent, GameStateEvent},
+    model::{
+        is_supported_generator, Difficulty, DifficultyRating, GameActionEvent, GameStateEvent,
+        ShareCode, ShareCodeError, GENERATOR_VERSION,
+    },
 };
 
 pub struct SeedDialog {
@@ -18,6 +20,8 @@ pub struct SeedDialog {
     subscription_id: Option<Unsubscriber<GameStateEvent>>,
     current_seed: Option<u64>,
     current_difficulty: Difficulty,
+    current_generator_version: u32,
+    current_rating: Option<DifficultyRating>,
 }
 
 impl Destroyable for SeedDialog {
@@ -40,22 +44,42 @@ impl SeedDialog {
             subscription_id: None,
             current_seed: None,
             current_difficulty: Difficulty::Easy, // Default value, will be updated by observer
+            current_generator_version: GENERATOR_VERSION,
+            current_rating: None,
         }));
 
-        // Connect observer to track current seed and difficulty
+        // Connect observer to track current seed, difficulty and rating
         let dialog_clone = dialog.clone();
-        let subscription_id = game_state_observer.subscribe(move |event| {
-            if let GameStateEvent::GridUpdate(board) = event {
+        let subscription_id = game_state_observer.subscribe(move |event| match event {
+            GameStateEvent::GridUpdate(board) => {
                 let mut dialog = dialog_clone.borrow_mut();
                 dialog.current_seed = Some(board.solution.seed);
                 dialog.current_difficulty = board.solution.difficulty;
+                dialog.current_generator_version = board.solution.generator_version;
             }
+            GameStateEvent::DifficultyRatingChanged(rating) => {
+                dialog_clone.borrow_mut().current_rating = Some(rating.clone());
+            }
+            _ => (),
         });
         dialog.borrow_mut().subscription_id = Some(subscription_id);
 
         dialog
     }
 
+    /// Accepts either a share code or a bare seed, which keeps the current difficulty
+    fn parse_entry(
+        text: &str,
+        current_difficulty: Difficulty,
+    ) -> Result<ShareCode, ShareCodeError> {
+        ShareCode::parse(text).or_else(|err| {
+            text.trim()
+                .parse::<u64>()
+                .map(|seed| ShareCode::new(current_difficulty, seed))
+                .map_err(|_| err)
+        })
+    }
+
     pub fn show_seed(&self) {
         let content_area = gtk4::Box::builder()
             .orientation(gtk4::Orientation::Vertical)
@@ -74,11 +98,55 @@ impl SeedDialog {
             .default_width(300)
             .build();
 
+        // puzzles from unknown generators can't be regenerated, so there's nothing to share
+        let share_code = self
+            .current_seed
+            .filter(|_| is_supported_generator(self.current_generator_version))
+            .map(|seed| ShareCode {
+                difficulty: self.current_difficulty,
+                seed,
+                generator_version: self.current_generator_version,
+            });
+
+        let code_label = Label::builder()
+            .label("Share code or seed:")
+            .halign(gtk4::Align::Start)
+            .build();
+        content_area.append(&code_label);
+
         let entry = Entry::builder()
-            .text(self.current_seed.map_or("".to_string(), |s| s.to_string()))
+            .text(share_code.map_or("".to_string(), |code| code.to_string()))
+            .tooltip_text(
+                self.current_seed
+                    .map_or("".to_string(), |seed| format!("Seed: {}", seed)),
+            )
             .build();
         content_area.append(&entry);
 
+        let error_label = Label::builder()
+            .css_classes(["error"])
+            .halign(gtk4::Align::Start)
+            .wrap(true)
+            .visible(false)
+            .build();
+        content_area.append(&error_label);
+
+        if let Some(rating) = &self.current_rating {
+            let rating_label = Label::builder()
+                .label(format!("Difficulty rating: {}", rating.score))
+                .tooltip_text(format!(
+                    "{} steps, {} clues scanned, {} advanced deductions, {} naked set deductions, {} hidden set deductions",
+                    rating.steps,
+                    rating.clues_scanned,
+                    rating.converging_deductions,
+                    rating.naked_set_deductions,
+                    rating.hidden_set_deductions
+                ))
+                .halign(gtk4::Align::Start)
+                .build();
+            content_area.append(&rating_label);
+        }
+
         let button_box = gtk4::Box::builder()
             .orientation(gtk4::Orientation::Horizontal)
             .build();
@@ -96,27 +164,36 @@ impl SeedDialog {
             }
         });
 
-        let value_accepted = Rc::new(Cell::new(false));
-
-        ok_button.connect_clicked({
+        let accept: Rc<dyn Fn()> = Rc::new({
             let dialog = dialog.clone();
-            let value_accepted = value_accepted.clone();
-
-            move |_| {
-                value_accepted.set(true);
-                dialog.close();
+            let entry = entry.clone();
+            let game_action_emitter = self.game_action_emitter.clone();
+            let current_difficulty = self.current_difficulty;
+            move || match Self::parse_entry(entry.text().as_str(), current_difficulty) {
+                Ok(code) => {
+                    if Some(code) != share_code {
+                        game_action_emitter.emit(GameActionEvent::RegenerateGame(
+                            code.difficulty,
+                            code.seed,
+                            code.generator_version,
+                        ));
+                    }
+                    dialog.close();
+                }
+                Err(err) => {
+                    error_label.set_label(&err.to_string());
+                    error_label.set_visible(true);
+                }
             }
         });
 
-        entry.connect_activate({
-            let dialog = dialog.clone();
-            let value_accepted = value_accepted.clone();
-            move |_| {
-                value_accepted.set(true);
-                dialog.close();
-            }
+        ok_button.connect_clicked({
+            let accept = accept.clone();
+            move |_| accept()
         });
 
+        entry.connect_activate(move |_| accept());
+
         let key_controller = EventControllerKey::new();
         key_controller.connect_key_pressed({
             let dialog = dialog.clone();
@@ -128,23 +205,6 @@ impl SeedDialog {
                 Propagation::Proceed
             }
         });
-        dialog.connect_close_request({
-            let value_accepted = value_accepted.clone();
-            let game_action_emitter = self.game_action_emitter.clone();
-            let current_seed = self.current_seed;
-            let current_difficulty = self.current_difficulty;
-            move |_| {
-                if value_accepted.take() {
-                    if let Ok(new_seed) = entry.text().as_str().parse::<u64>() {
-                        if Some(new_seed) != current_seed {
-                            game_action_emitter
-                                .emit(GameActionEvent::NewGame(current_difficulty, Some(new_seed)));
-                        }
-                    }
-                }
-                return Propagation::Proceed;
-            }
-        });
         dialog.add_controller(key_controller);
         dialog.present();
     }
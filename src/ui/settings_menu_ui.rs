@@ -20,6 +20,7 @@ pub struct SettingsMenuUI {
     action_toggle_tooltips: SimpleAction,
     action_toggle_spotlight: SimpleAction,
     action_toggle_touch_controls: SimpleAction,
+    action_toggle_ghost: SimpleAction,
     game_state_subscription: Option<Unsubscriber<GameStateEvent>>,
     settings_ref: Rc<RefCell<Settings>>,
     global_event_emitter: EventEmitter<GlobalEvent>,
@@ -37,6 +38,7 @@ impl Destroyable for SettingsMenuUI {
             .remove_action(&self.action_toggle_spotlight.name());
         self.window
             .remove_action(&self.action_toggle_touch_controls.name());
+        self.window.remove_action(&self.action_toggle_ghost.name());
     }
 }
 
@@ -52,6 +54,7 @@ impl SettingsMenuUI {
             Some("Touch Screen Controls"),
             Some("win.toggle-touch-controls"),
         );
+        settings_menu.append(Some("Race Previous Solve"), Some("win.toggle-ghost"));
 
         if Settings::is_debug_mode() {
             settings_menu.append(Some("Show Clue X-Ray"), Some("win.toggle-spotlight"));
@@ -60,6 +63,7 @@ impl SettingsMenuUI {
         let action_toggle_tooltips: SimpleAction;
         let action_toggle_spotlight: SimpleAction;
         let action_toggle_touch_controls: SimpleAction;
+        let action_toggle_ghost: SimpleAction;
 
         {
             let settings = settings_ref.borrow();
@@ -80,6 +84,12 @@ impl SettingsMenuUI {
                 None,
                 &settings.touch_screen_controls.to_variant(),
             );
+
+            action_toggle_ghost = SimpleAction::new_stateful(
+                "toggle-ghost",
+                None,
+                &settings.ghost_enabled.to_variant(),
+            );
         }
 
         let settings_menu_ui = Rc::new(RefCell::new(Self {
@@ -88,6 +98,7 @@ impl SettingsMenuUI {
             action_toggle_tooltips,
             action_toggle_spotlight,
             action_toggle_touch_controls,
+            action_toggle_ghost,
             game_state_subscription: None,
             settings_ref: settings_ref,
             global_event_emitter: global_event_emitter.clone(),
@@ -156,6 +167,22 @@ impl SettingsMenuUI {
                 });
             window.add_action(&settings_menu_ui_ref.action_toggle_touch_controls);
         }
+
+        // Connect ghost action
+        {
+            let weak_settings_menu_ui = Weak::clone(&weak_settings_menu_ui);
+            settings_menu_ui_ref
+                .action_toggle_ghost
+                .connect_activate(move |action, _| {
+                    let current_state = action.state().unwrap().get::<bool>().unwrap();
+                    let new_state = !current_state;
+                    action.set_state(&new_state.to_variant());
+                    if let Some(settings_menu_ui) = weak_settings_menu_ui.upgrade() {
+                        settings_menu_ui.borrow_mut().set_ghost_enabled(new_state);
+                    }
+                });
+            window.add_action(&settings_menu_ui_ref.action_toggle_ghost);
+        }
     }
 
     fn set_tooltips_enabled(&mut self, enabled: bool) {
@@ -194,6 +221,18 @@ impl SettingsMenuUI {
             .emit(GlobalEvent::SettingsChanged(settings));
     }
 
+    fn set_ghost_enabled(&mut self, enabled: bool) {
+        let mut settings = self.settings_ref.borrow_mut();
+        settings.ghost_enabled = enabled;
+        if !settings.save().is_ok() {
+            log::error!("Failed to save settings");
+        }
+
+        let settings = settings.clone();
+        self.global_event_emitter
+            .emit(GlobalEvent::SettingsChanged(settings));
+    }
+
     pub fn get_menu(&self) -> &Menu {
         &self.settings_menu
     }
@@ -0,0 +1,183 @@
+use gtk4::{cairo, prelude::*, DrawingArea};
+
+use crate::game::stats_report::{solve_time_histogram, solve_time_trend};
+use crate::model::GameStats;
+
+use super::StatsDialog;
+
+const CHART_WIDTH: i32 = 300;
+const CHART_HEIGHT: i32 = 160;
+const MARGIN: f64 = 20.0;
+const FONT_SIZE: f64 = 10.0;
+const HISTOGRAM_BUCKETS: usize = 8;
+/// The trend line averages over this many games
+const TREND_WINDOW: usize = 5;
+/// Histogram bars and the trend line
+const ACCENT: (f64, f64, f64) = (0.25, 0.55, 0.9);
+
+/// Solve time charts for the statistics window, drawn from the game history
+pub struct StatsCharts;
+
+impl StatsCharts {
+    fn chart_area(tooltip: &str) -> DrawingArea {
+        let area = DrawingArea::builder()
+            .content_width(CHART_WIDTH)
+            .content_height(CHART_HEIGHT)
+            .hexpand(true)
+            .build();
+        area.set_tooltip_text(Some(tooltip));
+        area
+    }
+
+    /// The plot's left, top, right and bottom edges, leaving room for labels
+    fn plot_bounds(width: i32, height: i32) -> (f64, f64, f64, f64) {
+        (
+            MARGIN,
+            MARGIN,
+            width as f64 - MARGIN,
+            height as f64 - MARGIN,
+        )
+    }
+
+    fn set_foreground(area: &DrawingArea, cr: &cairo::Context, alpha: f64) {
+        let color = area.color();
+        cr.set_source_rgba(
+            color.red() as f64,
+            color.green() as f64,
+            color.blue() as f64,
+            color.alpha() as f64 * alpha,
+        );
+    }
+
+    fn draw_axes(area: &DrawingArea, cr: &cairo::Context, width: i32, height: i32) {
+        let (left, top, right, bottom) = Self::plot_bounds(width, height);
+        Self::set_foreground(area, cr, 0.6);
+        cr.set_line_width(1.0);
+        cr.move_to(left, top);
+        cr.line_to(left, bottom);
+        cr.line_to(right, bottom);
+        let _ = cr.stroke();
+    }
+
+    /// Draws `text` with its left edge at `x`, or its right edge when `align_right` is set
+    fn draw_label(cr: &cairo::Context, text: &str, x: f64, y: f64, align_right: bool) {
+        cr.set_font_size(FONT_SIZE);
+        let x = match cr.text_extents(text) {
+            Ok(extents) if align_right => x - extents.x_advance(),
+            _ => x,
+        };
+        cr.move_to(x, y);
+        let _ = cr.show_text(text);
+    }
+
+    /// How many games took how long
+    pub fn histogram(games: &[GameStats]) -> DrawingArea {
+        let buckets = solve_time_histogram(games, HISTOGRAM_BUCKETS);
+        let area = Self::chart_area("Number of games by solve time");
+
+        area.set_draw_func(move |area, cr, width, height| {
+            let (Some(first), Some(last)) = (buckets.first(), buckets.last()) else {
+                return;
+            };
+            let (left, top, right, bottom) = Self::plot_bounds(width, height);
+            Self::draw_axes(area, cr, width, height);
+
+            let max_count = buckets.iter().map(|b| b.count).max().unwrap_or(1).max(1);
+            let bar_width = (right - left) / buckets.len() as f64;
+            cr.set_source_rgb(ACCENT.0, ACCENT.1, ACCENT.2);
+            for (i, bucket) in buckets.iter().enumerate() {
+                let bar_height = (bottom - top) * bucket.count as f64 / max_count as f64;
+                cr.rectangle(
+                    left + i as f64 * bar_width + 1.0,
+                    bottom - bar_height,
+                    bar_width - 2.0,
+                    bar_height,
+                );
+            }
+            let _ = cr.fill();
+
+            Self::set_foreground(area, cr, 1.0);
+            Self::draw_label(cr, &max_count.to_string(), left + 4.0, top - 6.0, false);
+            let label_y = bottom + FONT_SIZE + 4.0;
+            Self::draw_label(
+                cr,
+                &StatsDialog::format_duration(first.start),
+                left,
+                label_y,
+                false,
+            );
+            Self::draw_label(
+                cr,
+                &StatsDialog::format_duration(last.end),
+                right,
+                label_y,
+                true,
+            );
+        });
+        area
+    }
+
+    /// Each game's solve time in the order played, with a running average
+    pub fn trend(games: &[GameStats]) -> DrawingArea {
+        let points = solve_time_trend(games, TREND_WINDOW);
+        let area = Self::chart_area(&format!(
+            "Solve times, oldest first, with the average of every {} games",
+            TREND_WINDOW
+        ));
+
+        area.set_draw_func(move |area, cr, width, height| {
+            let Some(slowest) = points.iter().map(|p| p.completion_time).max() else {
+                return;
+            };
+            let (left, top, right, bottom) = Self::plot_bounds(width, height);
+            Self::draw_axes(area, cr, width, height);
+
+            let max_secs = slowest.as_secs_f64().max(1.0);
+            let x = |i: usize| {
+                if points.len() > 1 {
+                    left + (right - left) * i as f64 / (points.len() - 1) as f64
+                } else {
+                    (left + right) / 2.0
+                }
+            };
+            let y = |secs: f64| bottom - (bottom - top) * secs / max_secs;
+
+            Self::set_foreground(area, cr, 0.5);
+            for (i, point) in points.iter().enumerate() {
+                cr.arc(
+                    x(i),
+                    y(point.completion_time.as_secs_f64()),
+                    2.0,
+                    0.0,
+                    std::f64::consts::TAU,
+                );
+                let _ = cr.fill();
+            }
+
+            cr.set_source_rgb(ACCENT.0, ACCENT.1, ACCENT.2);
+            cr.set_line_width(2.0);
+            for (i, point) in points.iter().enumerate() {
+                let (px, py) = (x(i), y(point.average.as_secs_f64()));
+                if i == 0 {
+                    cr.move_to(px, py);
+                } else {
+                    cr.line_to(px, py);
+                }
+            }
+            let _ = cr.stroke();
+
+            Self::set_foreground(area, cr, 1.0);
+            Self::draw_label(
+                cr,
+                &StatsDialog::format_duration(slowest),
+                left + 4.0,
+                top - 6.0,
+                false,
+            );
+            let label_y = bottom + FONT_SIZE + 4.0;
+            Self::draw_label(cr, "Oldest", left, label_y, false);
+            Self::draw_label(cr, "Latest", right, label_y, true);
+        });
+        area
+    }
+}
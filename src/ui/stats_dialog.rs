@@ -1,18 +1,46 @@
 use chrono::{Local, TimeZone};
 use glib::Propagation;
 use gtk4::{
-    gdk, prelude::*, Align, ApplicationWindow, Box, EventControllerKey, Grid, Label, Orientation,
-    Separator,
+    gdk, prelude::*, AlertDialog, Align, ApplicationWindow, Box, EventControllerKey, FileDialog,
+    FileFilter, Grid, Label, Orientation, Separator,
 };
+use std::rc::Rc;
 use std::time::Duration;
 
 use crate::game::stats_manager::StatsManager;
+use crate::game::stats_report;
+use crate::model::daily_puzzle;
 use crate::model::{Difficulty, GameStats};
 
+use super::stats_charts::StatsCharts;
+use super::ReplayLauncher;
+
+#[derive(Clone, Copy)]
+enum ExportFormat {
+    Csv,
+    Json,
+}
+
+impl ExportFormat {
+    fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Json => "json",
+        }
+    }
+
+    fn contents(&self, history: &[GameStats]) -> Result<String, serde_json::Error> {
+        match self {
+            ExportFormat::Csv => Ok(stats_report::to_csv(history)),
+            ExportFormat::Json => stats_report::to_json(history),
+        }
+    }
+}
+
 pub struct StatsDialog;
 
 impl StatsDialog {
-    fn format_duration(duration: Duration) -> String {
+    pub(crate) fn format_duration(duration: Duration) -> String {
         let total_secs = duration.as_secs();
         let hours = total_secs / 3600;
         let minutes = (total_secs % 3600) / 60;
@@ -29,6 +57,7 @@ impl StatsDialog {
         difficulty: Difficulty,
         this_game_stats: Option<&GameStats>,
         stats_manager: &StatsManager,
+        replay_launcher: &ReplayLauncher,
     ) -> Grid {
         let scores_grid = Grid::new();
         scores_grid.set_row_spacing(5);
@@ -36,7 +65,18 @@ impl StatsDialog {
         scores_grid.set_margin_start(10);
 
         // Add headers
-        let headers = ["Rank", "Time", "Hints", "Grid Size", "Difficulty", "Date"];
+        let headers = [
+            "Rank",
+            "Time",
+            "Hints",
+            "Mistakes",
+            "Undos",
+            "Grid Size",
+            "Difficulty",
+            "Rating",
+            "Date",
+            "",
+        ];
         for (i, header) in headers.iter().enumerate() {
             let label = Label::new(Some(header));
             label.set_markup(&format!("<b>{}</b>", header));
@@ -85,19 +125,39 @@ impl StatsDialog {
             }
             scores_grid.attach(&hints, 2, row_index, 1, 1);
 
+            for (column, count) in [(3, score.mistakes), (4, score.undos)] {
+                let label = Label::new(Some(&count.map_or("-".to_string(), |c| c.to_string())));
+                label.set_halign(Align::End);
+                if is_current_playthrough {
+                    label.add_css_class("highlight-score");
+                }
+                scores_grid.attach(&label, column, row_index, 1, 1);
+            }
+
             let size = Label::new(Some(&format!("{}x{}", score.grid_size, score.grid_size)));
             size.set_halign(Align::End);
             if is_current_playthrough {
                 size.add_css_class("highlight-score");
             }
-            scores_grid.attach(&size, 3, row_index, 1, 1);
+            scores_grid.attach(&size, 5, row_index, 1, 1);
 
             let difficulty = Label::new(Some(&format!("{:?}", score.difficulty)));
             difficulty.set_halign(Align::End);
             if is_current_playthrough {
                 difficulty.add_css_class("highlight-score");
             }
-            scores_grid.attach(&difficulty, 4, row_index, 1, 1);
+            scores_grid.attach(&difficulty, 6, row_index, 1, 1);
+
+            let rating = Label::new(Some(
+                &score
+                    .difficulty_rating
+                    .map_or("-".to_string(), |r| r.to_string()),
+            ));
+            rating.set_halign(Align::End);
+            if is_current_playthrough {
+                rating.add_css_class("highlight-score");
+            }
+            scores_grid.attach(&rating, 7, row_index, 1, 1);
 
             let date = Local
                 .timestamp_opt(score.timestamp, 0)
@@ -109,7 +169,17 @@ impl StatsDialog {
             if is_current_playthrough {
                 date_label.add_css_class("highlight-score");
             }
-            scores_grid.attach(&date_label, 5, row_index, 1, 1);
+            scores_grid.attach(&date_label, 8, row_index, 1, 1);
+
+            if let Some(move_log) = score.move_log {
+                let replay_button = gtk4::Button::from_icon_name("media-playback-start-symbolic");
+                replay_button.set_tooltip_text(Some("Replay this game"));
+                replay_button.connect_clicked({
+                    let replay_launcher = replay_launcher.clone();
+                    move |_| replay_launcher.show(&move_log)
+                });
+                scores_grid.attach(&replay_button, 9, row_index, 1, 1);
+            }
         }
 
         scores_grid
@@ -160,11 +230,96 @@ impl StatsDialog {
         stats_grid
     }
 
+    fn create_daily_stats_grid(stats_manager: &StatsManager) -> Grid {
+        let streak = stats_manager.get_daily_streak(daily_puzzle::today());
+        let stats_grid = Grid::new();
+        stats_grid.set_row_spacing(5);
+        stats_grid.set_column_spacing(10);
+        stats_grid.set_margin_start(10);
+
+        let rows = [
+            ("Current Streak:", format!("{} days", streak.current)),
+            ("Best Streak:", format!("{} days", streak.best)),
+            (
+                "Dailies Completed:",
+                stats_manager.get_daily_results().len().to_string(),
+            ),
+        ];
+        for (i, (name, value)) in rows.iter().enumerate() {
+            let name_label = Label::new(Some(*name));
+            name_label.set_halign(Align::Start);
+            stats_grid.attach(&name_label, 0, i as i32, 1, 1);
+            let value_label = Label::new(Some(value.as_str()));
+            value_label.set_halign(Align::End);
+            stats_grid.attach(&value_label, 1, i as i32, 1, 1);
+        }
+
+        stats_grid
+    }
+
+    /// A histogram and trend of the solve times at `difficulty`, if any games were played there
+    fn create_charts(stats_manager: &StatsManager, difficulty: Difficulty) -> Option<Box> {
+        let games: Vec<GameStats> = stats_manager
+            .get_history()
+            .iter()
+            .filter(|game| game.difficulty == difficulty)
+            .cloned()
+            .collect();
+        if games.is_empty() {
+            return None;
+        }
+
+        let charts = Box::builder()
+            .orientation(Orientation::Horizontal)
+            .spacing(20)
+            .build();
+        charts.append(&StatsCharts::histogram(&games));
+        charts.append(&StatsCharts::trend(&games));
+        Some(charts)
+    }
+
+    /// Asks where to save the full game history and writes it there
+    fn export_history(modal: &gtk4::Window, history: Rc<Vec<GameStats>>, format: ExportFormat) {
+        let filter = FileFilter::new();
+        filter.add_suffix(format.extension());
+        let dialog = FileDialog::builder()
+            .title("Export Statistics")
+            .modal(true)
+            .default_filter(&filter)
+            .initial_name(format!("mindhunt-stats.{}", format.extension()))
+            .build();
+
+        let parent = modal.clone();
+        dialog.save(
+            Some(modal),
+            None::<&gio::Cancellable>,
+            move |result| {
+                let Some(path) = result.ok().and_then(|file| file.path()) else {
+                    return;
+                };
+                let written = match format.contents(&history) {
+                    Ok(contents) => std::fs::write(&path, contents).map_err(|e| e.to_string()),
+                    Err(e) => Err(e.to_string()),
+                };
+                if let Err(err) = written {
+                    log::error!(target: "stats_dialog", "Failed to export stats to {:?}: {}", path, err);
+                    AlertDialog::builder()
+                        .message("Could not export statistics")
+                        .detail(err)
+                        .modal(true)
+                        .build()
+                        .show(Some(&parent));
+                }
+            },
+        );
+    }
+
     pub fn show<F>(
         window: &ApplicationWindow,
         difficulty: Difficulty,
         stats_manager: &StatsManager,
         this_game_stats: Option<&GameStats>,
+        replay_launcher: &ReplayLauncher,
         on_close: F,
     ) where
         F: Fn() + 'static,
@@ -192,7 +347,12 @@ impl StatsDialog {
         vbox.append(&high_scores_label);
 
         // Add high scores grid
-        let scores_grid = Self::create_high_scores_grid(difficulty, this_game_stats, stats_manager);
+        let scores_grid = Self::create_high_scores_grid(
+            difficulty,
+            this_game_stats,
+            stats_manager,
+            replay_launcher,
+        );
         vbox.append(&scores_grid);
 
         // Add separator
@@ -210,10 +370,59 @@ impl StatsDialog {
         let stats_grid = Self::create_global_stats_grid(stats_manager, difficulty);
         vbox.append(&stats_grid);
 
+        if let Some(charts) = Self::create_charts(stats_manager, difficulty) {
+            let separator = Separator::new(Orientation::Horizontal);
+            separator.set_margin_top(20);
+            separator.set_margin_bottom(20);
+            vbox.append(&separator);
+
+            let charts_label = Label::new(Some("Solve Times"));
+            charts_label.set_markup("<b>Solve Times</b>");
+            charts_label.set_margin_bottom(10);
+            vbox.append(&charts_label);
+            vbox.append(&charts);
+        }
+
+        if !stats_manager.get_daily_results().is_empty() {
+            let separator = Separator::new(Orientation::Horizontal);
+            separator.set_margin_top(20);
+            separator.set_margin_bottom(20);
+            vbox.append(&separator);
+
+            let daily_label = Label::new(Some("Daily Puzzles"));
+            daily_label.set_markup("<b>Daily Puzzles</b>");
+            daily_label.set_margin_bottom(10);
+            vbox.append(&daily_label);
+            vbox.append(&Self::create_daily_stats_grid(stats_manager));
+        }
+
         let button_box = gtk4::Box::builder()
             .orientation(Orientation::Horizontal)
             .halign(Align::End)
+            .spacing(10)
             .build();
+        if let Some(move_log) = this_game_stats.and_then(|stats| stats.move_log.clone()) {
+            let replay_button = gtk4::Button::builder().label("Replay This Game").build();
+            replay_button.connect_clicked({
+                let replay_launcher = replay_launcher.clone();
+                move |_| replay_launcher.show(&move_log)
+            });
+            button_box.append(&replay_button);
+        }
+        let history = Rc::new(stats_manager.get_history().to_vec());
+        for (label, format) in [
+            ("Export CSV…", ExportFormat::Csv),
+            ("Export JSON…", ExportFormat::Json),
+        ] {
+            let export_button = gtk4::Button::builder().label(label).build();
+            export_button.set_sensitive(!history.is_empty());
+            export_button.connect_clicked({
+                let modal = modal.clone();
+                let history = Rc::clone(&history);
+                move |_| Self::export_history(&modal, Rc::clone(&history), format)
+            });
+            button_box.append(&export_button);
+        }
         let close_button = gtk4::Button::builder().label("Close").build();
         button_box.append(&close_button);
 
@@ -15,13 +15,14 @@ use crate::model::{GameActionEvent, PuzzleCompletionState};
 use crate::ui::stats_dialog::StatsDialog;
 
 use super::audio_set::AudioSet;
-use super::NotQuiteRightDialog;
+use super::{NotQuiteRightDialog, ReplayLauncher};
 
 pub struct SubmitUI {
     subscription_id: Option<Unsubscriber<GameStateEvent>>,
     stats_manager: Rc<RefCell<StatsManager>>,
     audio_set: Rc<AudioSet>,
     window: Rc<ApplicationWindow>,
+    replay_launcher: ReplayLauncher,
     game_action_emitter: EventEmitter<GameActionEvent>,
     submit_dialog: Rc<RefCell<CompletionDialog>>,
 }
@@ -41,6 +42,7 @@ impl SubmitUI {
         stats_manager: &Rc<RefCell<StatsManager>>,
         audio_set: &Rc<AudioSet>,
         window: &Rc<ApplicationWindow>,
+        replay_launcher: &ReplayLauncher,
     ) -> Rc<RefCell<Self>> {
         // Create submit button
         let submit_button = Rc::new(Button::with_label("Submit"));
@@ -70,6 +72,7 @@ impl SubmitUI {
             stats_manager: Rc::clone(stats_manager),
             audio_set: Rc::clone(audio_set),
             window: Rc::clone(window),
+            replay_launcher: replay_launcher.clone(),
             game_action_emitter: game_action_emitter,
             submit_dialog,
         }));
@@ -103,6 +106,7 @@ impl SubmitUI {
                     difficulty,
                     &stats_manager,
                     Some(stats),
+                    &self.replay_launcher,
                     move || {
                         game_action_emitter.emit(GameActionEvent::NewGame(difficulty, None));
                     },
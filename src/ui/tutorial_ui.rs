@@ -13,7 +13,7 @@ use crate::{
     helpers::Capitalize,
     model::{
         ClueWithAddress, Deduction, DeductionKind, Difficulty, Dimensions, GameActionEvent,
-        GameBoard, GameStateEvent, GlobalEvent, LayoutConfiguration,
+        GameBoard, GameStateEvent, GlobalEvent, LayoutConfiguration, NakedSet, Tile,
     },
     solver::{
         clue_completion_evaluator::is_clue_fully_completed, deduce_clue, simplify_deductions,
@@ -65,6 +65,7 @@ pub struct TutorialUI {
     settings: Settings,
     current_board: Option<GameBoard>,
     current_clue: Option<ClueWithAddress>,
+    current_naked_set: Option<NakedSet>,
     layout: Dimensions,
 }
 
@@ -130,6 +131,7 @@ impl TutorialUI {
             settings: settings.clone(),
             current_board: None,
             current_clue: None,
+            current_naked_set: None,
             layout: layout.tutorial.clone(),
         }));
 
@@ -219,8 +221,15 @@ impl TutorialUI {
                 }
                 _ => {}
             },
+            GameStateEvent::NakedSetHintHighlight(naked_set) => {
+                self.current_naked_set = Some(naked_set.clone());
+                if self.current_step == TutorialStep::PlayToEnd {
+                    self.sync_tutorial_text();
+                }
+            }
             GameStateEvent::GridUpdate(board) => {
                 self.current_board = Some(board.clone());
+                self.current_naked_set = None;
                 match &self.current_step {
                     TutorialStep::HintUsagePhase3(cwa, deduction) => {
                         let task_completed = if deduction.is_positive() {
@@ -269,7 +278,9 @@ impl TutorialUI {
 
     fn handle_game_action_event(&mut self, event: &GameActionEvent) {
         match event {
-            GameActionEvent::NewGame(difficulty, _) => {
+            GameActionEvent::NewGame(difficulty, _)
+            | GameActionEvent::RegenerateGame(difficulty, _, _)
+            | GameActionEvent::NewDailyGame(difficulty, _) => {
                 if *difficulty == Difficulty::Tutorial {
                     self.reset_tutorial();
                 } else {
@@ -453,6 +464,8 @@ You've completed the tutorial! You can try an easy puzzle by selecting <tt>'Easy
 
 Or, press <tt>Ctrl+N</tt> to restart this tutorial."
                     .to_string();
+            } else if let Some(naked_set) = &self.current_naked_set {
+                return Self::naked_set_template(naked_set);
             } else if let Some(selection) = &self.current_clue {
                 let selected_clue_marked_completed = board.is_clue_completed(&selection.address());
                 let deductions = simplify_deductions(
@@ -509,4 +522,33 @@ This clue is fully encoded in the board. Mark it as completed by pressing <tt>'C
             return "Weird".to_string();
         }
     }
+
+    fn naked_set_template(naked_set: &NakedSet) -> String {
+        let columns = naked_set
+            .columns
+            .iter()
+            .map(|column| format!("<big><tt>{}</tt></big>", column + 1))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let tiles = naked_set
+            .variants
+            .iter()
+            .map(|variant| {
+                format!(
+                    "{{tile:{}}}",
+                    Tile::new(naked_set.row, *variant).to_string()
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(" ");
+        format!(
+            "<big>Naked set</big>:\n\nThe cells in columns {} of row {} can only hold {}.\n\n\
+Since there are exactly as many of these tiles as there are cells, each of those cells must take \
+one of them, so {} <b>cannot be</b> anywhere else in the row.\n\n",
+            columns,
+            naked_set.row + 1,
+            tiles,
+            tiles
+        )
+    }
 }
@@ -1,10 +1,15 @@
 use crate::destroyable::Destroyable;
 use crate::events::{Channel, EventEmitter};
 use crate::game::game_state::GameState;
+use crate::game::profiles::ProfileManager;
+use crate::game::save_slots::{SaveSlotId, SaveSlotManager};
 use crate::game::settings::Settings;
 use crate::game::stats_manager::StatsManager;
+use crate::model::daily_puzzle;
+use crate::model::puzzle_file::{self, PUZZLE_FILE_EXTENSION};
+use crate::model::save_file::{LoadOutcome, SaveFileError};
 use crate::model::{
-    game_state_snapshot, Difficulty, GameActionEvent, GameStateEvent, GlobalEvent, InputEvent,
+    Difficulty, GameActionEvent, GameStateEvent, GameStateSnapshot, GlobalEvent, InputEvent,
 };
 use crate::ui::input_translator::InputTranslator;
 use crate::ui::seed_dialog::SeedDialog;
@@ -18,8 +23,9 @@ use gio::{Menu, SimpleAction};
 use glib::timeout_add_local_once;
 use gtk4::gdk::{Display, Monitor};
 use gtk4::{
-    prelude::*, AboutDialog, Application, ApplicationWindow, Button, CssProvider, HeaderBar, Label,
-    License, MenuButton, Orientation, STYLE_PROVIDER_PRIORITY_APPLICATION,
+    prelude::*, AboutDialog, AlertDialog, Application, ApplicationWindow, Button, CssProvider,
+    FileDialog, FileFilter, HeaderBar, Label, License, MenuButton, Orientation,
+    STYLE_PROVIDER_PRIORITY_APPLICATION,
 };
 use std::cell::RefCell;
 use std::env;
@@ -31,8 +37,12 @@ use super::clue_panels_ui::CluePanelsUI;
 use super::game_info_ui::GameInfoUI;
 use super::history_controls_ui::HistoryControlsUI;
 use super::layout_manager::{ClueStats, LayoutManager};
+use super::profile_ui::ProfileUI;
 use super::puzzle_grid_ui::PuzzleGridUI;
+use super::race_ui::RaceUI;
+use super::replay_dialog::ReplayLauncher;
 use super::resource_manager::ResourceManager;
+use super::save_slots_dialog::SaveSlotsDialog;
 use super::tutorial_ui::TutorialUI;
 
 const APP_VERSION: &str = env!("APP_VERSION");
@@ -86,20 +96,92 @@ fn hint_button_handler(
     }
 }
 
+fn puzzle_file_dialog(title: &str) -> FileDialog {
+    let filter = FileFilter::new();
+    filter.set_name(Some("Mind Hunt puzzles"));
+    filter.add_suffix(PUZZLE_FILE_EXTENSION);
+    FileDialog::builder()
+        .title(title)
+        .modal(true)
+        .default_filter(&filter)
+        .build()
+}
+
+/// Keeps the game in play in the automatic slot of its difficulty
+fn stash_current_game(game_state: &Rc<RefCell<GameState>>, save_slots: &SaveSlotManager) {
+    if let Err(e) = save_slots.save_auto(&game_state.borrow().get_game_save_state()) {
+        log::error!(target: "window", "Failed to save game state: {}", e);
+    }
+}
+
+fn show_error(window: &ApplicationWindow, message: &str, detail: &str) {
+    AlertDialog::builder()
+        .message(message)
+        .detail(detail)
+        .modal(true)
+        .build()
+        .show(Some(window));
+}
+
+/// The saved game in `outcome`, telling the player when it had to be restored from a backup or
+/// couldn't be read at all, rather than quietly starting over
+fn report_load_outcome(
+    window: &ApplicationWindow,
+    outcome: LoadOutcome<GameStateSnapshot>,
+) -> Option<GameStateSnapshot> {
+    match outcome {
+        LoadOutcome::Restored(save_state, err) => {
+            show_error(
+                window,
+                "Saved game restored from a backup",
+                &format!(
+                    "The saved game could not be read ({}), so its most recent backup was loaded. The last few moves may be missing.",
+                    err
+                ),
+            );
+            Some(save_state)
+        }
+        LoadOutcome::Unreadable(err) => {
+            show_error(
+                window,
+                "Saved game could not be read",
+                &format!(
+                    "{}\n\nA new puzzle was started instead. The unreadable save was kept alongside the other saves with an \".unreadable\" extension.",
+                    err
+                ),
+            );
+            None
+        }
+        LoadOutcome::TooNew(version) => {
+            show_error(
+                window,
+                "Saved game is from a newer version",
+                &format!(
+                    "The saved game was made by a newer version of Mind Hunt (format {}), which is needed to continue it. A new puzzle was started instead, and it won't be saved over the newer game.",
+                    version
+                ),
+            );
+            None
+        }
+        outcome => outcome.value(),
+    }
+}
+
 pub fn build_ui(app: &Application) {
     let (game_action_emitter, game_action_observer) = Channel::<GameActionEvent>::new();
     let (game_state_emitter, game_state_observer) = Channel::<GameStateEvent>::new();
     let (global_event_emitter, global_event_observer) = Channel::<GlobalEvent>::new();
     let (input_event_emitter, input_event_observer) = Channel::<InputEvent>::new();
 
-    let settings = Rc::new(RefCell::new(Settings::load()));
-    let saved_game_state = game_state_snapshot::load_game_state_snapshot();
-    if let Some(save_state) = &saved_game_state {
-        log::info!(target: "window", "Loaded saved game state");
-        // if these disagree, then bad things happen.
-        settings.borrow_mut().difficulty = save_state.board.solution.difficulty;
-    } else {
+    let profiles = Rc::new(RefCell::new(ProfileManager::load()));
+    let data_dir = profiles.borrow().current_dir();
+    let settings = Rc::new(RefCell::new(Settings::load(&data_dir)));
+    let save_slots = Rc::new(SaveSlotManager::new(data_dir.clone()));
+    let saved_game_state = save_slots.load(SaveSlotId::Auto(settings.borrow().difficulty));
+    if matches!(saved_game_state, LoadOutcome::Missing) {
         log::info!(target: "window", "No saved game state found");
+    } else {
+        log::info!(target: "window", "Loaded saved game state");
     }
 
     let resource_manager =
@@ -166,6 +248,7 @@ pub fn build_ui(app: &Application) {
     app.set_accels_for_action("win.new-game", &["<Control>n"]);
     app.set_accels_for_action("win.pause", &["space"]);
     app.set_accels_for_action("win.restart", &["<Control>r"]);
+    app.set_accels_for_action("win.save-as", &["<Control>s"]);
 
     // Create menu model for hamburger menu
     let menu = Menu::new();
@@ -179,9 +262,20 @@ pub fn build_ui(app: &Application) {
 
     // Add all menu items
     menu.append(Some("New Game"), Some("win.new-game"));
+    menu.append(Some("Daily Puzzle"), Some("win.daily-puzzle"));
+    menu.append(Some("Race…"), Some("win.race"));
     menu.append(Some("Restart"), Some("win.restart"));
+    menu.append(Some("Continue…"), Some("win.continue"));
+    menu.append(Some("Save As…"), Some("win.save-as"));
     menu.append(Some("Statistics"), Some("win.statistics"));
+    menu.append(Some("Import Statistics…"), Some("win.import-stats"));
+    menu.append(
+        Some("Import Statistics Folder…"),
+        Some("win.import-stats-folder"),
+    );
     menu.append(Some("Seed"), Some("win.seed"));
+    menu.append(Some("Open Puzzle…"), Some("win.open-puzzle"));
+    menu.append(Some("Export Puzzle…"), Some("win.export-puzzle"));
     menu.append_submenu(Some("Settings"), settings_menu_ui.borrow().get_menu());
     menu.append(Some("About"), Some("win.about"));
 
@@ -218,18 +312,25 @@ pub fn build_ui(app: &Application) {
     let current_difficulty = settings.borrow().difficulty;
     difficulty_selector.set_selected(current_difficulty.index() as u32);
 
-    // Handle difficulty changes
-    let settings_ref = Rc::clone(&settings);
-    let game_action_emitter_new_game = game_action_emitter.clone();
-    difficulty_selector.connect_selected_notify(move |selector| {
-        let new_difficulty = Difficulty::from_index(selector.selected() as usize);
-        settings_ref.borrow_mut().difficulty = new_difficulty;
-        let _ = settings_ref.borrow().save();
-        game_action_emitter_new_game.emit(GameActionEvent::NewGame(new_difficulty, None));
-    });
-
     header_bar.pack_start(&difficulty_box);
 
+    // Keep the selector in step when a shared or opened puzzle has another difficulty
+    let difficulty_sync_subscription = game_state_observer.subscribe({
+        let settings = Rc::clone(&settings);
+        let difficulty_selector = difficulty_selector.clone();
+        move |event| {
+            if let GameStateEvent::ClueSetUpdate(_, difficulty) = event {
+                if settings.borrow().difficulty != *difficulty {
+                    settings.borrow_mut().difficulty = *difficulty;
+                    if let Err(e) = settings.borrow().save() {
+                        log::error!(target: "window", "Failed to save settings: {}", e);
+                    }
+                    difficulty_selector.set_selected(difficulty.index() as u32);
+                }
+            }
+        }
+    });
+
     let history_controls_ui = HistoryControlsUI::new(game_state_observer.clone());
 
     let game_info_ui = GameInfoUI::new(
@@ -247,6 +348,8 @@ pub fn build_ui(app: &Application) {
     let default_layout =
         LayoutManager::calculate_layout(settings.borrow().difficulty, Some(ClueStats::default()));
 
+    let stats_manager = Rc::new(RefCell::new(StatsManager::new(data_dir)));
+
     // Create puzzle grid and clue set UI first
     let puzzle_grid_ui = PuzzleGridUI::new(
         input_event_emitter.clone(),
@@ -255,6 +358,7 @@ pub fn build_ui(app: &Application) {
         image_set.clone(),
         default_layout.clone(),
         &settings.borrow(),
+        Some(&stats_manager),
     );
 
     let clue_set_ui = CluePanelsUI::new(
@@ -274,8 +378,36 @@ pub fn build_ui(app: &Application) {
         settings.borrow().clone(),
     );
 
+    // Handle difficulty changes
+    let settings_ref = Rc::clone(&settings);
+    let game_action_emitter_new_game = game_action_emitter.clone();
+    difficulty_selector.connect_selected_notify({
+        let window = window.clone();
+        let game_state = Rc::clone(&game_state);
+        let save_slots = Rc::clone(&save_slots);
+        move |selector| {
+            let new_difficulty = Difficulty::from_index(selector.selected() as usize);
+            if new_difficulty == settings_ref.borrow().difficulty {
+                // the selector was synced to a loaded puzzle
+                return;
+            }
+            settings_ref.borrow_mut().difficulty = new_difficulty;
+            if let Err(e) = settings_ref.borrow().save() {
+                log::error!(target: "window", "Failed to save settings: {}", e);
+            }
+            // resume the puzzle left at the new difficulty, if any
+            stash_current_game(&game_state, &save_slots);
+            let saved_game_state =
+                report_load_outcome(&window, save_slots.load(SaveSlotId::Auto(new_difficulty)));
+            game_action_emitter_new_game.emit(match saved_game_state {
+                Some(save_state) => GameActionEvent::LoadState(save_state),
+                None => GameActionEvent::NewGame(new_difficulty, None),
+            });
+        }
+    });
+
     // Remove the old button_box since controls are now in header
-    let stats_manager = Rc::new(RefCell::new(StatsManager::new()));
+    let replay_launcher = ReplayLauncher::new(&window, &resource_manager, &settings);
 
     let submit_ui = SubmitUI::new(
         game_state_observer.clone(),
@@ -283,6 +415,7 @@ pub fn build_ui(app: &Application) {
         &stats_manager,
         &audio_set,
         &window,
+        &replay_launcher,
     );
 
     // Create left side box for timer and hints
@@ -324,9 +457,21 @@ pub fn build_ui(app: &Application) {
         .menu_model(&menu)
         .build();
 
+    // Switching profiles reopens the game in a new window; closing this one stashes the game
+    // into the profile being left
+    let profile_button = ProfileUI::new(&window, &profiles, {
+        let app = app.clone();
+        let window = window.clone();
+        move || {
+            build_ui(&app);
+            window.close();
+        }
+    });
+
     // Pack the controls on the right
     header_bar.pack_end(&menu_button); // Hamburger menu goes last
     header_bar.pack_end(&right_box); // Controls go before hamburger menu
+    header_bar.pack_end(&profile_button);
 
     window.set_titlebar(Some(&header_bar));
 
@@ -362,7 +507,7 @@ pub fn build_ui(app: &Application) {
     let puzzle_background = gtk4::Frame::builder()
         .name("puzzle-mat-board")
         .css_classes(["puzzle-mat-board"])
-        .child(&puzzle_grid_ui.borrow().grid)
+        .child(&puzzle_grid_ui.borrow().overlay)
         .build();
 
     // Instantiate TutorialUI
@@ -399,6 +544,7 @@ pub fn build_ui(app: &Application) {
 
     top_level_box.append(game_box.as_ref());
     top_level_box.append(pause_screen.as_ref());
+    top_level_box.append(&game_info_ui.borrow().generation_screen);
 
     scrolled_window.set_child(Some(&top_level_box));
     // window.set_child(Some(&top_level_box));
@@ -433,6 +579,21 @@ pub fn build_ui(app: &Application) {
     });
     window.add_action(&action_new_game);
 
+    // Daily puzzle: same seed for everyone on a given date and difficulty
+    let action_daily_puzzle = SimpleAction::new("daily-puzzle", None);
+    action_daily_puzzle.connect_activate({
+        let game_action_emitter = game_action_emitter.clone();
+        let settings = Rc::clone(&settings);
+        move |_, _| {
+            let difficulty = settings.borrow().difficulty;
+            game_action_emitter.emit(GameActionEvent::NewDailyGame(
+                difficulty,
+                daily_puzzle::today(),
+            ));
+        }
+    });
+    window.add_action(&action_daily_puzzle);
+
     let action_statistics = SimpleAction::new("statistics", None);
     let stats_manager_stats = Rc::clone(&stats_manager);
 
@@ -445,6 +606,7 @@ pub fn build_ui(app: &Application) {
                 settings.borrow().difficulty,
                 &stats_manager_stats.borrow_mut(),
                 None,
+                &replay_launcher,
                 || {},
             );
         }
@@ -452,6 +614,70 @@ pub fn build_ui(app: &Application) {
 
     window.add_action(&action_statistics);
 
+    // another machine's stats, either exported or straight from its (synced) data folder
+    let import_stats = {
+        let window = window.clone();
+        let stats_manager = Rc::clone(&stats_manager);
+        move |result: Result<gio::File, glib::Error>| {
+            let Some(path) = result.ok().and_then(|file| file.path()) else {
+                return;
+            };
+            match stats_manager.borrow_mut().import(&path) {
+                Ok(summary) => AlertDialog::builder()
+                    .message("Statistics imported")
+                    .detail(format!(
+                        "{} new games added; {} were already recorded.",
+                        summary.added, summary.already_recorded
+                    ))
+                    .modal(true)
+                    .build()
+                    .show(Some(window.as_ref())),
+                Err(err) => {
+                    log::error!(target: "window", "Failed to import stats from {:?}: {}", path, err);
+                    show_error(&window, "Could not import statistics", &err.to_string());
+                }
+            }
+        }
+    };
+    let action_import_stats = SimpleAction::new("import-stats", None);
+    action_import_stats.connect_activate({
+        let window = window.clone();
+        let import_stats = import_stats.clone();
+        move |_, _| {
+            let filter = FileFilter::new();
+            filter.set_name(Some("Exported statistics"));
+            filter.add_suffix("json");
+            FileDialog::builder()
+                .title("Import Statistics")
+                .modal(true)
+                .default_filter(&filter)
+                .build()
+                .open(
+                    Some(window.as_ref()),
+                    None::<&gio::Cancellable>,
+                    import_stats.clone(),
+                );
+        }
+    });
+    window.add_action(&action_import_stats);
+
+    let action_import_stats_folder = SimpleAction::new("import-stats-folder", None);
+    action_import_stats_folder.connect_activate({
+        let window = window.clone();
+        move |_, _| {
+            FileDialog::builder()
+                .title("Import Statistics Folder")
+                .modal(true)
+                .build()
+                .select_folder(
+                    Some(window.as_ref()),
+                    None::<&gio::Cancellable>,
+                    import_stats.clone(),
+                );
+        }
+    });
+    window.add_action(&action_import_stats_folder);
+
     let action_about = SimpleAction::new("about", None);
     action_about.connect_activate(move |_, _| {
         let dialog = AboutDialog::builder()
@@ -472,6 +698,19 @@ pub fn build_ui(app: &Application) {
         game_state_observer.clone(),
     );
 
+    let race_ui = RaceUI::new(
+        &window,
+        game_action_emitter.clone(),
+        game_state_observer.clone(),
+        &profiles.borrow().current().name,
+    );
+    let action_race = SimpleAction::new("race", None);
+    action_race.connect_activate({
+        let race_ui = race_ui.clone();
+        move |_, _| race_ui.borrow_mut().show()
+    });
+    window.add_action(&action_race);
+
     // Initialize game controls
     let game_controls = TopLevelInputEventMonitor::new(
         window.clone(),
@@ -482,7 +721,14 @@ pub fn build_ui(app: &Application) {
     );
 
     // Initialize game with saved difficulty
-    match saved_game_state {
+    if let Some(err) = save_slots.take_legacy_save_error() {
+        let outcome = match err {
+            SaveFileError::UnsupportedVersion(version) => LoadOutcome::TooNew(version),
+            err => LoadOutcome::Unreadable(err),
+        };
+        report_load_outcome(&window, outcome);
+    }
+    match report_load_outcome(&window, saved_game_state) {
         Some(save_state) => {
             game_action_emitter.emit(GameActionEvent::LoadState(save_state));
         }
@@ -511,6 +757,112 @@ pub fn build_ui(app: &Application) {
     });
     window.add_action(&action_restart);
 
+    // Add save slot actions
+    let action_save_as = SimpleAction::new("save-as", None);
+    action_save_as.connect_activate({
+        let window = window.clone();
+        let game_state = Rc::clone(&game_state);
+        let save_slots = Rc::clone(&save_slots);
+        move |_, _| {
+            let snapshot = game_state.borrow().get_game_save_state();
+            SaveSlotsDialog::show_save_as(&window, &save_slots, snapshot);
+        }
+    });
+    window.add_action(&action_save_as);
+
+    let action_continue = SimpleAction::new("continue", None);
+    action_continue.connect_activate({
+        let window = window.clone();
+        let game_state = Rc::clone(&game_state);
+        let save_slots = Rc::clone(&save_slots);
+        let game_action_emitter = game_action_emitter.clone();
+        let resource_manager = Rc::clone(&resource_manager);
+        move |_, _| {
+            let window_ref = window.clone();
+            let game_state = Rc::clone(&game_state);
+            let save_slots_ref = Rc::clone(&save_slots);
+            let game_action_emitter = game_action_emitter.clone();
+            let image_set = resource_manager.borrow().get_image_set();
+            SaveSlotsDialog::show_continue(&window, &save_slots, &image_set, move |id| {
+                if let Some(save_state) = report_load_outcome(&window_ref, save_slots_ref.load(id))
+                {
+                    stash_current_game(&game_state, &save_slots_ref);
+                    game_action_emitter.emit(GameActionEvent::LoadState(save_state));
+                }
+            });
+        }
+    });
+    window.add_action(&action_continue);
+
+    // Add puzzle file actions
+    let action_open_puzzle = SimpleAction::new("open-puzzle", None);
+    action_open_puzzle.connect_activate({
+        let window = window.clone();
+        let game_action_emitter = game_action_emitter.clone();
+        move |_, _| {
+            let window = window.clone();
+            let game_action_emitter = game_action_emitter.clone();
+            puzzle_file_dialog("Open Puzzle").open(
+                Some(window.as_ref()),
+                None::<&gio::Cancellable>,
+                move |result| {
+                    let Some(path) = result.ok().and_then(|file| file.path()) else {
+                        return;
+                    };
+                    match puzzle_file::import_puzzle(&path) {
+                        Ok(snapshot) if !snapshot.has_unique_solution() => {
+                            log::error!(target: "window", "Puzzle {:?} has no unique solution", path);
+                            show_error(
+                                &window,
+                                "Could not open puzzle",
+                                "Its clues do not lead to a single solution.",
+                            );
+                        }
+                        Ok(snapshot) => {
+                            game_action_emitter.emit(GameActionEvent::LoadState(snapshot))
+                        }
+                        Err(err) => {
+                            log::error!(target: "window", "Failed to open puzzle {:?}: {}", path, err);
+                            show_error(&window, "Could not open puzzle", &err.to_string());
+                        }
+                    }
+                },
+            );
+        }
+    });
+    window.add_action(&action_open_puzzle);
+
+    let action_export_puzzle = SimpleAction::new("export-puzzle", None);
+    action_export_puzzle.connect_activate({
+        let window = window.clone();
+        let game_state = Rc::clone(&game_state);
+        move |_, _| {
+            let board = game_state.borrow().get_puzzle_board();
+            let dialog = puzzle_file_dialog("Export Puzzle");
+            dialog.set_initial_name(Some(&format!(
+                "{}-{}.{}",
+                board.solution.difficulty.to_string().to_lowercase(),
+                board.solution.seed,
+                PUZZLE_FILE_EXTENSION
+            )));
+            let window = window.clone();
+            dialog.save(
+                Some(window.as_ref()),
+                None::<&gio::Cancellable>,
+                move |result| {
+                    let Some(path) = result.ok().and_then(|file| file.path()) else {
+                        return;
+                    };
+                    if let Err(err) = puzzle_file::export_puzzle(&board, &path) {
+                        log::error!(target: "window", "Failed to export puzzle to {:?}: {}", path, err);
+                        show_error(&window, "Could not export puzzle", &err.to_string());
+                    }
+                },
+            );
+        }
+    });
+    window.add_action(&action_export_puzzle);
+
     // Initialize input translator
     let input_translator = InputTranslator::new(
         game_action_emitter.clone(),
@@ -521,9 +873,8 @@ pub fn build_ui(app: &Application) {
 
     window.connect_close_request(move |_| {
         println!("Destroying window");
-        if !game_state.borrow_mut().get_game_save_state().save() {
-            log::error!(target: "window", "Failed to save game state");
-        }
+        stash_current_game(&game_state, &save_slots);
+        difficulty_sync_subscription.unsubscribe();
         history_controls_ui.borrow_mut().destroy();
         game_state.borrow_mut().destroy();
         game_info_ui.borrow_mut().destroy();
@@ -533,6 +884,7 @@ pub fn build_ui(app: &Application) {
         timer_button.borrow_mut().destroy();
         layout_manager.borrow_mut().destroy();
         seed_dialog.borrow_mut().destroy();
+        race_ui.borrow_mut().destroy();
         settings_menu_ui.borrow_mut().destroy();
         game_controls.borrow_mut().destroy();
         input_translator.borrow_mut().destroy();